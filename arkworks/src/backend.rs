@@ -0,0 +1,220 @@
+//! Proving backends for the mastermind circuits.
+//!
+//! Every backend is an `ark_snark::SNARK`; the only difference between them
+//! from the point of view of this crate is how the proving and verifying keys
+//! are produced. A circuit-specific setup (Groth16) must be rerun whenever the
+//! circuit changes, e.g. for a different NPEGS/SZ or number of rounds, while a
+//! universal setup only depends on a bound on the circuit size and can be
+//! specialized to each circuit without new trusted randomness.
+
+use std::time::{Duration, Instant};
+
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_groth16::Groth16;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use ark_serialize::{CanonicalSerialize, Compress};
+use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+use ark_std::rand::{CryptoRng, RngCore};
+
+/**
+ * A SNARK together with the setup needed to get keys for a circuit.
+ */
+pub trait Backend<F: PrimeField>: SNARK<F> {
+    const NAME: &'static str;
+
+    /// Whether the keys are specialized from a setup shared by all circuits
+    /// up to some size, rather than generated for this circuit only.
+    const UNIVERSAL: bool;
+
+    /// The output of the setup shared by every circuit: the structured
+    /// reference string of a universal setup, nothing for a circuit-specific
+    /// one.
+    type Params;
+
+    /**
+     * Produce the proving and verifying keys for the circuit from the shared
+     * parameters, running the setup of the circuit if it has its own.
+     */
+    fn keygen<C: ConstraintSynthesizer<F>, R: RngCore + CryptoRng>(
+        params: &Self::Params,
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), Self::Error>;
+
+    /**
+     * Turn a proof into a fresh-looking proof of the same statement, which
+     * cannot be linked to the original, e.g. when the codemaker gives the same
+     * response to the same guess in several games. None if the backend has no
+     * such operation, in which case a new proof must be made.
+     */
+    fn rerandomize<R: RngCore + CryptoRng>(
+        vk: &Self::VerifyingKey,
+        proof: &Self::Proof,
+        rng: &mut R,
+    ) -> Option<Self::Proof>;
+}
+
+impl<E: Pairing> Backend<E::ScalarField> for Groth16<E> {
+    const NAME: &'static str = "groth16";
+    const UNIVERSAL: bool = false;
+    type Params = ();
+
+    fn keygen<C: ConstraintSynthesizer<E::ScalarField>, R: RngCore + CryptoRng>(
+        _params: &(),
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), Self::Error> {
+        Self::setup(circuit, rng)
+    }
+
+    /**
+     * See theorem 3 of <https://eprint.iacr.org/2020/811>: the re-randomized
+     * proof is distributed like an honest proof of the same statement.
+     */
+    fn rerandomize<R: RngCore + CryptoRng>(
+        vk: &Self::VerifyingKey,
+        proof: &Self::Proof,
+        rng: &mut R,
+    ) -> Option<Self::Proof> {
+        Some(Self::rerandomize_proof(vk, proof, rng))
+    }
+}
+
+/**
+ * Compute the public inputs of a satisfied circuit by synthesizing it. The
+ * leading constant one, which the verifiers add on their own, is skipped.
+ */
+pub fn public_inputs<F: PrimeField, C: ConstraintSynthesizer<F>>(circuit: C) -> Vec<F> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    let cs = cs.borrow().unwrap();
+    cs.instance_assignment[1..].to_vec()
+}
+
+/**
+ * Costs of proving a circuit with a backend.
+ */
+#[derive(Clone, Debug)]
+pub struct BackendReport {
+    pub backend: &'static str,
+    pub universal: bool,
+    // without the shared setup of a universal backend
+    pub setup_time: Duration,
+    pub prove_time: Duration,
+    pub verify_time: Duration,
+    // compressed sizes, in bytes
+    pub proving_key_size: usize,
+    pub verifying_key_size: usize,
+    pub proof_size: usize,
+    pub verified: bool,
+}
+
+/**
+ * Run setup, prove and verify for the circuit with the backend S, measuring
+ * the time of each step and the size of the keys and the proof. The keys are
+ * derived from the given shared parameters.
+ */
+pub fn measure<F, S, C, R>(
+    params: &S::Params,
+    circuit: &C,
+    rng: &mut R,
+) -> Result<BackendReport, S::Error>
+where
+    F: PrimeField,
+    S: Backend<F>,
+    C: ConstraintSynthesizer<F> + Clone,
+    R: RngCore + CryptoRng,
+{
+    let start = Instant::now();
+    let (pk, vk) = S::keygen(params, circuit.clone(), rng)?;
+    let setup_time = start.elapsed();
+
+    let start = Instant::now();
+    let proof = S::prove(&pk, circuit.clone(), rng)?;
+    let prove_time = start.elapsed();
+
+    let inputs = public_inputs(circuit.clone());
+    let start = Instant::now();
+    let verified = S::verify(&vk, &inputs, &proof)?;
+    let verify_time = start.elapsed();
+
+    Ok(BackendReport {
+        backend: S::NAME,
+        universal: S::UNIVERSAL,
+        setup_time,
+        prove_time,
+        verify_time,
+        proving_key_size: pk.serialized_size(Compress::Yes),
+        verifying_key_size: vk.serialized_size(Compress::Yes),
+        proof_size: proof.serialized_size(Compress::Yes),
+        verified,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        compute_commitment, secret::Secret, GameVariant, InputEncoding, MastermindCircuit,
+    };
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_measure_groth16() {
+        let circuit = MastermindCircuit::<_, 6, 4> {
+            code: [Some(1), Some(2), Some(3), Some(4)],
+            nonce: Some(Fr::from(43u64)),
+            hash: Some(compute_commitment(
+                Fr::from(43u64),
+                &[1, 2, 3, 4],
+                GameVariant::CLASSIC.commitment,
+            )),
+            num_partial_correct: Some(2),
+            num_fully_correct: Some(2),
+            guess: [Some(1), Some(2), Some(4), Some(3)],
+            variant: GameVariant::CLASSIC,
+            encoding: InputEncoding::Bits,
+        };
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let report = measure::<Fr, Groth16<Bn254>, _, _>(&(), &circuit, rng).unwrap();
+        assert!(report.verified);
+        assert!(!report.universal);
+        // A compressed Groth16 proof on BN254 is two G1 points and a G2 point.
+        assert_eq!(report.proof_size, 32 + 64 + 32);
+    }
+
+    #[test]
+    fn test_rerandomize_groth16() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let secret = Secret::<Fr, 4>::new([1, 2, 3, 4], rng);
+        let circuit = MastermindCircuit::<_, 6, 4> {
+            code: secret.code.map(Some),
+            nonce: Some(secret.nonce),
+            hash: Some(secret.commitment(GameVariant::CLASSIC.commitment)),
+            num_partial_correct: Some(2),
+            num_fully_correct: Some(2),
+            guess: [Some(1), Some(2), Some(4), Some(3)],
+            variant: GameVariant::CLASSIC,
+            encoding: InputEncoding::Packed,
+        };
+        let inputs = circuit.public_inputs().unwrap();
+        let (pk, vk) = Groth16::<Bn254>::keygen(&(), circuit.clone(), rng).unwrap();
+        let proof = Groth16::<Bn254>::prove(&pk, circuit, rng).unwrap();
+
+        let bytes = |proof: &<Groth16<Bn254> as SNARK<Fr>>::Proof| {
+            let mut bytes = vec![];
+            proof.serialize_compressed(&mut bytes).unwrap();
+            bytes
+        };
+        let first = Groth16::<Bn254>::rerandomize(&vk, &proof, rng).unwrap();
+        let second = Groth16::<Bn254>::rerandomize(&vk, &proof, rng).unwrap();
+        for rerandomized in [&first, &second] {
+            assert!(Groth16::<Bn254>::verify(&vk, &inputs, rerandomized).unwrap());
+            assert_ne!(bytes(rerandomized), bytes(&proof));
+        }
+        assert_ne!(bytes(&first), bytes(&second));
+    }
+}
@@ -0,0 +1,209 @@
+//! Batch verification of the Groth16 proofs of one game.
+//!
+//! Every response of a game is proven against the same verifying key, so the
+//! proofs can be checked together. Each proof passes when
+//!
+//! ```text
+//! e(A, B) = e(alpha, beta) · e(X, gamma) · e(C, delta)
+//! ```
+//!
+//! with X the combination of the points of the verifying key by the public
+//! inputs. Raising the equation of proof i to a random 128-bit r_i and taking
+//! the product over all the proofs gives a single check
+//!
+//! ```text
+//! Π e(r_i·A_i, B_i) · e(Σ r_i·X_i, -gamma) · e(Σ r_i·C_i, -delta)
+//!     = e(alpha, beta)^(Σ r_i)
+//! ```
+//!
+//! of N + 2 Miller loops and one final exponentiation, instead of N times 3
+//! Miller loops and a final exponentiation. A batch with an invalid proof
+//! passes with probability at most 2^-128. The sum Σ r_i·X_i is a single
+//! multi-scalar multiplication over the points of the verifying key.
+
+use std::time::{Duration, Instant};
+
+use ark_ec::pairing::Pairing;
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, PrimeField, Zero};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof};
+use ark_relations::r1cs::SynthesisError;
+use ark_std::rand::{CryptoRng, Rng, RngCore};
+
+/**
+ * Check all the proofs at once. True if every proof is valid for its public
+ * inputs, up to a probability of 2^-128; an empty batch is valid.
+ */
+pub fn verify_batch<E: Pairing, R: RngCore + CryptoRng>(
+    pvk: &PreparedVerifyingKey<E>,
+    proofs: &[(&[E::ScalarField], &Proof<E>)],
+    rng: &mut R,
+) -> Result<bool, SynthesisError> {
+    if proofs.is_empty() {
+        return Ok(true);
+    }
+    let points = &pvk.vk.gamma_abc_g1;
+
+    // Scalars of the points of the verifying key in Σ r_i·X_i, the first one
+    // being the constant one of every proof.
+    let mut scalars = vec![E::ScalarField::zero(); points.len()];
+    let mut a = Vec::with_capacity(proofs.len());
+    let mut c = E::G1::zero();
+    for (inputs, proof) in proofs {
+        if inputs.len() + 1 != points.len() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+        let r = E::ScalarField::from(rng.gen::<u128>());
+        scalars[0] += r;
+        for (scalar, input) in scalars[1..].iter_mut().zip(inputs.iter()) {
+            *scalar += r * input;
+        }
+        a.push(proof.a * r);
+        c += proof.c * r;
+    }
+    let x = E::G1::msm(points, &scalars).map_err(|_| SynthesisError::MalformedVerifyingKey)?;
+
+    let mut g1 = E::G1::normalize_batch(&a);
+    g1.extend(E::G1::normalize_batch(&[x, c]));
+    let mut g2: Vec<E::G2Prepared> = proofs.iter().map(|(_, proof)| proof.b.into()).collect();
+    g2.push(pvk.gamma_g2_neg_pc.clone());
+    g2.push(pvk.delta_g2_neg_pc.clone());
+
+    let test = E::final_exponentiation(E::multi_miller_loop(g1, g2))
+        .ok_or(SynthesisError::UnexpectedIdentity)?;
+    Ok(test.0 == pvk.alpha_g1_beta_g2.pow(scalars[0].into_bigint()))
+}
+
+/**
+ * The indices of the invalid proofs: none if the batch passes, else found by
+ * verifying every proof on its own.
+ */
+pub fn find_invalid<E: Pairing, R: RngCore + CryptoRng>(
+    pvk: &PreparedVerifyingKey<E>,
+    proofs: &[(&[E::ScalarField], &Proof<E>)],
+    rng: &mut R,
+) -> Result<Vec<usize>, SynthesisError> {
+    if verify_batch(pvk, proofs, rng)? {
+        return Ok(vec![]);
+    }
+    let mut invalid = vec![];
+    for (i, (inputs, proof)) in proofs.iter().enumerate() {
+        if !Groth16::<E>::verify_proof(pvk, proof, inputs)? {
+            invalid.push(i);
+        }
+    }
+    Ok(invalid)
+}
+
+/**
+ * Time to verify the same proofs one by one and as a batch.
+ */
+#[derive(Clone, Debug)]
+pub struct BatchReport {
+    pub num_proofs: usize,
+    pub separate_time: Duration,
+    pub batch_time: Duration,
+    pub verified: bool,
+}
+
+/**
+ * Verify the proofs one by one with `Groth16::verify_proof`, then as a batch,
+ * measuring the time of both.
+ */
+pub fn measure_batch<E: Pairing, R: RngCore + CryptoRng>(
+    pvk: &PreparedVerifyingKey<E>,
+    proofs: &[(&[E::ScalarField], &Proof<E>)],
+    rng: &mut R,
+) -> Result<BatchReport, SynthesisError> {
+    let start = Instant::now();
+    let mut verified = true;
+    for (inputs, proof) in proofs {
+        verified &= Groth16::<E>::verify_proof(pvk, proof, inputs)?;
+    }
+    let separate_time = start.elapsed();
+
+    let start = Instant::now();
+    verified &= verify_batch(pvk, proofs, rng)?;
+    let batch_time = start.elapsed();
+
+    Ok(BatchReport {
+        num_proofs: proofs.len(),
+        separate_time,
+        batch_time,
+        verified,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{secret::Secret, GameVariant, InputEncoding, MastermindCircuit};
+    use ark_bn254::{Bn254, Fr};
+    use ark_ff::One;
+    use ark_groth16::prepare_verifying_key;
+    use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn batch<'a>(
+        inputs: &'a [Vec<Fr>],
+        proofs: &'a [Proof<Bn254>],
+    ) -> Vec<(&'a [Fr], &'a Proof<Bn254>)> {
+        inputs.iter().map(Vec::as_slice).zip(proofs).collect()
+    }
+
+    #[test]
+    fn test_batch() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let variant = GameVariant::CLASSIC;
+        let secret = Secret::<Fr, 4>::new([1, 2, 3, 4], rng);
+        let rounds = [[1, 2, 4, 3], [0, 0, 0, 0], [1, 2, 3, 4]].map(|guess| {
+            let (fully, partial) = variant.score(&secret.code, &guess);
+            MastermindCircuit::<Fr, 6, 4> {
+                code: secret.code.map(Some),
+                nonce: Some(secret.nonce),
+                hash: Some(secret.commitment(variant.commitment)),
+                num_partial_correct: Some(partial),
+                num_fully_correct: Some(fully),
+                guess: guess.map(Some),
+                variant,
+                encoding: InputEncoding::Packed,
+            }
+        });
+
+        let (pk, vk) = Groth16::<Bn254>::setup(rounds[0].clone(), rng).unwrap();
+        let pvk = prepare_verifying_key(&vk);
+        let mut inputs = vec![];
+        let mut proofs = vec![];
+        for round in &rounds {
+            inputs.push(round.public_inputs().unwrap());
+            proofs.push(Groth16::<Bn254>::prove(&pk, round.clone(), rng).unwrap());
+        }
+        assert!(verify_batch(&pvk, &batch(&inputs, &proofs), rng).unwrap());
+        assert!(find_invalid(&pvk, &batch(&inputs, &proofs), rng)
+            .unwrap()
+            .is_empty());
+        assert!(verify_batch::<Bn254, _>(&pvk, &[], rng).unwrap());
+
+        // A response of the second round that was not proven.
+        let mut tampered = inputs.clone();
+        tampered[1][1] += Fr::one();
+        assert!(!verify_batch(&pvk, &batch(&tampered, &proofs), rng).unwrap());
+        assert_eq!(
+            find_invalid(&pvk, &batch(&tampered, &proofs), rng).unwrap(),
+            [1]
+        );
+
+        // Two valid proofs swapped between their inputs.
+        let mut swapped = inputs.clone();
+        swapped.swap(0, 2);
+        assert_eq!(
+            find_invalid(&pvk, &batch(&swapped, &proofs), rng).unwrap(),
+            [0, 2]
+        );
+
+        let mut short = inputs;
+        short[2].pop();
+        assert!(verify_batch(&pvk, &batch(&short, &proofs), rng).is_err());
+    }
+}
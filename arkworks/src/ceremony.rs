@@ -0,0 +1,426 @@
+//! A local, multi-party phase-2 setup ceremony for the Groth16 keys of the
+//! mastermind circuit, in the style of the MPC of Bowe, Gabizon and Miers
+//! (<https://eprint.iacr.org/2017/1050>) used by snarkjs and the Zcash
+//! ceremonies.
+//!
+//! The keys generated by `Groth16::setup` are only as secure as the RNG they
+//! come from: whoever knows its seed can forge proofs. In phase 2 every
+//! participant multiplies the delta of the keys by a secret d and divides the
+//! `h_query` and `l_query` points by it, so forging needs either the phase-1
+//! secrets (tau, alpha and beta, known to whoever ran `initialize`, see below)
+//! or the secrets of all the phase-2 participants. Each contribution comes
+//! with a proof of knowledge of d, bound to the hash of the keys it starts
+//! from, and anyone can check the whole chain of contributions.
+//!
+//! Phase 1 (the powers of tau, alpha and beta) is simulated: `initialize`
+//! draws it at random on the machine running it. A real ceremony would start
+//! from the transcript of a public powers-of-tau ceremony instead.
+//!
+//! A ceremony lives in a directory:
+//!
+//! - `params-<i>.bin`: the proving key after i contributions, `params-0.bin`
+//!   being the initial one;
+//! - `contribution-<i>.bin`: the public part of contribution i;
+//! - `proving_key.bin` and `verifying_key.bin`: the final keys, once checked.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::{ConstraintSynthesizer, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_snark::CircuitSpecificSetupSNARK;
+use ark_std::rand::{CryptoRng, Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+
+/**
+ * The public part of a contribution: the new delta and a proof of knowledge
+ * of the factor it was multiplied by.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Contribution<E: Pairing> {
+    // Hash of the keys the contribution was made to
+    pub transcript: [u8; 32],
+    pub delta_after: E::G1Affine,
+    // A random point s and s·d
+    pub s: E::G1Affine,
+    pub s_delta: E::G1Affine,
+    // r·d, with r the point hashed from the transcript, s and s·d
+    pub r_delta: E::G2Affine,
+}
+
+/**
+ * Initial keys for the circuit, from a simulated phase 1.
+ */
+pub fn initialize<E: Pairing, C: ConstraintSynthesizer<E::ScalarField>, R: RngCore + CryptoRng>(
+    circuit: C,
+    rng: &mut R,
+) -> Result<ProvingKey<E>, SynthesisError> {
+    Ok(Groth16::<E>::setup(circuit, rng)?.0)
+}
+
+/**
+ * SHA-256 of the serialized keys, to which the next contribution is bound.
+ */
+pub fn transcript_hash<E: Pairing>(params: &ProvingKey<E>) -> [u8; 32] {
+    let mut bytes = vec![];
+    params.serialize_uncompressed(&mut bytes).unwrap();
+    Sha256::digest(&bytes).into()
+}
+
+/**
+ * The point r of the proof of knowledge, derived from the transcript and the
+ * points s and s·d so that nobody knows its discrete logarithm.
+ */
+fn hash_to_g2<E: Pairing>(
+    transcript: &[u8; 32],
+    s: &E::G1Affine,
+    s_delta: &E::G1Affine,
+) -> E::G2Affine {
+    let mut bytes = transcript.to_vec();
+    s.serialize_uncompressed(&mut bytes).unwrap();
+    s_delta.serialize_uncompressed(&mut bytes).unwrap();
+    let seed: [u8; 32] = Sha256::digest(&bytes).into();
+    E::G2Affine::rand(&mut ChaCha20Rng::from_seed(seed))
+}
+
+/**
+ * Multiply delta by a secret drawn from the RNG and divide the `h_query` and
+ * `l_query` points by it. The secret is dropped on return.
+ */
+pub fn contribute<E: Pairing, R: RngCore + CryptoRng>(
+    params: &mut ProvingKey<E>,
+    rng: &mut R,
+) -> Contribution<E> {
+    let transcript = transcript_hash(params);
+    let mut d = E::ScalarField::zero();
+    while d.is_zero() {
+        d = E::ScalarField::rand(rng);
+    }
+    let d_inverse = d.inverse().unwrap();
+
+    let s = E::G1Affine::rand(rng);
+    let s_delta = (s * d).into_affine();
+    let r_delta = (hash_to_g2::<E>(&transcript, &s, &s_delta) * d).into_affine();
+
+    params.delta_g1 = (params.delta_g1 * d).into_affine();
+    params.vk.delta_g2 = (params.vk.delta_g2 * d).into_affine();
+    let scale = |points: &[E::G1Affine]| -> Vec<E::G1Affine> {
+        let scaled: Vec<E::G1> = points.iter().map(|p| *p * d_inverse).collect();
+        E::G1::normalize_batch(&scaled)
+    };
+    params.h_query = scale(&params.h_query);
+    params.l_query = scale(&params.l_query);
+
+    Contribution {
+        transcript,
+        delta_after: params.delta_g1,
+        s,
+        s_delta,
+        r_delta,
+    }
+}
+
+/**
+ * Whether e(a1, b2) == e(b1, a2), i.e. the pairs (a1, b1) and (a2, b2) have
+ * the same ratio.
+ */
+fn same_ratio<E: Pairing>(
+    (a1, b1): (E::G1Affine, E::G1Affine),
+    (a2, b2): (E::G2Affine, E::G2Affine),
+) -> bool {
+    E::pairing(a1, b2) == E::pairing(b1, a2)
+}
+
+/**
+ * A random linear combination of the points, so that one pairing checks that
+ * all of them were scaled by the same factor.
+ */
+fn combine<E: Pairing>(points: &[E::G1Affine], coeffs: &[E::ScalarField]) -> E::G1Affine {
+    E::G1::msm_unchecked(points, coeffs).into_affine()
+}
+
+/**
+ * Check that `after` is `before` with one contribution: the proof of knowledge
+ * holds for the transcript of `before`, delta was multiplied by the factor
+ * known to the contributor, `h_query` and `l_query` were divided by it, and
+ * nothing else changed.
+ */
+pub fn verify_contribution<E: Pairing, R: RngCore + CryptoRng>(
+    before: &ProvingKey<E>,
+    after: &ProvingKey<E>,
+    contribution: &Contribution<E>,
+    rng: &mut R,
+) -> bool {
+    let unchanged = before.vk.alpha_g1 == after.vk.alpha_g1
+        && before.vk.beta_g2 == after.vk.beta_g2
+        && before.vk.gamma_g2 == after.vk.gamma_g2
+        && before.vk.gamma_abc_g1 == after.vk.gamma_abc_g1
+        && before.beta_g1 == after.beta_g1
+        && before.a_query == after.a_query
+        && before.b_g1_query == after.b_g1_query
+        && before.b_g2_query == after.b_g2_query
+        && before.h_query.len() == after.h_query.len()
+        && before.l_query.len() == after.l_query.len();
+    if !unchanged
+        || contribution.transcript != transcript_hash(before)
+        || contribution.delta_after != after.delta_g1
+        || after.delta_g1.is_zero()
+        || contribution.s.is_zero()
+    {
+        return false;
+    }
+
+    // Proof of knowledge of d, and delta multiplied by it in G1 and G2. The
+    // generators of the keys are random points rather than the generators of
+    // the curves, so the G2 delta is compared with the one before.
+    let r = hash_to_g2::<E>(
+        &contribution.transcript,
+        &contribution.s,
+        &contribution.s_delta,
+    );
+    if !same_ratio::<E>(
+        (contribution.s, contribution.s_delta),
+        (r, contribution.r_delta),
+    ) || !same_ratio::<E>((before.delta_g1, after.delta_g1), (r, contribution.r_delta))
+        || !same_ratio::<E>(
+            (before.delta_g1, after.delta_g1),
+            (before.vk.delta_g2, after.vk.delta_g2),
+        )
+    {
+        return false;
+    }
+
+    // Every h_i and l_i divided by d: h_i·delta is unchanged.
+    [
+        (&before.h_query, &after.h_query),
+        (&before.l_query, &after.l_query),
+    ]
+    .iter()
+    .all(|(before_query, after_query)| {
+        let coeffs: Vec<E::ScalarField> = (0..before_query.len())
+            .map(|_| E::ScalarField::from(rng.gen::<u128>()))
+            .collect();
+        same_ratio::<E>(
+            (
+                combine::<E>(after_query, &coeffs),
+                combine::<E>(before_query, &coeffs),
+            ),
+            (before.vk.delta_g2, after.vk.delta_g2),
+        )
+    })
+}
+
+fn params_path(dir: &Path, i: usize) -> PathBuf {
+    dir.join(format!("params-{i}.bin"))
+}
+
+fn contribution_path(dir: &Path, i: usize) -> PathBuf {
+    dir.join(format!("contribution-{i}.bin"))
+}
+
+fn read<T: CanonicalDeserialize>(path: &Path) -> io::Result<T> {
+    let reader = BufReader::new(File::open(path)?);
+    T::deserialize_uncompressed(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write<T: CanonicalSerialize>(path: &Path, value: &T) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    value
+        .serialize_uncompressed(&mut writer)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/**
+ * Number of contributions made so far in the directory.
+ */
+pub fn num_contributions(dir: impl AsRef<Path>) -> usize {
+    let dir = dir.as_ref();
+    (1..).take_while(|&i| params_path(dir, i).exists()).count()
+}
+
+/**
+ * Start a ceremony in the directory, writing `params-0.bin`.
+ */
+pub fn init<E: Pairing, C: ConstraintSynthesizer<E::ScalarField>, R: RngCore + CryptoRng>(
+    dir: impl AsRef<Path>,
+    circuit: C,
+    rng: &mut R,
+) -> io::Result<()> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    if params_path(dir, 0).exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "a ceremony was already started in the directory",
+        ));
+    }
+    let params = initialize::<E, _, _>(circuit, rng).map_err(io::Error::other)?;
+    write(&params_path(dir, 0), &params)
+}
+
+/**
+ * Contribute to the latest keys of the ceremony, writing the next
+ * `params-<i>.bin` and `contribution-<i>.bin`. Returns i.
+ */
+pub fn contribute_to<E: Pairing, R: RngCore + CryptoRng>(
+    dir: impl AsRef<Path>,
+    rng: &mut R,
+) -> io::Result<usize> {
+    let dir = dir.as_ref();
+    let i = num_contributions(dir);
+    let mut params: ProvingKey<E> = read(&params_path(dir, i))?;
+    let contribution = contribute(&mut params, rng);
+    write(&contribution_path(dir, i + 1), &contribution)?;
+    write(&params_path(dir, i + 1), &params)?;
+    Ok(i + 1)
+}
+
+/**
+ * Check every contribution of the ceremony in order, and return the final
+ * keys.
+ */
+pub fn verify<E: Pairing, R: RngCore + CryptoRng>(
+    dir: impl AsRef<Path>,
+    rng: &mut R,
+) -> io::Result<ProvingKey<E>> {
+    let dir = dir.as_ref();
+    let mut params: ProvingKey<E> = read(&params_path(dir, 0))?;
+    for i in 1..=num_contributions(dir) {
+        let next: ProvingKey<E> = read(&params_path(dir, i))?;
+        let contribution: Contribution<E> = read(&contribution_path(dir, i))?;
+        if !verify_contribution(&params, &next, &contribution, rng) {
+            return Err(invalid(format!("contribution {i} is invalid")));
+        }
+        params = next;
+    }
+    Ok(params)
+}
+
+/**
+ * Check the ceremony and write its keys to `proving_key.bin` and
+ * `verifying_key.bin`. A ceremony without any contribution is refused.
+ */
+pub fn finalize<E: Pairing, R: RngCore + CryptoRng>(
+    dir: impl AsRef<Path>,
+    rng: &mut R,
+) -> io::Result<(ProvingKey<E>, VerifyingKey<E>)> {
+    let dir = dir.as_ref();
+    if num_contributions(dir) == 0 {
+        return Err(invalid("the ceremony has no contribution".to_string()));
+    }
+    let pk = verify::<E, _>(dir, rng)?;
+    write(&dir.join("proving_key.bin"), &pk)?;
+    write(&dir.join("verifying_key.bin"), &pk.vk)?;
+    Ok((pk.clone(), pk.vk))
+}
+
+/**
+ * Read the keys written by `finalize`.
+ */
+pub fn read_keys<E: Pairing>(
+    dir: impl AsRef<Path>,
+) -> io::Result<(ProvingKey<E>, VerifyingKey<E>)> {
+    let dir = dir.as_ref();
+    Ok((
+        read(&dir.join("proving_key.bin"))?,
+        read(&dir.join("verifying_key.bin"))?,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{secret::Secret, GameVariant, InputEncoding, MastermindCircuit};
+    use ark_bn254::{Bn254, Fr};
+    use ark_snark::SNARK;
+
+    fn game(rng: &mut ChaCha20Rng) -> MastermindCircuit<Fr, 6, 4> {
+        let secret = Secret::<Fr, 4>::new([1, 2, 3, 4], rng);
+        MastermindCircuit {
+            code: secret.code.map(Some),
+            nonce: Some(secret.nonce),
+            hash: Some(secret.commitment(GameVariant::CLASSIC.commitment)),
+            num_partial_correct: Some(2),
+            num_fully_correct: Some(2),
+            guess: [Some(1), Some(2), Some(4), Some(3)],
+            variant: GameVariant::CLASSIC,
+            encoding: InputEncoding::Packed,
+        }
+    }
+
+    #[test]
+    fn test_contributions() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let circuit = game(rng);
+        let initial = initialize::<Bn254, _, _>(circuit.clone(), rng).unwrap();
+
+        let mut first = initial.clone();
+        let first_contribution = contribute(&mut first, rng);
+        assert!(verify_contribution(
+            &initial,
+            &first,
+            &first_contribution,
+            rng
+        ));
+        let mut second = first.clone();
+        let second_contribution = contribute(&mut second, rng);
+        assert!(verify_contribution(
+            &first,
+            &second,
+            &second_contribution,
+            rng
+        ));
+        // Contributions are bound to the keys they were made to.
+        assert!(!verify_contribution(
+            &initial,
+            &second,
+            &second_contribution,
+            rng
+        ));
+
+        // The final keys prove and verify.
+        let proof = Groth16::<Bn254>::prove(&second, circuit.clone(), rng).unwrap();
+        let inputs = circuit.public_inputs().unwrap();
+        assert!(Groth16::<Bn254>::verify(&second.vk, &inputs, &proof).unwrap());
+
+        // Only dividing some of the points by the factor is caught.
+        let mut tampered = second.clone();
+        tampered.l_query[0] = first.l_query[0];
+        assert!(!verify_contribution(
+            &first,
+            &tampered,
+            &second_contribution,
+            rng
+        ));
+
+        // As is a contribution reusing the proof of knowledge of another one.
+        let mut forged = first.clone();
+        let (two, half) = (Fr::from(2u64), Fr::from(2u64).inverse().unwrap());
+        forged.delta_g1 = (forged.delta_g1 * two).into_affine();
+        forged.vk.delta_g2 = (forged.vk.delta_g2 * two).into_affine();
+        for point in forged.h_query.iter_mut().chain(forged.l_query.iter_mut()) {
+            *point = (*point * half).into_affine();
+        }
+        let forged_contribution = Contribution {
+            transcript: transcript_hash(&first),
+            delta_after: forged.delta_g1,
+            ..first_contribution
+        };
+        assert!(!verify_contribution(
+            &first,
+            &forged,
+            &forged_contribution,
+            rng
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+//! A circuit proving that the committed code is legal, so that the codemaker
+//! can prove at the start of the game that the hash they publish is the hash
+//! of a code the codebreaker can find. This is the counterpart of the halo2
+//! `init` example.
+
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::{
+    assert_code_is_valid, fp_input_private, fp_input_public, secret::Secret,
+    uint64_arr_input_private, GameVariant, MastermindCircuit,
+};
+
+/**
+ * Proves that the code behind `hash` is legal under the rules of the variant.
+ * The hash is the only public input.
+ */
+#[derive(Clone)]
+pub struct CodeCommitmentCircuit<F: PrimeField, const NPEGS: usize, const SZ: usize> {
+    // Codemaker information (private)
+    pub code: [Option<u64>; SZ],
+    pub nonce: Option<F>,
+
+    // Codemaker's hash; public, identifies the game
+    pub hash: Option<F>,
+
+    pub variant: GameVariant,
+}
+
+impl<F: PrimeField + Absorb, const NPEGS: usize, const SZ: usize>
+    CodeCommitmentCircuit<F, NPEGS, SZ>
+{
+    /**
+     * Commit to the secret code, under the hash scheme of the variant.
+     */
+    pub fn new(secret: &Secret<F, SZ>, variant: GameVariant) -> Self {
+        CodeCommitmentCircuit {
+            code: secret.code.map(Some),
+            nonce: Some(secret.nonce),
+            hash: Some(secret.commitment(variant.commitment)),
+            variant,
+        }
+    }
+
+    /**
+     * The public inputs a verifier passes to the SNARK, or None if the hash is
+     * missing.
+     */
+    pub fn public_inputs(&self) -> Option<Vec<F>> {
+        Some(vec![self.hash?])
+    }
+}
+
+impl<F: PrimeField, const NPEGS: usize, const SZ: usize> From<&MastermindCircuit<F, NPEGS, SZ>>
+    for CodeCommitmentCircuit<F, NPEGS, SZ>
+{
+    fn from(game: &MastermindCircuit<F, NPEGS, SZ>) -> Self {
+        CodeCommitmentCircuit {
+            code: game.code,
+            nonce: game.nonce,
+            hash: game.hash,
+            variant: game.variant,
+        }
+    }
+}
+
+/**
+ * Whether the public inputs of a mastermind proof are for the game whose code
+ * was committed to by a code commitment proof, i.e. whether both proofs are
+ * about the same hash. The hash is the first public input of both circuits,
+ * whatever the input encoding.
+ */
+pub fn is_same_game<F: PrimeField>(commitment_inputs: &[F], game_inputs: &[F]) -> bool {
+    matches!((commitment_inputs.first(), game_inputs.first()), (Some(a), Some(b)) if a == b)
+}
+
+impl<F: PrimeField + Absorb, const NPEGS: usize, const SZ: usize> ConstraintSynthesizer<F>
+    for CodeCommitmentCircuit<F, NPEGS, SZ>
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let code = uint64_arr_input_private(&cs, &self.code)?;
+        let nonce = fp_input_private(&cs, &self.nonce)?;
+        let hash = fp_input_public(&cs, &self.hash)?;
+
+        assert_code_is_valid::<_, NPEGS>(cs, &code, &nonce, &hash, &self.variant)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::InputEncoding;
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn commit(code: [u64; 4], variant: GameVariant) -> CodeCommitmentCircuit<Fr, 6, 4> {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        CodeCommitmentCircuit::new(&Secret::new(code, rng), variant)
+    }
+
+    fn is_satisfied(circuit: CodeCommitmentCircuit<Fr, 6, 4>) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_legal_code() {
+        assert!(is_satisfied(commit([1, 2, 3, 4], GameVariant::CLASSIC)));
+        assert!(is_satisfied(commit(
+            [1, 2, 3, 4],
+            GameVariant::BULLS_AND_COWS
+        )));
+    }
+
+    #[test]
+    fn test_illegal_code() {
+        // A color out of range, even with a matching hash.
+        assert!(!is_satisfied(commit([1, 2, 3, 6], GameVariant::CLASSIC)));
+        // Duplicate colors are not allowed in Bulls and Cows.
+        assert!(!is_satisfied(commit(
+            [1, 1, 3, 4],
+            GameVariant::BULLS_AND_COWS
+        )));
+        // A hash of another code.
+        let mut circuit = commit([1, 2, 3, 4], GameVariant::CLASSIC);
+        circuit.code[0] = Some(0);
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_same_game() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let secret = Secret::<Fr, 4>::new([1, 2, 3, 4], rng);
+        let commitment = CodeCommitmentCircuit::<Fr, 6, 4>::new(&secret, GameVariant::CLASSIC);
+        let game = MastermindCircuit::<Fr, 6, 4> {
+            code: commitment.code,
+            nonce: commitment.nonce,
+            hash: commitment.hash,
+            num_partial_correct: Some(2),
+            num_fully_correct: Some(2),
+            guess: [Some(1), Some(2), Some(4), Some(3)],
+            variant: GameVariant::CLASSIC,
+            encoding: InputEncoding::Packed,
+        };
+
+        let (pk, vk) = Groth16::<Bn254>::setup(commitment.clone(), rng).unwrap();
+        let proof = Groth16::<Bn254>::prove(&pk, commitment.clone(), rng).unwrap();
+        let commitment_inputs = commitment.public_inputs().unwrap();
+        assert!(Groth16::<Bn254>::verify(&vk, &commitment_inputs, &proof).unwrap());
+
+        let game_inputs = game.public_inputs().unwrap();
+        assert!(is_same_game(&commitment_inputs, &game_inputs));
+        let game = CodeCommitmentCircuit::from(&game);
+        assert_eq!(game.public_inputs().unwrap(), commitment_inputs);
+
+        // The same code under another nonce is another game.
+        let other = Secret::<Fr, 4>::new([1, 2, 3, 4], rng);
+        let other = CodeCommitmentCircuit::<Fr, 6, 4>::new(&other, GameVariant::CLASSIC);
+        assert!(!is_same_game(&other.public_inputs().unwrap(), &game_inputs));
+    }
+}
@@ -0,0 +1,169 @@
+//! A circuit for the codebreaker: prove knowledge of a code consistent with
+//! every response received so far without revealing it, e.g. to claim victory
+//! in a sealed-bid round. The code is published only as a commitment, which
+//! can be opened later.
+
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::{fp::FpVar, FieldVar},
+};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::fp_input_public;
+use crate::history::{ConsistencyCircuit, Round};
+use crate::GameVariant;
+
+/**
+ * The first public input of `DeductionCircuit`. The circuit is otherwise
+ * `ConsistencyCircuit`, so without it the codemaker's end-of-game consistency
+ * proof, whose hash is the commitment of the game, would also verify as a
+ * deduction of the codebreaker.
+ */
+pub fn deduction_tag<F: PrimeField>() -> F {
+    F::from_le_bytes_mod_order(b"mastermind deduction")
+}
+
+/**
+ * Proves that the codebreaker knows a legal code which is consistent with the
+ * public history of the game, and that `commitment` is the hash of that code
+ * under the codebreaker's own nonce.
+ *
+ * These are the constraints of `ConsistencyCircuit`, with the candidate in
+ * place of the codemaker's code and the codebreaker's commitment in place of
+ * the hash of the game, behind the public input `deduction_tag`: the public
+ * inputs are the tag, the commitment, then the history.
+ */
+#[derive(Clone)]
+pub struct DeductionCircuit<F: PrimeField, const NPEGS: usize, const SZ: usize> {
+    // Codebreaker information (private)
+    pub candidate: [Option<u64>; SZ],
+    pub nonce: Option<F>,
+
+    // Public information
+    // - commitment to the candidate code, computed like the codemaker's hash
+    pub commitment: Option<F>,
+    // - the guesses and responses of the game so far
+    pub history: Vec<Round<SZ>>,
+
+    pub variant: GameVariant,
+}
+
+impl<F: PrimeField, const NPEGS: usize, const SZ: usize> From<DeductionCircuit<F, NPEGS, SZ>>
+    for ConsistencyCircuit<F, NPEGS, SZ>
+{
+    fn from(circuit: DeductionCircuit<F, NPEGS, SZ>) -> Self {
+        ConsistencyCircuit {
+            code: circuit.candidate,
+            nonce: circuit.nonce,
+            hash: circuit.commitment,
+            history: circuit.history,
+            variant: circuit.variant,
+        }
+    }
+}
+
+impl<F: PrimeField + Absorb, const NPEGS: usize, const SZ: usize> ConstraintSynthesizer<F>
+    for DeductionCircuit<F, NPEGS, SZ>
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let tag = fp_input_public(&cs, &Some(deduction_tag()))?;
+        tag.enforce_equal(&FpVar::constant(deduction_tag()))?;
+        ConsistencyCircuit::from(self).generate_constraints(cs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compute_commitment;
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn history() -> Vec<Round<4>> {
+        // The codemaker's code is [1, 2, 3, 4].
+        vec![
+            Round::new([0, 0, 1, 1], 0, 1),
+            Round::new([1, 2, 4, 3], 2, 2),
+            Round::new([1, 2, 3, 5], 3, 0),
+        ]
+    }
+
+    fn circuit(candidate: [u64; 4], commitment: Option<Fr>) -> DeductionCircuit<Fr, 6, 4> {
+        let nonce = Fr::from(7u64);
+        DeductionCircuit {
+            candidate: candidate.map(Some),
+            nonce: Some(nonce),
+            commitment: commitment.or(Some(compute_commitment(
+                nonce,
+                &candidate,
+                GameVariant::CLASSIC.commitment,
+            ))),
+            history: history(),
+            variant: GameVariant::CLASSIC,
+        }
+    }
+
+    fn is_satisfied(candidate: [u64; 4], commitment: Option<Fr>) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit(candidate, commitment)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_consistent_candidate() {
+        assert!(is_satisfied([1, 2, 3, 4], None));
+    }
+
+    #[test]
+    fn test_inconsistent_candidate() {
+        // [1, 2, 3, 0] would have scored 3 black pegs and 0 white pegs on the
+        // last round too, but not 0 black pegs and 1 white peg on the first one.
+        assert!(!is_satisfied([1, 2, 3, 0], None));
+        assert!(!is_satisfied([4, 3, 2, 1], None));
+    }
+
+    #[test]
+    fn test_wrong_commitment() {
+        assert!(!is_satisfied([1, 2, 3, 4], Some(Fr::from(0u64))));
+    }
+
+    #[test]
+    fn test_domain_separation() {
+        // The codemaker proves at the end of the game that their code is
+        // consistent with the history, with the hash of the game as commitment.
+        // The keys are only generated for the first round, to keep it fast.
+        let deduction = DeductionCircuit {
+            history: history()[..1].to_vec(),
+            ..circuit([1, 2, 3, 4], None)
+        };
+        let consistency = ConsistencyCircuit::from(deduction.clone());
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let (pk, consistency_vk) = Groth16::<Bn254>::setup(consistency.clone(), rng).unwrap();
+        let proof = Groth16::<Bn254>::prove(&pk, consistency, rng).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        deduction.clone().generate_constraints(cs.clone()).unwrap();
+        let inputs = cs.borrow().unwrap().instance_assignment[1..].to_vec();
+        assert_eq!(inputs[0], deduction_tag());
+        assert!(Groth16::<Bn254>::verify(&consistency_vk, &inputs[1..], &proof).unwrap());
+
+        // That proof is no deduction, under the inputs of either circuit, even
+        // with keys from the same randomness.
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let (_, deduction_vk) = Groth16::<Bn254>::setup(deduction, rng).unwrap();
+        for inputs in [&inputs[..], &inputs[1..]] {
+            assert!(!matches!(
+                Groth16::<Bn254>::verify(&deduction_vk, inputs, &proof),
+                Ok(true)
+            ));
+        }
+    }
+}
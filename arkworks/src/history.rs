@@ -0,0 +1,147 @@
+//! A circuit proving that the committed code is consistent with the whole
+//! history of a game, so that a referee can settle a dispute with a single
+//! proof at the end of the game instead of re-verifying every response.
+
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::uint64::UInt64;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::{
+    assert_code_is_valid, assert_guess_is_valid, assert_response_is_valid, fp_input_private,
+    fp_input_public, uint64_arr_input_private, uint64_arr_input_public, uint64_input_public,
+    GameVariant,
+};
+
+/**
+ * A single round of the game: the codebreaker's guess and the codemaker's
+ * response. All of it is public.
+ */
+#[derive(Clone, Debug)]
+pub struct Round<const SZ: usize> {
+    pub guess: [Option<u64>; SZ],
+    pub num_partial_correct: Option<u64>,
+    pub num_fully_correct: Option<u64>,
+}
+
+impl<const SZ: usize> Round<SZ> {
+    pub fn new(guess: [u64; SZ], num_fully_correct: u64, num_partial_correct: u64) -> Self {
+        Round {
+            guess: guess.map(Some),
+            num_partial_correct: Some(num_partial_correct),
+            num_fully_correct: Some(num_fully_correct),
+        }
+    }
+}
+
+/**
+ * Proves that the code behind `hash` is legal and that every response in the
+ * history is the correct response to its guess.
+ *
+ * The number of rounds is part of the shape of the circuit, so the setup must
+ * be run with a history of the same length as the one being proven.
+ */
+#[derive(Clone)]
+pub struct ConsistencyCircuit<F: PrimeField, const NPEGS: usize, const SZ: usize> {
+    // Codemaker information (private)
+    pub code: [Option<u64>; SZ],
+    pub nonce: Option<F>,
+
+    // Public game information
+    pub hash: Option<F>,
+    pub history: Vec<Round<SZ>>,
+
+    pub variant: GameVariant,
+}
+
+impl<F: PrimeField + Absorb, const NPEGS: usize, const SZ: usize> ConstraintSynthesizer<F>
+    for ConsistencyCircuit<F, NPEGS, SZ>
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let code = uint64_arr_input_private(&cs, &self.code)?;
+        let nonce = fp_input_private(&cs, &self.nonce)?;
+        let hash = fp_input_public(&cs, &self.hash)?;
+
+        assert_code_is_valid::<_, NPEGS>(cs.clone(), &code, &nonce, &hash, &self.variant)?;
+        assert_consistent_with_history::<_, NPEGS, SZ>(&cs, &code, &self.history, &self.variant)
+    }
+}
+
+/**
+ * Allocate the rounds of the history as public inputs and assert that every
+ * guess is legal and every response is the correct response to its guess for
+ * the given code.
+ */
+pub(crate) fn assert_consistent_with_history<F: PrimeField, const NPEGS: usize, const SZ: usize>(
+    cs: &ConstraintSystemRef<F>,
+    code: &[UInt64<F>],
+    history: &[Round<SZ>],
+    variant: &GameVariant,
+) -> Result<(), SynthesisError> {
+    for round in history {
+        let guess = uint64_arr_input_public(cs, &round.guess)?;
+        let num_partial_correct = uint64_input_public(cs, &round.num_partial_correct)?;
+        let num_fully_correct = uint64_input_public(cs, &round.num_fully_correct)?;
+
+        assert_guess_is_valid::<_, NPEGS>(&guess, variant)?;
+        assert_response_is_valid::<_, NPEGS>(
+            code,
+            &guess,
+            &num_partial_correct,
+            &num_fully_correct,
+            variant,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compute_commitment;
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn circuit(code: [u64; 4], history: Vec<Round<4>>) -> ConsistencyCircuit<Fr, 6, 4> {
+        let nonce = Fr::from(42u64);
+        ConsistencyCircuit {
+            code: code.map(Some),
+            nonce: Some(nonce),
+            hash: Some(compute_commitment(
+                nonce,
+                &code,
+                GameVariant::CLASSIC.commitment,
+            )),
+            history,
+            variant: GameVariant::CLASSIC,
+        }
+    }
+
+    fn is_satisfied(circuit: ConsistencyCircuit<Fr, 6, 4>) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_consistent_history() {
+        let history = vec![
+            Round::new([0, 0, 1, 1], 0, 1),
+            Round::new([1, 2, 4, 3], 2, 2),
+            Round::new([1, 2, 3, 4], 4, 0),
+        ];
+        assert!(is_satisfied(circuit([1, 2, 3, 4], history)));
+        assert!(is_satisfied(circuit([1, 2, 3, 4], vec![])));
+    }
+
+    #[test]
+    fn test_inconsistent_history() {
+        // The second response contradicts the committed code.
+        let history = vec![
+            Round::new([0, 0, 1, 1], 0, 1),
+            Round::new([1, 2, 4, 3], 1, 2),
+            Round::new([1, 2, 3, 4], 4, 0),
+        ];
+        assert!(!is_satisfied(circuit([1, 2, 3, 4], history)));
+    }
+}
@@ -1,17 +1,42 @@
-use ark_bn254::Fr;
 use ark_crypto_primitives::sponge::poseidon::constraints::*;
-use ark_crypto_primitives::sponge::{constraints::CryptographicSpongeVar, poseidon::*};
+use ark_crypto_primitives::sponge::{
+    constraints::CryptographicSpongeVar, poseidon::*, Absorb, CryptographicSponge,
+};
 use ark_ff::fields::PrimeField;
 use ark_r1cs_std::cmp::*;
+use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
 use ark_r1cs_std::uint64::UInt64;
+use ark_relations::ns;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use std::ops::Range;
+
+pub mod backend;
+pub mod batch;
+pub mod ceremony;
+pub mod commitment;
+pub mod deduction;
+pub mod history;
+pub mod marlin;
+pub mod poseidon;
+pub mod r1cs_file;
+pub mod secret;
+pub mod smt;
+pub mod snarkjs;
+pub mod solidity;
+pub mod stats;
+pub mod test_vectors;
+pub mod underconstrained;
+pub mod variant;
+pub use poseidon::HashScheme;
+pub use variant::{GameVariant, Scoring};
 
 /**
  * Create a PoseidonConfig. For our purposes, the exact config doesn't matter
- * too much, so long as it produces a valid hash.
+ * too much, so long as it produces a valid hash. The round constants and MDS
+ * matrix are generated for the field F, so each field gets its own parameters.
  */
-pub fn get_poseidon_config() -> PoseidonConfig<Fr> {
+pub fn get_poseidon_config<F: PrimeField>() -> PoseidonConfig<F> {
     // (rate, alpha, full_rounds, partial_rounds, skip_matrices)
     // PoseidonDefaultConfigEntry::new(2, 17, 8, 31, 0),
     // Adapted from https://github.com/PayneJoe/PNova/blob/main/src/provider/bn254.rs#L2
@@ -22,14 +47,14 @@ pub fn get_poseidon_config() -> PoseidonConfig<Fr> {
     let skip_matrices = 0;
     let capacity = 1;
 
-    let (ark, mds) = find_poseidon_ark_and_mds::<Fr>(
-        Fr::MODULUS_BIT_SIZE as u64,
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+        F::MODULUS_BIT_SIZE as u64,
         rate,
         full_rounds as u64,
         partial_rounds as u64,
         skip_matrices as u64,
     );
-    let config = PoseidonConfig::<Fr> {
+    PoseidonConfig::<F> {
         full_rounds,
         partial_rounds,
         alpha,
@@ -37,8 +62,58 @@ pub fn get_poseidon_config() -> PoseidonConfig<Fr> {
         mds,
         rate,
         capacity,
-    };
-    config
+    }
+}
+
+/**
+ * Compute the Poseidon hash of the inputs natively, truncated to 64 bits in the
+ * same way as the hash computed in the circuit. This is used to compute the
+ * public hash of a game from the nonce and the code.
+ */
+pub fn compute_poseidon_hash<F: PrimeField + Absorb>(inputs: &[F]) -> u64 {
+    let params = get_poseidon_config::<F>();
+
+    let mut sponge = PoseidonSponge::<F>::new(&params);
+    for i in inputs {
+        sponge.absorb(i);
+    }
+
+    // Finalize the hash and get the output
+    let hash = sponge.squeeze_bytes(8);
+
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash);
+
+    // Convert to u64
+    u64::from_le_bytes(bytes)
+}
+
+/**
+ * Compute the commitment to the code under the nonce, i.e. the hash of the
+ * nonce followed by the code, with the given hash scheme.
+ */
+pub fn compute_commitment<F: PrimeField + Absorb>(nonce: F, code: &[u64], scheme: HashScheme) -> F {
+    let mut inputs = vec![nonce];
+    inputs.extend(code.iter().map(|&peg| F::from(peg)));
+    match scheme {
+        HashScheme::Circomlib => poseidon::circomlib_hash(&inputs),
+        HashScheme::Legacy => F::from(compute_poseidon_hash::<F>(&inputs)),
+    }
+}
+
+/**
+ * How the public data of a game (the response and the guess) is exposed.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InputEncoding {
+    /// Every value is a UInt64 public input, i.e. 64 public inputs each.
+    #[default]
+    Bits,
+    /// The guess and the response are packed into a single public input and
+    /// unpacked in the circuit, see `encode_packed_public_inputs`. The verifier
+    /// does one scalar multiplication per public input, so this makes
+    /// verification much cheaper.
+    Packed,
 }
 
 /**
@@ -48,15 +123,16 @@ pub fn get_poseidon_config() -> PoseidonConfig<Fr> {
  * SZ: Size of the code (use 4 for a standard game)
  */
 #[derive(Clone)]
-pub struct MastermindCircuit<const NPEGS: usize, const SZ: usize> {
+pub struct MastermindCircuit<F: PrimeField, const NPEGS: usize, const SZ: usize> {
     // Codemaker infomation:
     // - codemaker code, which must be private
     pub code: [Option<u64>; SZ],
-    // - codemaker's nonce; private, used to protect against dictionary attacks against the code
-    pub nonce: Option<u64>,
+    // - codemaker's nonce; private, used to protect against dictionary attacks against the code.
+    //   It must be a uniformly random field element, see `secret::Secret`.
+    pub nonce: Option<F>,
 
     // - codemaker's hash; public, identifies the current game
-    pub hash: Option<u64>,
+    pub hash: Option<F>,
     // - codemaker's response; public
     pub num_partial_correct: Option<u64>,
     pub num_fully_correct: Option<u64>,
@@ -64,55 +140,276 @@ pub struct MastermindCircuit<const NPEGS: usize, const SZ: usize> {
     // Codebreaker input (public):
     // - codebreaker guess (same size as the code)
     pub guess: [Option<u64>; SZ],
+
+    // Rules of the game; these change the shape of the circuit, so they must be
+    // the same at setup and proving time.
+    pub variant: GameVariant,
+    // How the public data is exposed; this changes the shape of the circuit too.
+    pub encoding: InputEncoding,
+}
+
+impl<F: PrimeField, const NPEGS: usize, const SZ: usize> MastermindCircuit<F, NPEGS, SZ> {
+    /**
+     * Number of witnesses which are private inputs: the 64 bits of each code
+     * peg and the nonce, the first witnesses allocated.
+     */
+    pub const NUM_PRIVATE_INPUTS: usize = 64 * SZ + 1;
+
+    /**
+     * The public inputs a verifier passes to the SNARK for this game, or None
+     * if a public value is missing. See `encode_public_inputs` and
+     * `encode_packed_public_inputs`.
+     */
+    pub fn public_inputs(&self) -> Option<Vec<F>> {
+        let guess = self.guess.iter().copied().collect::<Option<Vec<_>>>()?;
+        let (hash, fully, partial) = (
+            self.hash?,
+            self.num_fully_correct?,
+            self.num_partial_correct?,
+        );
+        Some(match self.encoding {
+            InputEncoding::Bits => encode_public_inputs(hash, &guess, fully, partial),
+            InputEncoding::Packed => encode_packed_public_inputs(
+                hash,
+                &guess,
+                fully,
+                partial,
+                self.variant.num_colors::<NPEGS>(),
+            ),
+        })
+    }
+}
+
+/**
+ * Encode the public data of a game into the public inputs of the mastermind
+ * circuit, in the order they are allocated: the hash, the number of partially
+ * correct pegs, the number of fully correct pegs, then the guess. The hash is
+ * a single field element, while every u64 is allocated with UInt64::new_input
+ * and so becomes 64 public inputs, one per bit, least significant bit first.
+ */
+pub fn encode_public_inputs<F: PrimeField>(
+    hash: F,
+    guess: &[u64],
+    num_fully_correct: u64,
+    num_partial_correct: u64,
+) -> Vec<F> {
+    let mut inputs = vec![hash];
+    for value in [num_partial_correct, num_fully_correct].iter().chain(guess) {
+        inputs.extend((0..BITS_PER_VALUE).map(|i| F::from((value >> i) & 1)));
+    }
+    inputs
+}
+
+/// Number of public inputs of a u64 in the bits encoding, one per bit.
+const BITS_PER_VALUE: usize = 64;
+
+/**
+ * The positions, among the public inputs of `encode_public_inputs`, of the
+ * bits of the number of partially correct pegs and of the number of fully
+ * correct pegs.
+ */
+pub fn response_public_inputs() -> (Range<usize>, Range<usize>) {
+    let partial = 1..1 + BITS_PER_VALUE;
+    let fully = partial.end..partial.end + BITS_PER_VALUE;
+    (partial, fully)
+}
+
+/**
+ * Weights of the values packed into a single public input: the guess pegs are
+ * the digits in base num_colors, followed by the number of fully correct pegs
+ * and the number of partially correct pegs as digits in base SZ + 1. Every
+ * value is constrained to be less than its base by the rest of the circuit,
+ * so the packing is unique.
+ */
+fn packing_weights<F: PrimeField>(code_size: usize, num_colors: u64) -> Vec<F> {
+    let mut weights = Vec::with_capacity(code_size + 2);
+    let mut weight = F::one();
+    for _ in 0..code_size {
+        weights.push(weight);
+        weight *= F::from(num_colors);
+    }
+    for _ in 0..2 {
+        weights.push(weight);
+        weight *= F::from(code_size as u64 + 1);
+    }
+    weights
+}
+
+/**
+ * Encode the public data of a game into the public inputs of the mastermind
+ * circuit in packed mode: the hash, then the guess and the response packed
+ * into a single field element.
+ */
+pub fn encode_packed_public_inputs<F: PrimeField>(
+    hash: F,
+    guess: &[u64],
+    num_fully_correct: u64,
+    num_partial_correct: u64,
+    num_colors: u64,
+) -> Vec<F> {
+    let weights = packing_weights::<F>(guess.len(), num_colors);
+    let packed = guess
+        .iter()
+        .chain(&[num_fully_correct, num_partial_correct])
+        .zip(weights)
+        .map(|(&value, weight)| weight * F::from(value))
+        .sum();
+    vec![hash, packed]
+}
+
+/**
+ * Allocate a private input, created via "new witness".
+ */
+pub(crate) fn uint64_input_private<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    opt: &Option<u64>,
+) -> Result<UInt64<F>, SynthesisError> {
+    UInt64::<F>::new_witness(cs.clone(), || opt.ok_or(SynthesisError::AssignmentMissing))
+}
+
+pub(crate) fn uint64_arr_input_private<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    arr: &[Option<u64>],
+) -> Result<Vec<UInt64<F>>, SynthesisError> {
+    arr.iter()
+        .map(|opt| uint64_input_private(cs, opt))
+        .collect()
+}
+
+/**
+ * Allocate a field element as a private input.
+ */
+pub(crate) fn fp_input_private<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    opt: &Option<F>,
+) -> Result<FpVar<F>, SynthesisError> {
+    FpVar::<F>::new_witness(cs.clone(), || opt.ok_or(SynthesisError::AssignmentMissing))
+}
+
+/**
+ * Allocate a field element as a public input.
+ */
+pub(crate) fn fp_input_public<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    opt: &Option<F>,
+) -> Result<FpVar<F>, SynthesisError> {
+    FpVar::<F>::new_input(cs.clone(), || opt.ok_or(SynthesisError::AssignmentMissing))
+}
+
+/**
+ * Allocate a public input, created via "new input".
+ */
+pub(crate) fn uint64_input_public<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    opt: &Option<u64>,
+) -> Result<UInt64<F>, SynthesisError> {
+    UInt64::<F>::new_input(cs.clone(), || opt.ok_or(SynthesisError::AssignmentMissing))
+}
+
+pub(crate) fn uint64_arr_input_public<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    arr: &[Option<u64>],
+) -> Result<Vec<UInt64<F>>, SynthesisError> {
+    arr.iter().map(|opt| uint64_input_public(cs, opt)).collect()
 }
 
 /**
  * Asserts that the pegs are legal by ensuring they are within the NPEGS bound:
- * in other words, check if each peg is a valid "color".
+ * in other words, check if each peg is a valid "color". If the variant allows
+ * blanks, the blank (encoded as NPEGS) is legal too.
  */
-fn assert_pegs_are_legal<const NPEGS: usize>(pegs: &Vec<UInt64<Fr>>) -> Result<(), SynthesisError> {
-    let npegs_const = UInt64::<Fr>::constant(NPEGS.try_into().unwrap());
-    let zero = UInt64::<Fr>::constant(0);
+pub(crate) fn assert_pegs_are_legal<F: PrimeField, const NPEGS: usize>(
+    pegs: &[UInt64<F>],
+    variant: &GameVariant,
+) -> Result<(), SynthesisError> {
+    let _ns = ns!(pegs.cs(), "legal");
+    let npegs_const = UInt64::<F>::constant(variant.num_colors::<NPEGS>());
+    let zero = UInt64::<F>::constant(0);
 
     for peg in pegs {
         let upper = peg.is_lt(&npegs_const)?;
         let lower = zero.is_le(peg)?;
 
-        upper.enforce_equal(&Boolean::<Fr>::TRUE)?;
-        lower.enforce_equal(&Boolean::<Fr>::TRUE)?;
+        upper.enforce_equal(&Boolean::<F>::TRUE)?;
+        lower.enforce_equal(&Boolean::<F>::TRUE)?;
     }
 
     Ok(())
 }
 
 /**
- * Assert that the given code is valid. This checks that the pegs are legal and
- * that the provided hash is equal to the hash of the nonce and code pegs.
+ * Asserts that no two pegs have the same color, using a pairwise inequality
+ * constraint for every pair of pegs.
  */
-fn assert_code_is_valid<const NPEGS: usize>(
-    cs: ConstraintSystemRef<Fr>,
-    code: &Vec<UInt64<Fr>>,
-    nonce: &UInt64<Fr>,
-    hash: &UInt64<Fr>,
+pub(crate) fn assert_pegs_are_distinct<F: PrimeField>(
+    pegs: &[UInt64<F>],
 ) -> Result<(), SynthesisError> {
-    // The code must be a valid assignment.
-    assert_pegs_are_legal::<NPEGS>(code)?;
+    let _ns = ns!(pegs.cs(), "distinct");
+    for i in 0..pegs.len() {
+        for j in (i + 1)..pegs.len() {
+            pegs[i]
+                .is_neq(&pegs[j])?
+                .enforce_equal(&Boolean::<F>::TRUE)?;
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Assert that the given guess is valid: the pegs are legal, and distinct if the
+ * variant requires it.
+ */
+pub(crate) fn assert_guess_is_valid<F: PrimeField, const NPEGS: usize>(
+    guess: &[UInt64<F>],
+    variant: &GameVariant,
+) -> Result<(), SynthesisError> {
+    assert_pegs_are_legal::<_, NPEGS>(guess, variant)?;
+    if variant.distinct_guess() {
+        assert_pegs_are_distinct(guess)?;
+    }
+    Ok(())
+}
 
-    let config = get_poseidon_config();
-    let mut sponge = PoseidonSpongeVar::<Fr>::new(cs, &config);
+/**
+ * Assert that the given code is valid. This checks that the pegs are legal
+ * (and distinct, if the variant requires it) and that the provided hash is
+ * equal to the hash of the nonce and code pegs, under the hash scheme of the
+ * variant.
+ */
+pub(crate) fn assert_code_is_valid<F: PrimeField + Absorb, const NPEGS: usize>(
+    cs: ConstraintSystemRef<F>,
+    code: &[UInt64<F>],
+    nonce: &FpVar<F>,
+    hash: &FpVar<F>,
+    variant: &GameVariant,
+) -> Result<(), SynthesisError> {
+    // The code must be a valid assignment.
+    assert_pegs_are_legal::<_, NPEGS>(code, variant)?;
+    if variant.distinct_code() {
+        assert_pegs_are_distinct(code)?;
+    }
 
-    let mut sponge_vals = vec![nonce.to_fp()?];
+    let _ns = ns!(cs, "commitment");
+    let mut inputs = vec![nonce.clone()];
     for peg in code {
-        sponge_vals.push(peg.to_fp()?);
+        inputs.push(peg.to_fp()?);
     }
-    sponge.absorb(&sponge_vals)?;
 
-    let computed_hash = sponge.squeeze_field_elements(1)?;
-    assert_eq!(computed_hash.len(), 1);
-    let hash_bits = computed_hash[0].to_bits_le()?;
-    let computed_hash_64 = UInt64::<Fr>::from_bits_le(&hash_bits[0..64]);
+    let computed_hash = match variant.commitment {
+        HashScheme::Circomlib => poseidon::circomlib_hash_var(&inputs)?,
+        HashScheme::Legacy => {
+            let config = get_poseidon_config::<F>();
+            let mut sponge = PoseidonSpongeVar::<F>::new(cs, &config);
+            sponge.absorb(&inputs)?;
 
-    computed_hash_64.enforce_equal(hash)?;
+            let squeezed = sponge.squeeze_field_elements(1)?;
+            assert_eq!(squeezed.len(), 1);
+            let hash_bits = squeezed[0].to_bits_le()?;
+            UInt64::<F>::from_bits_le(&hash_bits[0..64]).to_fp()?
+        }
+    };
+
+    computed_hash.enforce_equal(hash)?;
 
     Ok(())
 }
@@ -122,16 +419,16 @@ fn assert_code_is_valid<const NPEGS: usize>(
  * The number of fully correct guesses ("black pegs") is:
  *      sum code[i] == guess[i] for i in range 0..number of pegs
  */
-fn count_correct_guesses(
-    code: &Vec<UInt64<Fr>>,
-    guess: &Vec<UInt64<Fr>>,
-) -> Result<UInt64<Fr>, SynthesisError> {
-    let mut sum_fully_correct = UInt64::<Fr>::constant(0);
+pub(crate) fn count_correct_guesses<F: PrimeField>(
+    code: &[UInt64<F>],
+    guess: &[UInt64<F>],
+) -> Result<UInt64<F>, SynthesisError> {
+    let mut sum_fully_correct = UInt64::<F>::constant(0);
     for i in 0..code.len() {
         let is_correct = code[i].is_eq(&guess[i])?;
-        // let is_correct_64 = UInt64::<Fr>::from_bits_le(&vec![is_correct]);
+        // let is_correct_64 = UInt64::<F>::from_bits_le(&vec![is_correct]);
         let is_correct_64 =
-            is_correct.select(&UInt64::<Fr>::constant(1), &UInt64::<Fr>::constant(0))?;
+            is_correct.select(&UInt64::<F>::constant(1), &UInt64::<F>::constant(0))?;
         sum_fully_correct.wrapping_add_in_place(&is_correct_64);
     }
     Ok(sum_fully_correct)
@@ -140,12 +437,15 @@ fn count_correct_guesses(
 /**
  * Count the number of pegs of the given color present in the code.
  */
-fn count_color(peg: &UInt64<Fr>, code: &Vec<UInt64<Fr>>) -> Result<UInt64<Fr>, SynthesisError> {
-    let mut count = UInt64::<Fr>::constant(0);
-    for i in 0..code.len() {
-        let is_same_color = code[i].is_eq(peg)?;
+fn count_color<F: PrimeField>(
+    peg: &UInt64<F>,
+    code: &[UInt64<F>],
+) -> Result<UInt64<F>, SynthesisError> {
+    let mut count = UInt64::<F>::constant(0);
+    for code_peg in code {
+        let is_same_color = code_peg.is_eq(peg)?;
         let is_same_color_64 =
-            is_same_color.select(&UInt64::<Fr>::constant(1), &UInt64::<Fr>::constant(0))?;
+            is_same_color.select(&UInt64::<F>::constant(1), &UInt64::<F>::constant(0))?;
         count.wrapping_add_in_place(&is_same_color_64);
     }
     Ok(count)
@@ -154,7 +454,7 @@ fn count_color(peg: &UInt64<Fr>, code: &Vec<UInt64<Fr>>) -> Result<UInt64<Fr>, S
 /**
  * Find the minimum between a and b.
  */
-fn min(a: &UInt64<Fr>, b: &UInt64<Fr>) -> Result<UInt64<Fr>, SynthesisError> {
+fn min<F: PrimeField>(a: &UInt64<F>, b: &UInt64<F>) -> Result<UInt64<F>, SynthesisError> {
     a.is_lt(b)?.select(a, b)
 }
 
@@ -165,41 +465,79 @@ fn min(a: &UInt64<Fr>, b: &UInt64<Fr>) -> Result<UInt64<Fr>, SynthesisError> {
  * In essence, for each color, count the number of pegs of that color in the guess and code. Find the minimum of those two numbers.
  * Then add this up across all colors, subtracting the overlap that are fully correct pegs.
  */
-fn count_partial_guesses<const NPEGS: usize>(
-    code: &Vec<UInt64<Fr>>,
-    guess: &Vec<UInt64<Fr>>,
-    fully_correct: &UInt64<Fr>,
-) -> Result<UInt64<Fr>, SynthesisError> {
-    let mut partial_sum = UInt64::<Fr>::constant(0);
-    for p in 0..NPEGS {
-        let peg = UInt64::<Fr>::constant(p as u64);
+pub(crate) fn count_partial_guesses<F: PrimeField>(
+    code: &[UInt64<F>],
+    guess: &[UInt64<F>],
+    fully_correct: &UInt64<F>,
+    num_colors: u64,
+) -> Result<UInt64<F>, SynthesisError> {
+    let mut partial_sum = UInt64::<F>::constant(0);
+    for p in 0..num_colors {
+        let peg = UInt64::<F>::constant(p);
         let guess_count = count_color(&peg, guess)?;
         let code_count = count_color(&peg, code)?;
         let color_min = min(&guess_count, &code_count)?;
         partial_sum.wrapping_add_in_place(&color_min);
     }
     let fp_diff = partial_sum.to_fp()? - fully_correct.to_fp()?;
-    let diff = UInt64::<Fr>::from_fp(&fp_diff)?.0;
-    return Ok(diff);
+    let diff = UInt64::<F>::from_fp(&fp_diff)?.0;
+    Ok(diff)
+}
+
+/**
+ * Compute the number of "cows" in a game of Bulls and Cows: the number of pairs
+ * (i, j), i != j, with code[i] == guess[j]. This is only the number of
+ * partially correct guesses if the code and the guess are made of distinct
+ * digits.
+ */
+pub(crate) fn count_misplaced_digits<F: PrimeField>(
+    code: &[UInt64<F>],
+    guess: &[UInt64<F>],
+) -> Result<UInt64<F>, SynthesisError> {
+    let mut cows = UInt64::<F>::constant(0);
+    for (i, code_peg) in code.iter().enumerate() {
+        for (j, guess_peg) in guess.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let is_cow = code_peg.is_eq(guess_peg)?;
+            let is_cow_64 = is_cow.select(&UInt64::<F>::constant(1), &UInt64::<F>::constant(0))?;
+            cows.wrapping_add_in_place(&is_cow_64);
+        }
+    }
+    Ok(cows)
 }
 
 /**
  * Assert that the response is valid, assuming that the code and guess are already
  * valid. This checks that, given the code and the guess, that the provided feedback
  * (number of black and white pegs, i.e. number of fully correct and partially correct
- * guesses) is correct.
+ * guesses) is correct under the scoring rules of the variant.
  */
-fn assert_response_is_valid<const NPEGS: usize>(
-    code: &Vec<UInt64<Fr>>,
-    guess: &Vec<UInt64<Fr>>,
-    num_partial_correct: &UInt64<Fr>,
-    num_fully_correct: &UInt64<Fr>,
+pub(crate) fn assert_response_is_valid<F: PrimeField, const NPEGS: usize>(
+    code: &[UInt64<F>],
+    guess: &[UInt64<F>],
+    num_partial_correct: &UInt64<F>,
+    num_fully_correct: &UInt64<F>,
+    variant: &GameVariant,
 ) -> Result<(), SynthesisError> {
     // Assume we've already checked the code and guess for legality.
-    let sum_fully_correct = count_correct_guesses(code, guess)?;
-    sum_fully_correct.enforce_equal(num_fully_correct)?;
+    {
+        let _ns = ns!(code.cs(), "fully_correct");
+        let sum_fully_correct = count_correct_guesses(code, guess)?;
+        sum_fully_correct.enforce_equal(num_fully_correct)?;
+    }
 
-    let computed_partial_correct = count_partial_guesses::<NPEGS>(code, guess, &num_fully_correct)?;
+    let _ns = ns!(code.cs(), "partially_correct");
+    let computed_partial_correct = match variant.scoring {
+        Scoring::Classic => count_partial_guesses(
+            code,
+            guess,
+            num_fully_correct,
+            variant.num_colors::<NPEGS>(),
+        )?,
+        Scoring::BullsAndCows => count_misplaced_digits(code, guess)?,
+    };
     computed_partial_correct.enforce_equal(num_partial_correct)?;
 
     Ok(())
@@ -208,50 +546,290 @@ fn assert_response_is_valid<const NPEGS: usize>(
 /**
  * This is the core logic of the mastermind circuit.
  */
-impl<const NPEGS: usize, const SZ: usize> ConstraintSynthesizer<Fr>
-    for MastermindCircuit<NPEGS, SZ>
+impl<F: PrimeField + Absorb, const NPEGS: usize, const SZ: usize> ConstraintSynthesizer<F>
+    for MastermindCircuit<F, NPEGS, SZ>
 {
-    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
         // Allocate the variables
-        // - private inputs are created via "new witness"
-        let uint64_input_private = |opt: &Option<u64>| {
-            UInt64::<Fr>::new_witness(cs.clone(), || opt.ok_or(SynthesisError::AssignmentMissing))
-        };
-        let uint64_arr_input_private = |arr: &[Option<u64>; SZ]| {
-            arr.iter()
-                .map(uint64_input_private)
-                .collect::<Vec<Result<_, _>>>()
-                .into_iter()
-                .collect::<Result<_, _>>()
-        };
-        // - public inputs are created via "new input"
-        let uint64_input_public = |opt: &Option<u64>| {
-            UInt64::<Fr>::new_input(cs.clone(), || opt.ok_or(SynthesisError::AssignmentMissing))
-        };
-        let uint64_arr_input_public = |arr: &[Option<u64>; SZ]| {
-            arr.iter()
-                .map(uint64_input_public)
-                .collect::<Vec<Result<_, _>>>()
-                .into_iter()
-                .collect::<Result<_, _>>()
+        let inputs_ns = ns!(cs, "inputs");
+        // - Game info
+        let code = uint64_arr_input_private(&cs, &self.code)?;
+        let nonce = fp_input_private(&cs, &self.nonce)?;
+        let hash = fp_input_public(&cs, &self.hash)?;
+        let (num_partial_correct, num_fully_correct, guess) = match self.encoding {
+            InputEncoding::Bits => (
+                // - Codemaker response
+                uint64_input_public(&cs, &self.num_partial_correct)?,
+                uint64_input_public(&cs, &self.num_fully_correct)?,
+                // - Codebreaker input
+                uint64_arr_input_public(&cs, &self.guess)?,
+            ),
+            InputEncoding::Packed => {
+                // The values are witnesses, bound to the single packed input.
+                let num_partial_correct = uint64_input_private(&cs, &self.num_partial_correct)?;
+                let num_fully_correct = uint64_input_private(&cs, &self.num_fully_correct)?;
+                let guess = uint64_arr_input_private(&cs, &self.guess)?;
+
+                let _ns = ns!(cs, "packing");
+                let packed = FpVar::<F>::new_input(cs.clone(), || {
+                    let inputs = self
+                        .public_inputs()
+                        .ok_or(SynthesisError::AssignmentMissing)?;
+                    Ok(inputs[1])
+                })?;
+                let weights = packing_weights::<F>(SZ, self.variant.num_colors::<NPEGS>());
+                let mut unpacked = FpVar::<F>::zero();
+                for (value, weight) in guess
+                    .iter()
+                    .chain([&num_fully_correct, &num_partial_correct])
+                    .zip(weights)
+                {
+                    unpacked += value.to_fp()? * weight;
+                }
+                unpacked.enforce_equal(&packed)?;
+
+                (num_partial_correct, num_fully_correct, guess)
+            }
         };
 
-        // - Game info
-        let code: Vec<_> = uint64_arr_input_private(&self.code)?;
-        let nonce = uint64_input_private(&self.nonce)?;
-        let hash = uint64_input_public(&self.hash)?;
-        // - Codemaker response
-        let num_partial_correct = uint64_input_public(&self.num_partial_correct)?;
-        let num_fully_correct = uint64_input_public(&self.num_fully_correct)?;
-        // - Codebreaker input
-        let guess: Vec<_> = uint64_arr_input_public(&self.guess)?;
+        drop(inputs_ns);
 
         // Make sure the code is valid
-        assert_code_is_valid::<NPEGS>(cs, &code, &nonce, &hash)?;
+        {
+            let _ns = ns!(cs, "code");
+            assert_code_is_valid::<_, NPEGS>(cs.clone(), &code, &nonce, &hash, &self.variant)?;
+        }
         // Check that the guess is valid
-        assert_pegs_are_legal::<NPEGS>(&guess)?;
+        {
+            let _ns = ns!(cs, "guess");
+            assert_guess_is_valid::<_, NPEGS>(&guess, &self.variant)?;
+        }
         // Check that the response is valid
-        assert_response_is_valid::<NPEGS>(&code, &guess, &num_partial_correct, &num_fully_correct)?;
+        let _ns = ns!(cs, "response");
+        assert_response_is_valid::<_, NPEGS>(
+            &code,
+            &guess,
+            &num_partial_correct,
+            &num_fully_correct,
+            &self.variant,
+        )?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /**
+     * Build a circuit for the given game, filling in the hash and the response
+     * with the native reference implementation.
+     */
+    fn game<const NPEGS: usize>(
+        code: [u64; 4],
+        guess: [u64; 4],
+        variant: GameVariant,
+    ) -> MastermindCircuit<Fr, NPEGS, 4> {
+        let nonce = Fr::from(42u64);
+        let (fully_correct, partial_correct) = variant.score(&code, &guess);
+        MastermindCircuit {
+            code: code.map(Some),
+            nonce: Some(nonce),
+            hash: Some(compute_commitment(nonce, &code, variant.commitment)),
+            num_partial_correct: Some(partial_correct),
+            num_fully_correct: Some(fully_correct),
+            guess: guess.map(Some),
+            variant,
+            encoding: InputEncoding::Bits,
+        }
+    }
+
+    fn is_satisfied<F: PrimeField + Absorb, const NPEGS: usize>(
+        circuit: MastermindCircuit<F, NPEGS, 4>,
+    ) -> bool {
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_classic() {
+        let v = GameVariant::CLASSIC;
+        assert!(is_satisfied(game::<6>([1, 2, 3, 4], [1, 2, 4, 3], v)));
+        assert!(is_satisfied(game::<6>([1, 1, 2, 2], [2, 2, 1, 1], v)));
+
+        let mut wrong_response = game::<6>([1, 2, 3, 4], [1, 2, 4, 3], v);
+        wrong_response.num_partial_correct = Some(1);
+        assert!(!is_satisfied(wrong_response));
+        // out of range peg
+        assert!(!is_satisfied(game::<6>([6, 2, 3, 4], [1, 2, 4, 3], v)));
+    }
+
+    #[test]
+    fn test_no_duplicates() {
+        let v = GameVariant {
+            duplicates: false,
+            ..GameVariant::CLASSIC
+        };
+        assert!(is_satisfied(game::<6>([0, 1, 2, 3], [3, 3, 3, 3], v)));
+        assert!(!is_satisfied(game::<6>([0, 1, 2, 2], [3, 3, 3, 3], v)));
+    }
+
+    #[test]
+    fn test_blanks() {
+        let v = GameVariant {
+            blanks: true,
+            ..GameVariant::CLASSIC
+        };
+        assert!(is_satisfied(game::<6>([6, 1, 6, 3], [6, 6, 1, 0], v)));
+        assert!(!is_satisfied(game::<6>([7, 1, 6, 3], [6, 6, 1, 0], v)));
+        assert!(!is_satisfied(game::<6>(
+            [6, 1, 6, 3],
+            [6, 6, 1, 0],
+            GameVariant::CLASSIC
+        )));
+    }
+
+    #[test]
+    fn test_bulls_and_cows() {
+        let v = GameVariant::BULLS_AND_COWS;
+        assert!(is_satisfied(game::<10>([9, 8, 7, 6], [6, 1, 8, 0], v)));
+        assert!(is_satisfied(game::<10>([1, 2, 3, 4], [1, 2, 4, 3], v)));
+        // duplicate digits in the code or in the guess
+        assert!(!is_satisfied(game::<10>([9, 9, 7, 6], [6, 1, 8, 0], v)));
+        assert!(!is_satisfied(game::<10>([9, 8, 7, 6], [6, 6, 8, 0], v)));
+    }
+
+    #[test]
+    fn test_encode_public_inputs() {
+        use ark_bn254::Bn254;
+        use ark_groth16::Groth16;
+        use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+        use ark_std::rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let circuit = game::<6>([1, 2, 3, 4], [1, 2, 4, 3], GameVariant::CLASSIC);
+        let inputs = circuit.public_inputs().unwrap();
+        // 1 hash + (2 counts + 4 pegs) * 64 bits
+        assert_eq!(inputs.len(), 1 + 6 * 64);
+        assert_eq!(inputs, crate::backend::public_inputs(circuit.clone()));
+
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let (pk, vk) = Groth16::<Bn254>::setup(circuit.clone(), rng).unwrap();
+        let proof = Groth16::<Bn254>::prove(&pk, circuit.clone(), rng).unwrap();
+        let verify = |inputs: &[Fr]| Groth16::<Bn254>::verify(&vk, inputs, &proof).unwrap();
+        assert!(verify(&inputs));
+
+        let hash = circuit.hash.unwrap();
+        let (guess, fully, partial) = ([1, 2, 4, 3], 2, 2);
+        assert!(verify(&encode_public_inputs(hash, &guess, fully, partial)));
+        assert!(!verify(&encode_public_inputs(
+            hash + Fr::from(1u64),
+            &guess,
+            fully,
+            partial
+        )));
+        assert!(!verify(&encode_public_inputs(
+            hash,
+            &[1, 2, 3, 4],
+            fully,
+            partial
+        )));
+        assert!(!verify(&encode_public_inputs(
+            hash,
+            &guess,
+            fully + 1,
+            partial
+        )));
+        assert!(!verify(&encode_public_inputs(
+            hash,
+            &guess,
+            fully,
+            partial - 1
+        )));
+
+        // Only the bits of the response differ between two responses.
+        let (partial_bits, fully_bits) = response_public_inputs();
+        let other = encode_public_inputs(hash, &guess, 0, 3);
+        let encoded = encode_public_inputs(hash, &guess, fully, partial);
+        let differs: Vec<usize> = (0..inputs.len())
+            .filter(|&i| encoded[i] != other[i])
+            .collect();
+        assert_eq!(differs, [partial_bits.start, fully_bits.start + 1]);
+    }
+
+    #[test]
+    fn test_packed_public_inputs() {
+        use ark_bn254::Bn254;
+        use ark_groth16::Groth16;
+        use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+        use ark_std::rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let mut circuit = game::<6>([1, 2, 3, 4], [1, 2, 4, 3], GameVariant::CLASSIC);
+        circuit.encoding = InputEncoding::Packed;
+        let inputs = circuit.public_inputs().unwrap();
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs, crate::backend::public_inputs(circuit.clone()));
+
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let (pk, vk) = Groth16::<Bn254>::setup(circuit.clone(), rng).unwrap();
+        let proof = Groth16::<Bn254>::prove(&pk, circuit.clone(), rng).unwrap();
+        let verify = |inputs: &[Fr]| Groth16::<Bn254>::verify(&vk, inputs, &proof).unwrap();
+        assert!(verify(&inputs));
+
+        let hash = circuit.hash.unwrap();
+        let encode = |guess: &[u64], fully, partial| {
+            encode_packed_public_inputs(hash, guess, fully, partial, 6)
+        };
+        assert!(verify(&encode(&[1, 2, 4, 3], 2, 2)));
+        assert!(!verify(&encode(&[1, 2, 3, 4], 2, 2)));
+        assert!(!verify(&encode(&[1, 2, 4, 3], 1, 2)));
+        assert!(!verify(&encode(&[1, 2, 4, 3], 2, 1)));
+
+        // A wrong response is rejected in packed mode too.
+        circuit.num_partial_correct = Some(1);
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_legacy_commitment() {
+        let v = GameVariant {
+            commitment: HashScheme::Legacy,
+            ..GameVariant::CLASSIC
+        };
+        assert!(is_satisfied(game::<6>([1, 2, 3, 4], [1, 2, 4, 3], v)));
+
+        // The circuit checks the hash under the scheme of the variant.
+        let mut circuit = game::<6>([1, 2, 3, 4], [1, 2, 4, 3], v);
+        circuit.variant = GameVariant::CLASSIC;
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_bls12_381() {
+        use ark_bls12_381::Fr as BlsFr;
+
+        let bn254 = game::<6>([1, 2, 3, 4], [1, 2, 4, 3], GameVariant::CLASSIC);
+        let mut circuit = MastermindCircuit::<BlsFr, 6, 4> {
+            code: bn254.code,
+            nonce: Some(BlsFr::from(42u64)),
+            hash: bn254.hash.and_then(|h| BlsFr::from_bigint(h.into_bigint())),
+            num_partial_correct: bn254.num_partial_correct,
+            num_fully_correct: bn254.num_fully_correct,
+            guess: bn254.guess,
+            variant: bn254.variant,
+            encoding: bn254.encoding,
+        };
+        // The hash depends on the field, so the BN254 hash is rejected.
+        assert!(!is_satisfied(circuit.clone()));
+        circuit.hash = Some(compute_commitment(
+            BlsFr::from(42u64),
+            &[1, 2, 3, 4],
+            HashScheme::Circomlib,
+        ));
+        assert!(is_satisfied(circuit));
+    }
+}
@@ -1,95 +1,848 @@
+use ark_bls12_381::Bls12_381;
 use ark_bn254::Bn254;
-use ark_crypto_primitives::sponge::CryptographicSponge;
-use ark_groth16::Groth16;
-use ark_snark::CircuitSpecificSetupSNARK;
-use ark_snark::SNARK;
-use ark_std::rand::SeedableRng;
-use arkworks::get_poseidon_config;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16};
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_snark::{CircuitSpecificSetupSNARK, UniversalSetupSNARK, SNARK};
+use ark_std::rand::{Rng, SeedableRng};
+use arkworks::backend::{self, Backend, BackendReport};
+use arkworks::batch::{self, BatchReport};
+use arkworks::ceremony;
+use arkworks::commitment::{self, CodeCommitmentCircuit};
+use arkworks::history::{ConsistencyCircuit, Round};
+use arkworks::marlin::{Marlin, MarlinBound, MarlinCurve};
+use arkworks::r1cs_file;
+use arkworks::secret::Secret;
+use arkworks::smt::{self, Verdict};
+use arkworks::snarkjs::{self, SnarkjsCurve};
+use arkworks::solidity;
+use arkworks::stats::{self, CostReport};
+use arkworks::underconstrained;
+use arkworks::GameVariant;
+use arkworks::InputEncoding;
 use arkworks::MastermindCircuit;
+use clap::{Parser, Subcommand, ValueEnum};
 use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
 
-use ark_bn254::Fr;
-use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
-// Define the parameters for Poseidon hash (for Bn254)
-type PoseidonSpongeBn254 = PoseidonSponge<Fr>;
+/**
+ * The pairing-friendly curves the circuit can be proven on.
+ */
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Curve {
+    Bn254,
+    #[value(name = "bls12-381")]
+    Bls12_381,
+}
+
+/**
+ * The proof systems the circuit can be proven with.
+ */
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum BackendKind {
+    Groth16,
+    Marlin,
+}
 
-// Example function to compute the Poseidon hash so we can compute the input hash.
-fn compute_poseidon_hash(inputs: &[u64]) -> u64 {
-    // Create Poseidon parameters (configuration specific to Bn254)
-    let params = get_poseidon_config();
+#[derive(Parser, Debug)]
+#[command(about = "Set up, prove and verify the mastermind circuit on test inputs")]
+struct Cli {
+    /// Curves to prove and verify on
+    #[arg(
+        long = "curve",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "bn254"
+    )]
+    curves: Vec<Curve>,
 
-    let mut sponge = PoseidonSpongeBn254::new(&params);
-    for i in inputs {
-        sponge.absorb(i);
-    }
+    /// Proof systems to prove and verify with
+    #[arg(
+        long = "backend",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "groth16"
+    )]
+    backends: Vec<BackendKind>,
+
+    /// Print the setup, proving and verification costs of each backend
+    #[arg(long)]
+    compare: bool,
+
+    /// Re-randomize every proof before printing, verifying or writing it, so
+    /// that proofs of the same statement cannot be linked
+    #[arg(long)]
+    rerandomize: bool,
+
+    /// Write the constraint system and the witness of the mastermind circuit
+    /// to `<DIR>/mastermind-<curve>.{r1cs,wtns,sym}`, for the circom tools
+    #[arg(long, value_name = "DIR")]
+    export: Option<PathBuf>,
+
+    /// Also prove and verify a circuit compiled by circom, read from
+    /// `<STEM>.r1cs` and its witness from `<STEM>.wtns`
+    #[arg(long, value_name = "STEM")]
+    circom: Option<PathBuf>,
+
+    /// Write a proof of the packed mastermind game to
+    /// `<DIR>/mastermind-<curve>/{proof,verification_key,public}.json`, for
+    /// `snarkjs groth16 verify`
+    #[arg(long, value_name = "DIR")]
+    snarkjs: Option<PathBuf>,
+
+    /// Write a Solidity verifier for the packed mastermind game and a game
+    /// contract using it to `<DIR>/mastermind-<curve>/`, on BN254 only
+    #[arg(long, value_name = "DIR")]
+    solidity: Option<PathBuf>,
+
+    /// Prove the packed game of `--snarkjs` and `--solidity` with the keys
+    /// written by `ceremony finalize` to DIR, on BN254 only. Without it, the
+    /// keys are set up from a fixed seed and anyone can forge proofs for them
+    #[arg(long, value_name = "DIR")]
+    keys: Option<PathBuf>,
 
-    // Finalize the hash and get the output
-    let hash = sponge.squeeze_bytes(8);
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-    let mut bytes = [0u8; 8];
-    bytes.copy_from_slice(&hash);
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Generate the Groth16 keys of the packed mastermind game on BN254 in a
+    /// phase-2 ceremony, one participant at a time. Phase 1 is simulated by
+    /// `init`, unchecked: whoever runs it knows its secrets and can forge
+    /// proofs for the final keys whatever the contributions, so the ceremony
+    /// does not make the keys any safer yet
+    Ceremony {
+        #[command(subcommand)]
+        step: CeremonyStep,
+    },
+    /// Print the constraints, witness variables and public inputs added by
+    /// each gadget of the mastermind circuit, on the curves of `--curve`
+    Stats,
+    /// Look for under-constrained witnesses in the mastermind circuit, on the
+    /// curves of `--curve`
+    Underconstrained,
+    /// Ask cvc5, if it is on the PATH, whether the response of the mastermind
+    /// circuit is unique, on the curves of `--curve`
+    Smt {
+        /// Also write the SMT-LIB queries to `<DIR>/<query>-<curve>.smt2`
+        #[arg(long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+}
 
-    // Convert to u64
-    u64::from_le_bytes(bytes)
+#[derive(Subcommand, Debug)]
+enum CeremonyStep {
+    /// Start a ceremony in the directory, with a phase 1 drawn from the
+    /// randomness of this machine, which can forge proofs for the keys
+    Init { dir: PathBuf },
+    /// Add a contribution from fresh OS randomness to the latest keys
+    Contribute {
+        dir: PathBuf,
+        /// Extra entropy mixed into the randomness of the contribution
+        #[arg(long)]
+        entropy: Option<String>,
+    },
+    /// Check every contribution made so far
+    Verify { dir: PathBuf },
+    /// Check the ceremony and write the final keys
+    Finalize { dir: PathBuf },
 }
 
 /**
- * Utility for setting the hash for test inputs. Assuming that the nonce
- * and the code are set, update the hash so that the hash is the hash of the
- * nonce and code.
+ * Where to write the files produced for one curve, if anywhere.
  */
-fn fill_in_hash(circuit: &mut MastermindCircuit<6, 4>) {
-    let inputs = vec![
-        circuit.nonce.unwrap(),
-        circuit.code[0].unwrap(),
-        circuit.code[1].unwrap(),
-        circuit.code[2].unwrap(),
-        circuit.code[3].unwrap(),
-    ];
-    let hash_u64 = compute_poseidon_hash(&inputs);
-    circuit.hash = Some(hash_u64);
+struct Outputs {
+    // Directories of the snarkjs JSON files and of the Solidity contracts
+    snarkjs: Option<PathBuf>,
+    solidity: Option<PathBuf>,
 }
 
 /**
  * Verify the circuit. Checks the constrains and outputs the proof if so. If
  * the constraints are violated, the prove function will fail.
  */
-fn validate(rng: &mut ChaCha20Rng, circuit: &MastermindCircuit<6, 4>) {
+fn validate<F: PrimeField, S: Backend<F>, C: ConstraintSynthesizer<F> + Clone>(
+    params: &S::Params,
+    rng: &mut ChaCha20Rng,
+    circuit: &C,
+    public_inputs: &[F],
+    rerandomize: bool,
+) -> (S::VerifyingKey, S::Proof)
+where
+    S::Proof: Debug,
+{
     // Create the parameters.
-    let params = Groth16::<Bn254>::setup(circuit.clone(), rng).unwrap();
+    let (pk, vk) = S::keygen(params, circuit.clone(), rng).unwrap();
+    let proof = prove_and_verify::<F, S, C>(rng, &pk, &vk, circuit, public_inputs, rerandomize);
+    (vk, proof)
+}
 
+/**
+ * Prove the circuit with the keys and verify the proof, like `validate`.
+ */
+fn prove_and_verify<F: PrimeField, S: Backend<F>, C: ConstraintSynthesizer<F> + Clone>(
+    rng: &mut ChaCha20Rng,
+    pk: &S::ProvingKey,
+    vk: &S::VerifyingKey,
+    circuit: &C,
+    public_inputs: &[F],
+    rerandomize: bool,
+) -> S::Proof
+where
+    S::Proof: Debug,
+{
     // Generate the proof. This will fail if the constraints are violated.
-    let proof = Groth16::<Bn254>::prove(&params.0, circuit.clone(), rng).unwrap();
-
+    let mut proof = S::prove(pk, circuit.clone(), rng).unwrap();
+    if rerandomize {
+        proof = match S::rerandomize(vk, &proof, rng) {
+            Some(proof) => proof,
+            // A fresh proof is as unlinkable as a re-randomized one.
+            None => S::prove(pk, circuit.clone(), rng).unwrap(),
+        };
+    }
     println!("Proof: {:?}", proof);
+
+    let verified = S::verify(vk, public_inputs, &proof).unwrap();
+    assert!(verified, "Proof does not verify");
+    println!("Proof verified");
+    proof
 }
 
-fn main() {
-    // Example inputs
-    let mut test1 = MastermindCircuit::<6, 4> {
-        code: [Some(0), Some(0), Some(0), Some(0)],
-        nonce: Some(42),
-        hash: Some(0),
-        num_partial_correct: Some(0),
-        num_fully_correct: Some(0),
+/**
+ * Warn that keys come from the seeded RNG of `main`: anyone can forge proofs
+ * for them.
+ */
+fn warn_seeded_keys(what: &str) {
+    eprintln!("WARNING: {what} set up from a fixed seed, anyone can forge proofs for them");
+}
+
+/**
+ * Print the costs measured for proving one circuit.
+ */
+fn print_report(circuit: &str, report: &BackendReport) {
+    println!(
+        "{:<17} {:<8} setup {:>10.2?} prove {:>10.2?} verify {:>10.2?} pk {:>8} B vk {:>6} B proof {:>5} B",
+        circuit,
+        report.backend,
+        report.setup_time,
+        report.prove_time,
+        report.verify_time,
+        report.proving_key_size,
+        report.verifying_key_size,
+        report.proof_size,
+    );
+}
+
+/**
+ * Print the time to verify the proofs of a game one by one and as a batch.
+ */
+fn print_batch_report(circuit: &str, backend: &str, report: &BatchReport) {
+    println!(
+        "{:<17} {:<8} {} proofs: verify one by one {:>10.2?} batch {:>10.2?}",
+        circuit, backend, report.num_proofs, report.separate_time, report.batch_time,
+    );
+}
+
+/**
+ * Prove every round of a game with the same key, then verify the proofs one
+ * by one and as a batch.
+ */
+fn measure_batch<E: Pairing>(
+    game: &MastermindCircuit<E::ScalarField, 6, 4>,
+    num_rounds: u64,
+    rng: &mut ChaCha20Rng,
+) -> BatchReport
+where
+    E::ScalarField: Absorb,
+{
+    let code = game.code.map(Option::unwrap);
+    let rounds: Vec<_> = (0..num_rounds)
+        .map(|i| {
+            let guess = [i, i + 1, 2 * i, 3 * i].map(|peg| peg % 6);
+            let (fully, partial) = game.variant.score(&code, &guess);
+            MastermindCircuit {
+                guess: guess.map(Some),
+                num_fully_correct: Some(fully),
+                num_partial_correct: Some(partial),
+                ..game.clone()
+            }
+        })
+        .collect();
+
+    let (pk, vk) = Groth16::<E>::setup(game.clone(), rng).unwrap();
+    let pvk = prepare_verifying_key(&vk);
+    let inputs: Vec<_> = rounds.iter().map(|r| r.public_inputs().unwrap()).collect();
+    let proofs: Vec<_> = rounds
+        .iter()
+        .map(|r| Groth16::<E>::prove(&pk, r.clone(), rng).unwrap())
+        .collect();
+    let batch: Vec<_> = inputs.iter().map(Vec::as_slice).zip(&proofs).collect();
+    let report = batch::measure_batch(&pvk, &batch, rng).unwrap();
+    assert!(report.verified, "Proofs do not verify");
+    report
+}
+
+/**
+ * An example game of the classic variant: the codemaker commits to the code
+ * under a fresh nonce, and answers the guess [1, 2, 4, 3] with the response
+ * (fully correct, partially correct).
+ */
+fn example_game<F: PrimeField + Absorb>(
+    rng: &mut ChaCha20Rng,
+    code: [u64; 4],
+    response: (u64, u64),
+) -> MastermindCircuit<F, 6, 4> {
+    let variant = GameVariant::CLASSIC;
+    let secret = Secret::<F, 4>::new(code, rng);
+    MastermindCircuit {
+        code: secret.code.map(Some),
+        nonce: Some(secret.nonce),
+        hash: Some(secret.commitment(variant.commitment)),
+        num_partial_correct: Some(response.1),
+        num_fully_correct: Some(response.0),
         guess: [Some(1), Some(2), Some(4), Some(3)],
+        variant,
+        encoding: InputEncoding::Bits,
+    }
+}
+
+/**
+ * Write the constraint system and the witness of an example game to
+ * `<stem>.{r1cs,wtns,sym}`. They do not depend on the backend, so this runs
+ * once per curve.
+ */
+fn export<F: PrimeField + Absorb>(rng: &mut ChaCha20Rng, stem: &Path) {
+    if let Some(dir) = stem.parent() {
+        std::fs::create_dir_all(dir).unwrap();
+    }
+    r1cs_file::export(example_game::<F>(rng, [1, 2, 3, 4], (2, 2)), stem).unwrap();
+    println!("Constraint system written to {}.r1cs", stem.display());
+}
+
+/**
+ * Prove and verify the example games over the scalar field of the curve E.
+ */
+fn run<E: SnarkjsCurve + MarlinCurve>(
+    rng: &mut ChaCha20Rng,
+    backend: BackendKind,
+    compare: bool,
+    rerandomize: bool,
+    circom: Option<&Path>,
+    keys: Option<&Path>,
+    outputs: &Outputs,
+) where
+    E::ScalarField: Absorb,
+{
+    // Example inputs, each code committed under a fresh nonce
+    let test1 = example_game::<E::ScalarField>(rng, [0, 0, 0, 0], (0, 0));
+    let test2 = example_game::<E::ScalarField>(rng, [1, 2, 3, 4], (2, 2));
+
+    // At the start of the game, the codemaker proves that the code behind the
+    // hash is legal.
+    let commitment = CodeCommitmentCircuit::from(&test2);
+    let commitment_inputs = commitment.public_inputs().unwrap();
+
+    // At the end of the game, a single proof shows that the code behind the
+    // hash is consistent with every response given during the game.
+    let history = ConsistencyCircuit::<_, 6, 4> {
+        code: test2.code,
+        nonce: test2.nonce,
+        hash: test2.hash,
+        history: vec![
+            Round::new([1, 2, 4, 3], 2, 2),
+            Round::new([0, 0, 0, 0], 0, 0),
+            Round::new([1, 2, 3, 4], 4, 0),
+        ],
+        variant: GameVariant::CLASSIC,
     };
-    fill_in_hash(&mut test1);
 
-    let mut test2 = MastermindCircuit::<6, 4> {
-        code: [Some(1), Some(2), Some(3), Some(4)],
-        nonce: Some(43),
-        hash: Some(0),
+    // The same game, with the guess and the response packed into a single
+    // public input.
+    let packed = MastermindCircuit {
+        encoding: InputEncoding::Packed,
+        ..test2.clone()
+    };
+
+    // A circuit compiled by circom, over the same field only.
+    let circom = circom.and_then(|stem| match r1cs_file::import::<E::ScalarField>(stem) {
+        Ok(circuit) => Some(circuit),
+        Err(e) => {
+            println!("Skipping the circom circuit {}: {e}", stem.display());
+            None
+        }
+    });
+
+    // A single universal setup for every circuit of the run, none for the
+    // circuit-specific setup of Groth16.
+    let srs = match backend {
+        BackendKind::Groth16 => None,
+        BackendKind::Marlin => {
+            let bound = [
+                MarlinBound::of_circuit::<E::ScalarField, _>(commitment.clone()),
+                MarlinBound::of_circuit::<E::ScalarField, _>(test2.clone()),
+                MarlinBound::of_circuit::<E::ScalarField, _>(packed.clone()),
+                MarlinBound::of_circuit::<E::ScalarField, _>(history.clone()),
+            ]
+            .into_iter()
+            .chain(circom.clone().map(MarlinBound::of_circuit))
+            .map(Result::unwrap)
+            .fold(MarlinBound::default(), |bound, other| bound.join(&other));
+            Some(Marlin::<E>::universal_setup(&bound, rng).unwrap())
+        }
+    };
+    let srs = srs.as_ref();
+
+    match backend {
+        BackendKind::Groth16 => {
+            validate::<_, Groth16<E>, _>(&(), rng, &commitment, &commitment_inputs, rerandomize);
+            // The verifier encodes the public data of each game on its own.
+            for test in [&test1, &test2] {
+                let inputs = test.public_inputs().unwrap();
+                validate::<_, Groth16<E>, _>(&(), rng, test, &inputs, rerandomize);
+            }
+            let inputs = backend::public_inputs(history.clone());
+            validate::<_, Groth16<E>, _>(&(), rng, &history, &inputs, rerandomize);
+        }
+        BackendKind::Marlin => {
+            validate::<_, Marlin<E>, _>(
+                srs.unwrap(),
+                rng,
+                &commitment,
+                &commitment_inputs,
+                rerandomize,
+            );
+            for test in [&test1, &test2] {
+                let inputs = test.public_inputs().unwrap();
+                validate::<_, Marlin<E>, _>(srs.unwrap(), rng, test, &inputs, rerandomize);
+            }
+            let inputs = backend::public_inputs(history.clone());
+            validate::<_, Marlin<E>, _>(srs.unwrap(), rng, &history, &inputs, rerandomize);
+        }
+    }
+    if outputs.snarkjs.is_some() || outputs.solidity.is_some() {
+        let inputs = packed.public_inputs().unwrap();
+        match backend {
+            BackendKind::Groth16 => {
+                let (pk, vk) = match keys {
+                    Some(dir) => ceremony::read_keys::<E>(dir).unwrap_or_else(|e| {
+                        panic!("Could not read the keys in {}: {e}", dir.display())
+                    }),
+                    None => {
+                        warn_seeded_keys("the keys of the --snarkjs and --solidity outputs are");
+                        eprintln!(
+                            "WARNING: pass --keys with the keys of a ceremony to publish them"
+                        );
+                        Groth16::<E>::setup(packed.clone(), rng).unwrap()
+                    }
+                };
+                let proof = prove_and_verify::<_, Groth16<E>, _>(
+                    rng,
+                    &pk,
+                    &vk,
+                    &packed,
+                    &inputs,
+                    rerandomize,
+                );
+                if let Some(dir) = &outputs.snarkjs {
+                    snarkjs::export(dir, &vk, &proof, &inputs).unwrap();
+                    println!("Proof written to {}", dir.join("proof.json").display());
+                }
+                if let Some(dir) = &outputs.solidity {
+                    match solidity::export(dir, &pk, &packed, rng) {
+                        Ok(()) => println!("Contracts written to {}", dir.display()),
+                        Err(e) => println!("Skipping the Solidity contracts: {e}"),
+                    }
+                }
+            }
+            // snarkjs and the Solidity verifier only check Groth16 proofs.
+            BackendKind::Marlin => println!("Skipping the snarkjs and Solidity outputs of marlin"),
+        }
+    }
+
+    // Every later proof must be about the committed game.
+    assert!(commitment::is_same_game(
+        &commitment_inputs,
+        &test2.public_inputs().unwrap()
+    ));
+    assert!(!commitment::is_same_game(
+        &commitment_inputs,
+        &test1.public_inputs().unwrap()
+    ));
+
+    if let Some(circuit) = &circom {
+        let inputs = circuit.public_inputs().unwrap();
+        match backend {
+            BackendKind::Groth16 => {
+                validate::<_, Groth16<E>, _>(&(), rng, circuit, &inputs, rerandomize);
+            }
+            BackendKind::Marlin => {
+                validate::<_, Marlin<E>, _>(srs.unwrap(), rng, circuit, &inputs, rerandomize);
+            }
+        }
+    }
+
+    if compare {
+        let (single, single_packed, game) = match backend {
+            BackendKind::Groth16 => (
+                backend::measure::<_, Groth16<E>, _, _>(&(), &test2, rng).unwrap(),
+                backend::measure::<_, Groth16<E>, _, _>(&(), &packed, rng).unwrap(),
+                backend::measure::<_, Groth16<E>, _, _>(&(), &history, rng).unwrap(),
+            ),
+            BackendKind::Marlin => (
+                backend::measure::<_, Marlin<E>, _, _>(srs.unwrap(), &test2, rng).unwrap(),
+                backend::measure::<_, Marlin<E>, _, _>(srs.unwrap(), &packed, rng).unwrap(),
+                backend::measure::<_, Marlin<E>, _, _>(srs.unwrap(), &history, rng).unwrap(),
+            ),
+        };
+        print_report("mastermind", &single);
+        print_report("mastermind-packed", &single_packed);
+        print_report("consistency", &game);
+        if let Some(circuit) = &circom {
+            let report = match backend {
+                BackendKind::Groth16 => {
+                    backend::measure::<_, Groth16<E>, _, _>(&(), circuit, rng).unwrap()
+                }
+                BackendKind::Marlin => {
+                    backend::measure::<_, Marlin<E>, _, _>(srs.unwrap(), circuit, rng).unwrap()
+                }
+            };
+            print_report("circom", &report);
+        }
+
+        // The proofs of a whole game, verified together.
+        match backend {
+            BackendKind::Groth16 => {
+                let report = measure_batch::<E>(&packed, 10, rng);
+                print_batch_report("mastermind-batch", Groth16::<E>::NAME, &report);
+            }
+            // The batch verifier aggregates the pairings of Groth16 proofs.
+            BackendKind::Marlin => {}
+        }
+    }
+}
+
+/**
+ * The game the ceremony generates keys for. The keys only depend on the shape
+ * of the circuit, the values are there to try them out.
+ */
+fn ceremony_game(
+    rng: &mut ChaCha20Rng,
+) -> MastermindCircuit<<Bn254 as Pairing>::ScalarField, 6, 4> {
+    let variant = GameVariant::CLASSIC;
+    let secret = Secret::new([1, 2, 3, 4], rng);
+    MastermindCircuit {
+        code: secret.code.map(Some),
+        nonce: Some(secret.nonce),
+        hash: Some(secret.commitment(variant.commitment)),
         num_partial_correct: Some(2),
         num_fully_correct: Some(2),
         guess: [Some(1), Some(2), Some(4), Some(3)],
+        variant,
+        encoding: InputEncoding::Packed,
+    }
+}
+
+/**
+ * An RNG seeded from the OS, and from the entropy given by the participant if
+ * any, so that nobody else can know the secret of a contribution.
+ */
+fn contributor_rng(entropy: Option<&str>) -> ChaCha20Rng {
+    let mut hasher = Sha256::new();
+    hasher.update(ChaCha20Rng::from_entropy().gen::<[u8; 32]>());
+    if let Some(entropy) = entropy {
+        hasher.update(entropy.as_bytes());
+    }
+    ChaCha20Rng::from_seed(hasher.finalize().into())
+}
+
+fn run_ceremony(step: &CeremonyStep) {
+    match step {
+        CeremonyStep::Init { dir } => {
+            let rng = &mut contributor_rng(None);
+            ceremony::init::<Bn254, _, _>(dir, ceremony_game(rng), rng).unwrap();
+            println!("Ceremony started in {}", dir.display());
+        }
+        CeremonyStep::Contribute { dir, entropy } => {
+            let rng = &mut contributor_rng(entropy.as_deref());
+            let i = ceremony::contribute_to::<Bn254, _>(dir, rng).unwrap();
+            println!("Contribution {i} written to {}", dir.display());
+        }
+        CeremonyStep::Verify { dir } => {
+            let rng = &mut contributor_rng(None);
+            let params = ceremony::verify::<Bn254, _>(dir, rng).unwrap();
+            println!(
+                "{} contributions verified, keys hash {}",
+                ceremony::num_contributions(dir),
+                hex(&ceremony::transcript_hash(&params))
+            );
+        }
+        CeremonyStep::Finalize { dir } => {
+            let rng = &mut contributor_rng(None);
+            let (pk, vk) = ceremony::finalize::<Bn254, _>(dir, rng).unwrap();
+            let file = std::fs::File::create(dir.join("verification_key.json")).unwrap();
+            snarkjs::write_verifying_key(&vk, file).unwrap();
+            println!("Keys written to {}", dir.display());
+
+            // The keys prove the game like the ones of `Groth16::setup`.
+            let game = ceremony_game(rng);
+            let proof = Groth16::<Bn254>::prove(&pk, game.clone(), rng).unwrap();
+            let inputs = game.public_inputs().unwrap();
+            assert!(
+                Groth16::<Bn254>::verify(&vk, &inputs, &proof).unwrap(),
+                "Proof does not verify"
+            );
+            println!("Proof verified");
+        }
+    }
+}
+
+/**
+ * Print the cost of each gadget, indented by nesting.
+ */
+fn print_cost_report(circuit: &str, report: &CostReport) {
+    println!(
+        "{:<30} {:>7} constraints {:>7} witnesses {:>4} public inputs",
+        circuit,
+        report.total.constraints,
+        report.total.witness_variables,
+        report.total.public_inputs,
+    );
+    for gadget in &report.gadgets {
+        let depth = gadget.path.matches('/').count();
+        let name = gadget.path.rsplit('/').next().unwrap();
+        println!(
+            "{:<30} {:>7} constraints {:>7} witnesses {:>4} public inputs",
+            format!("{}{name}", "  ".repeat(depth + 1)),
+            gadget.counts.constraints,
+            gadget.counts.witness_variables,
+            gadget.counts.public_inputs,
+        );
+    }
+}
+
+/**
+ * Print the cost of each gadget of the mastermind circuit over the scalar
+ * field F, with both encodings of the public inputs. Only the shape of the
+ * circuit matters, so no value is set.
+ */
+fn run_stats<F: PrimeField + Absorb>() {
+    for encoding in [InputEncoding::Bits, InputEncoding::Packed] {
+        let circuit = MastermindCircuit::<F, 6, 4> {
+            code: [None; 4],
+            nonce: None,
+            hash: None,
+            num_partial_correct: None,
+            num_fully_correct: None,
+            guess: [None; 4],
+            variant: GameVariant::CLASSIC,
+            encoding,
+        };
+        let name = match encoding {
+            InputEncoding::Bits => "mastermind",
+            InputEncoding::Packed => "mastermind-packed",
+        };
+        print_cost_report(name, &stats::measure(circuit).unwrap());
+    }
+}
+
+/**
+ * Run the under-constrained witness analysis and the perturbation checker on
+ * an example game over the scalar field F, with both encodings of the public
+ * inputs.
+ */
+fn run_underconstrained<F: PrimeField + Absorb>(rng: &mut ChaCha20Rng) {
+    let variant = GameVariant::CLASSIC;
+    let secret = Secret::<F, 4>::new([1, 2, 3, 4], rng);
+    for encoding in [InputEncoding::Bits, InputEncoding::Packed] {
+        let circuit = MastermindCircuit::<F, 6, 4> {
+            code: secret.code.map(Some),
+            nonce: Some(secret.nonce),
+            hash: Some(secret.commitment(variant.commitment)),
+            num_partial_correct: Some(2),
+            num_fully_correct: Some(2),
+            guess: [Some(1), Some(2), Some(4), Some(3)],
+            variant,
+            encoding,
+        };
+        let name = match encoding {
+            InputEncoding::Bits => "mastermind",
+            InputEncoding::Packed => "mastermind-packed",
+        };
+        let num_private_inputs = MastermindCircuit::<F, 6, 4>::NUM_PRIVATE_INPUTS;
+        let report = underconstrained::check(circuit, num_private_inputs, rng).unwrap();
+        let analysis = &report.analysis;
+        println!(
+            "{:<17} {} witnesses: {} unused, {} undetermined, {} determined if non-zero, {} free",
+            name,
+            report.num_witnesses,
+            analysis.unused.len(),
+            analysis.undetermined.len(),
+            analysis.conditional.len(),
+            report.free.len(),
+        );
+        for perturbation in &report.free {
+            println!(
+                "  wire {} can be {} instead of {}",
+                perturbation.wire, perturbation.alternative, perturbation.value
+            );
+        }
+    }
+}
+
+/**
+ * Export the uniqueness queries of the response of the mastermind circuit over
+ * the scalar field F: for a tiny game of 2 colors and 2 pegs, with its inputs
+ * fixed and over all inputs, and for an example classic game with its inputs
+ * fixed. Each query is run with the solver if it is installed.
+ */
+fn run_smt<F: PrimeField + Absorb>(rng: &mut ChaCha20Rng, output: Option<&Path>, curve: &str) {
+    let variant = GameVariant::CLASSIC;
+    let tiny_secret = Secret::<F, 2>::new([0, 1], rng);
+    let tiny = MastermindCircuit::<F, 2, 2> {
+        code: tiny_secret.code.map(Some),
+        nonce: Some(tiny_secret.nonce),
+        hash: Some(tiny_secret.commitment(variant.commitment)),
+        num_partial_correct: Some(0),
+        num_fully_correct: Some(1),
+        guess: [Some(1), Some(1)],
+        variant,
+        encoding: InputEncoding::Bits,
     };
-    fill_in_hash(&mut test2);
+    let secret = Secret::<F, 4>::new([1, 2, 3, 4], rng);
+    let classic = MastermindCircuit::<F, 6, 4> {
+        code: secret.code.map(Some),
+        nonce: Some(secret.nonce),
+        hash: Some(secret.commitment(variant.commitment)),
+        num_partial_correct: Some(2),
+        num_fully_correct: Some(2),
+        guess: [Some(1), Some(2), Some(4), Some(3)],
+        variant,
+        encoding: InputEncoding::Bits,
+    };
+    let queries = [
+        ("mastermind-2x2", smt::mastermind_query(tiny.clone(), true)),
+        ("mastermind-2x2-all", smt::mastermind_query(tiny, false)),
+        ("mastermind", smt::mastermind_query(classic, true)),
+    ];
+
+    for (name, query) in queries {
+        let query = query.unwrap();
+        if let Some(dir) = output {
+            std::fs::create_dir_all(dir).unwrap();
+            let path = dir.join(format!("{name}-{curve}.smt2"));
+            std::fs::write(&path, &query).unwrap();
+            println!("{:<18} written to {}", name, path.display());
+        }
+        let verdict = match smt::solve(&query).unwrap() {
+            None => format!("{} is not on the PATH", smt::SOLVER),
+            Some(Verdict::Unique) => "unsat, the response is unique".to_string(),
+            Some(Verdict::NotUnique) => "sat, the response is NOT unique".to_string(),
+            Some(Verdict::Unknown(output)) => format!("unknown: {}", output.trim()),
+        };
+        println!("{:<18} {} bytes: {}", name, query.len(), verdict);
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    match &cli.command {
+        Some(Command::Ceremony { step }) => {
+            run_ceremony(step);
+            return;
+        }
+        Some(Command::Stats) => {
+            for curve in &cli.curves {
+                println!("Curve: {:?}", curve);
+                match curve {
+                    Curve::Bn254 => run_stats::<<Bn254 as Pairing>::ScalarField>(),
+                    Curve::Bls12_381 => run_stats::<<Bls12_381 as Pairing>::ScalarField>(),
+                }
+            }
+            return;
+        }
+        Some(Command::Underconstrained) => {
+            let rng = &mut ChaCha20Rng::seed_from_u64(42);
+            for curve in &cli.curves {
+                println!("Curve: {:?}", curve);
+                match curve {
+                    Curve::Bn254 => run_underconstrained::<<Bn254 as Pairing>::ScalarField>(rng),
+                    Curve::Bls12_381 => {
+                        run_underconstrained::<<Bls12_381 as Pairing>::ScalarField>(rng)
+                    }
+                }
+            }
+            return;
+        }
+        Some(Command::Smt { output }) => {
+            let rng = &mut ChaCha20Rng::seed_from_u64(42);
+            for curve in &cli.curves {
+                println!("Curve: {:?}", curve);
+                let name = curve.to_possible_value().unwrap().get_name().to_string();
+                let output = output.as_deref();
+                match curve {
+                    Curve::Bn254 => run_smt::<<Bn254 as Pairing>::ScalarField>(rng, output, &name),
+                    Curve::Bls12_381 => {
+                        run_smt::<<Bls12_381 as Pairing>::ScalarField>(rng, output, &name)
+                    }
+                }
+            }
+            return;
+        }
+        None => {}
+    }
 
     // The RNG needs to implement CryptoRng
     // - Use ChaCha20Rng with a fixed seed
     let rng = &mut ChaCha20Rng::seed_from_u64(42);
+    warn_seeded_keys("the keys of the example proofs are");
 
-    validate(rng, &test1);
-    validate(rng, &test2);
+    for curve in cli.curves {
+        let name = curve.to_possible_value().unwrap().get_name().to_string();
+        let output = |dir: &PathBuf| dir.join(format!("mastermind-{name}"));
+        if let Some(stem) = cli.export.as_ref().map(output) {
+            match curve {
+                Curve::Bn254 => export::<<Bn254 as Pairing>::ScalarField>(rng, &stem),
+                Curve::Bls12_381 => export::<<Bls12_381 as Pairing>::ScalarField>(rng, &stem),
+            }
+        }
+        for &backend in &cli.backends {
+            println!("Curve: {:?}, backend: {:?}", curve, backend);
+            let outputs = Outputs {
+                snarkjs: cli.snarkjs.as_ref().map(output),
+                solidity: cli.solidity.as_ref().map(output),
+            };
+            let circom = cli.circom.as_deref();
+            let keys = cli.keys.as_deref();
+            match curve {
+                Curve::Bn254 => run::<Bn254>(
+                    rng,
+                    backend,
+                    cli.compare,
+                    cli.rerandomize,
+                    circom,
+                    keys,
+                    &outputs,
+                ),
+                Curve::Bls12_381 => run::<Bls12_381>(
+                    rng,
+                    backend,
+                    cli.compare,
+                    cli.rerandomize,
+                    circom,
+                    keys,
+                    &outputs,
+                ),
+            }
+        }
+    }
 }
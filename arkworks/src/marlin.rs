@@ -0,0 +1,711 @@
+//! Marlin (<https://eprint.iacr.org/2019/1047>) as a universal-setup backend.
+//!
+//! Marlin has only been released for arkworks 0.3, so this module bridges the
+//! two versions: the circuit is synthesized with the arkworks 0.5 gadgets of
+//! this crate into its constraint matrices, which are replayed into an
+//! arkworks 0.3 constraint system for the Marlin indexer and prover. Keys and proofs are
+//! wrapped so that they serialize with ark-serialize 0.5 like the Groth16
+//! ones, and field elements are converted through their little-endian bytes,
+//! the scalar fields being the same on both sides.
+
+use std::{
+    fmt::{self, Debug, Display},
+    io::{Read, Write},
+    marker::PhantomData,
+};
+
+use ark_ec::pairing::Pairing;
+use ark_ec_03::PairingEngine;
+use ark_ff::{BigInteger, PrimeField};
+use ark_marlin::{IndexProverKey, IndexVerifierKey, UniversalSRS};
+use ark_poly_03::univariate::DensePolynomial;
+use ark_poly_commit_03::marlin_pc::MarlinKZG10;
+use ark_relations::r1cs::{
+    ConstraintMatrices, ConstraintSynthesizer, ConstraintSystem, SynthesisError, SynthesisMode,
+};
+use ark_relations_03::r1cs as r1cs_03;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_serialize_03::{
+    CanonicalDeserialize as _, CanonicalSerialize as _, SerializationError as SerializationError03,
+};
+use ark_snark::{UniversalSetupIndexError, UniversalSetupSNARK, SNARK};
+use ark_std::rand::{CryptoRng, RngCore, SeedableRng};
+use blake2::Blake2s;
+use rand_chacha::ChaCha20Rng;
+
+use crate::backend::Backend;
+
+/**
+ * A pairing-friendly curve of arkworks 0.5 together with the same curve in
+ * arkworks 0.3.
+ */
+pub trait MarlinCurve: Pairing {
+    type Engine: PairingEngine;
+}
+
+impl MarlinCurve for ark_bn254::Bn254 {
+    type Engine = ark_bn254_03::Bn254;
+}
+
+impl MarlinCurve for ark_bls12_381::Bls12_381 {
+    type Engine = ark_bls12_381_03::Bls12_381;
+}
+
+type Fr03<E> = <<E as MarlinCurve>::Engine as PairingEngine>::Fr;
+type Pc<E> = MarlinKZG10<<E as MarlinCurve>::Engine, DensePolynomial<Fr03<E>>>;
+type Inner<E> = ark_marlin::Marlin<Fr03<E>, Pc<E>, Blake2s>;
+
+/**
+ * The Marlin proof system over the curve E, with the KZG-based polynomial
+ * commitments of the Marlin paper.
+ */
+pub struct Marlin<E: MarlinCurve>(PhantomData<E>);
+
+#[derive(Debug)]
+pub enum MarlinError {
+    Synthesis(SynthesisError),
+    // Errors of the arkworks 0.3 side, which do not implement std::error::Error
+    Marlin(String),
+}
+
+impl Display for MarlinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarlinError::Synthesis(e) => write!(f, "synthesis error: {e}"),
+            MarlinError::Marlin(e) => write!(f, "marlin error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for MarlinError {}
+
+impl From<SynthesisError> for MarlinError {
+    fn from(e: SynthesisError) -> Self {
+        MarlinError::Synthesis(e)
+    }
+}
+
+fn marlin_error<E: Debug>(e: E) -> MarlinError {
+    MarlinError::Marlin(format!("{e:?}"))
+}
+
+/**
+ * The size of the largest circuit a universal setup supports, in the terms of
+ * the Marlin indexer: the number of constraints and of variables once the
+ * public inputs are padded, and the largest number of non-zero entries of the
+ * three matrices.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarlinBound {
+    pub num_constraints: usize,
+    pub num_variables: usize,
+    pub num_non_zero: usize,
+}
+
+impl MarlinBound {
+    /**
+     * The bound needed by the constraints, mirroring the padding of the
+     * indexer, which adds public inputs up to a power of two.
+     */
+    pub fn of<F: PrimeField>(matrices: &ConstraintMatrices<F>) -> Self {
+        MarlinBound {
+            num_constraints: matrices.num_constraints,
+            num_variables: matrices.num_instance_variables.next_power_of_two()
+                + matrices.num_witness_variables,
+            num_non_zero: matrices
+                .a_num_non_zero
+                .max(matrices.b_num_non_zero)
+                .max(matrices.c_num_non_zero),
+        }
+    }
+
+    /**
+     * The bound needed by the circuit, see `of`.
+     */
+    pub fn of_circuit<F: PrimeField, C: ConstraintSynthesizer<F>>(
+        circuit: C,
+    ) -> Result<Self, SynthesisError> {
+        let (matrices, _) = synthesize(circuit, true)?;
+        Ok(Self::of(&matrices))
+    }
+
+    /**
+     * The smallest bound within which are both bounds, to index several
+     * circuits against a single setup.
+     */
+    pub fn join(&self, other: &MarlinBound) -> Self {
+        MarlinBound {
+            num_constraints: self.num_constraints.max(other.num_constraints),
+            num_variables: self.num_variables.max(other.num_variables),
+            num_non_zero: self.num_non_zero.max(other.num_non_zero),
+        }
+    }
+
+    /**
+     * Whether every circuit within this bound is within the other one.
+     */
+    pub fn within(&self, other: &MarlinBound) -> bool {
+        self.num_constraints <= other.num_constraints
+            && self.num_variables <= other.num_variables
+            && self.num_non_zero <= other.num_non_zero
+    }
+}
+
+/**
+ * The universal structured reference string, shared by every circuit within
+ * the bound it was generated for.
+ */
+pub struct MarlinSrs<E: MarlinCurve> {
+    pub bound: MarlinBound,
+    srs: UniversalSRS<Fr03<E>, Pc<E>>,
+}
+
+impl<E: MarlinCurve> Clone for MarlinSrs<E> {
+    fn clone(&self) -> Self {
+        MarlinSrs {
+            bound: self.bound,
+            srs: self.srs.clone(),
+        }
+    }
+}
+
+impl<E: MarlinCurve> Debug for MarlinSrs<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MarlinSrs")
+            .field("bound", &self.bound)
+            .finish_non_exhaustive()
+    }
+}
+
+/**
+ * The index prover key, kept in its uncompressed arkworks 0.3 encoding: it
+ * holds its polynomials behind `Rc`s, so it cannot be shared between threads
+ * as the SNARK keys must.
+ */
+pub struct ProvingKey<E: MarlinCurve> {
+    bytes: Vec<u8>,
+    _curve: PhantomData<E>,
+}
+pub struct VerifyingKey<E: MarlinCurve>(IndexVerifierKey<Fr03<E>, Pc<E>>);
+pub struct Proof<E: MarlinCurve>(ark_marlin::Proof<Fr03<E>, Pc<E>>);
+
+impl<E: MarlinCurve> Clone for ProvingKey<E> {
+    fn clone(&self) -> Self {
+        ProvingKey {
+            bytes: self.bytes.clone(),
+            _curve: PhantomData,
+        }
+    }
+}
+
+impl<E: MarlinCurve> Clone for VerifyingKey<E> {
+    fn clone(&self) -> Self {
+        VerifyingKey(self.0.clone())
+    }
+}
+
+impl<E: MarlinCurve> Clone for Proof<E> {
+    fn clone(&self) -> Self {
+        // The Marlin proof is not Clone, but round-trips through its encoding.
+        let mut bytes = vec![];
+        self.0.serialize(&mut bytes).unwrap();
+        Proof(ark_marlin::Proof::deserialize(&bytes[..]).unwrap())
+    }
+}
+
+impl<E: MarlinCurve> Debug for Proof<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Proof")
+            .field(
+                "commitments",
+                &self.0.commitments.iter().map(Vec::len).sum::<usize>(),
+            )
+            .field("evaluations", &self.0.evaluations.len())
+            .finish_non_exhaustive()
+    }
+}
+
+fn serialization_error(e: SerializationError03) -> SerializationError {
+    match e {
+        SerializationError03::IoError(e) => SerializationError::IoError(e),
+        SerializationError03::NotEnoughSpace => SerializationError::NotEnoughSpace,
+        _ => SerializationError::InvalidData,
+    }
+}
+
+/**
+ * Implement the ark-serialize 0.5 traits of a wrapper with the ark-serialize
+ * 0.3 ones of the wrapped value. Arkworks 0.3 has no unchecked compressed
+ * encoding, so compressed values are always checked.
+ */
+macro_rules! bridge_serialization {
+    ($($wrapper:ident),*) => {$(
+        impl<E: MarlinCurve> Valid for $wrapper<E> {
+            fn check(&self) -> Result<(), SerializationError> {
+                Ok(())
+            }
+        }
+
+        impl<E: MarlinCurve> CanonicalSerialize for $wrapper<E> {
+            fn serialize_with_mode<W: Write>(
+                &self,
+                writer: W,
+                compress: Compress,
+            ) -> Result<(), SerializationError> {
+                match compress {
+                    Compress::Yes => self.0.serialize(writer),
+                    Compress::No => self.0.serialize_uncompressed(writer),
+                }
+                .map_err(serialization_error)
+            }
+
+            fn serialized_size(&self, compress: Compress) -> usize {
+                match compress {
+                    Compress::Yes => self.0.serialized_size(),
+                    Compress::No => self.0.uncompressed_size(),
+                }
+            }
+        }
+
+        impl<E: MarlinCurve> CanonicalDeserialize for $wrapper<E> {
+            fn deserialize_with_mode<R: Read>(
+                reader: R,
+                compress: Compress,
+                validate: Validate,
+            ) -> Result<Self, SerializationError> {
+                match (compress, validate) {
+                    (Compress::Yes, _) => ark_serialize_03::CanonicalDeserialize::deserialize(reader),
+                    (Compress::No, Validate::Yes) => {
+                        ark_serialize_03::CanonicalDeserialize::deserialize_uncompressed(reader)
+                    }
+                    (Compress::No, Validate::No) => {
+                        ark_serialize_03::CanonicalDeserialize::deserialize_unchecked(reader)
+                    }
+                }
+                .map($wrapper)
+                .map_err(serialization_error)
+            }
+        }
+    )*};
+}
+
+bridge_serialization!(VerifyingKey, Proof);
+
+impl<E: MarlinCurve> ProvingKey<E> {
+    fn new(pk: &IndexProverKey<Fr03<E>, Pc<E>>) -> Self {
+        let mut bytes = vec![];
+        pk.serialize_uncompressed(&mut bytes).unwrap();
+        ProvingKey {
+            bytes,
+            _curve: PhantomData,
+        }
+    }
+
+    fn decode(&self) -> Result<IndexProverKey<Fr03<E>, Pc<E>>, SerializationError> {
+        IndexProverKey::deserialize_unchecked(&self.bytes[..]).map_err(serialization_error)
+    }
+}
+
+impl<E: MarlinCurve> Valid for ProvingKey<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl<E: MarlinCurve> CanonicalSerialize for ProvingKey<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match compress {
+            Compress::Yes => self
+                .decode()?
+                .serialize(writer)
+                .map_err(serialization_error),
+            Compress::No => Ok(writer.write_all(&self.bytes)?),
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        match compress {
+            Compress::Yes => self.decode().unwrap().serialized_size(),
+            Compress::No => self.bytes.len(),
+        }
+    }
+}
+
+impl<E: MarlinCurve> CanonicalDeserialize for ProvingKey<E> {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let pk: IndexProverKey<Fr03<E>, Pc<E>> = match (compress, validate) {
+            (Compress::Yes, _) => IndexProverKey::deserialize(reader),
+            (Compress::No, Validate::Yes) => IndexProverKey::deserialize_uncompressed(reader),
+            (Compress::No, Validate::No) => IndexProverKey::deserialize_unchecked(reader),
+        }
+        .map_err(serialization_error)?;
+        Ok(ProvingKey::new(&pk))
+    }
+}
+
+fn convert<F: PrimeField, G: ark_ff_03::PrimeField>(x: F) -> G {
+    G::from_le_bytes_mod_order(&x.into_bigint().to_bytes_le())
+}
+
+/**
+ * Synthesize the circuit and read back its constraint matrices, and outside
+ * of the setup the assignment of its variables: the instance ones, starting
+ * with the constant one, followed by the witness ones, as they are numbered
+ * in the matrices.
+ */
+fn synthesize<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+    setup: bool,
+) -> Result<(ConstraintMatrices<F>, Option<Vec<F>>), SynthesisError> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    if setup {
+        cs.set_mode(SynthesisMode::Setup);
+    }
+    circuit.generate_constraints(cs.clone())?;
+    cs.finalize();
+    let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+    let witness = (!setup).then(|| {
+        let cs = cs.borrow().unwrap();
+        let mut witness = cs.instance_assignment.clone();
+        witness.extend(&cs.witness_assignment);
+        witness
+    });
+    Ok((matrices, witness))
+}
+
+/**
+ * The constraint matrices of a circuit, and optionally the assignment of its
+ * variables, as an arkworks 0.3 circuit over the field G.
+ */
+struct Replay<'a, F: PrimeField, G> {
+    matrices: &'a ConstraintMatrices<F>,
+    witness: Option<&'a [F]>,
+    _field: PhantomData<G>,
+}
+
+impl<'a, F: PrimeField, G> Replay<'a, F, G> {
+    fn new(matrices: &'a ConstraintMatrices<F>, witness: Option<&'a [F]>) -> Self {
+        Replay {
+            matrices,
+            witness,
+            _field: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, G: ark_ff_03::PrimeField> r1cs_03::ConstraintSynthesizer<G>
+    for Replay<'_, F, G>
+{
+    fn generate_constraints(
+        self,
+        cs: r1cs_03::ConstraintSystemRef<G>,
+    ) -> Result<(), r1cs_03::SynthesisError> {
+        let value = |var: usize| {
+            self.witness
+                .map(|w| convert(w[var]))
+                .ok_or(r1cs_03::SynthesisError::AssignmentMissing)
+        };
+
+        let matrices = self.matrices;
+        let num_variables = matrices.num_instance_variables + matrices.num_witness_variables;
+        let mut vars = vec![r1cs_03::Variable::One];
+        for var in 1..num_variables {
+            vars.push(if var < matrices.num_instance_variables {
+                cs.new_input_variable(|| value(var))?
+            } else {
+                cs.new_witness_variable(|| value(var))?
+            });
+        }
+
+        let lc = |row: &[(F, usize)]| {
+            r1cs_03::LinearCombination(
+                row.iter()
+                    .map(|&(coeff, var)| (convert(coeff), vars[var]))
+                    .collect(),
+            )
+        };
+        for i in 0..matrices.num_constraints {
+            cs.enforce_constraint(lc(&matrices.a[i]), lc(&matrices.b[i]), lc(&matrices.c[i]))?;
+        }
+        Ok(())
+    }
+}
+
+impl<E: MarlinCurve> Marlin<E> {
+    fn index_matrices(
+        pp: &MarlinSrs<E>,
+        matrices: &ConstraintMatrices<E::ScalarField>,
+    ) -> Result<(ProvingKey<E>, VerifyingKey<E>), UniversalSetupIndexError<MarlinBound, MarlinError>>
+    {
+        let bound = MarlinBound::of(matrices);
+        if !bound.within(&pp.bound) {
+            return Err(UniversalSetupIndexError::NeedLargerBound(bound));
+        }
+        let (pk, vk) = Inner::<E>::index(&pp.srs, Replay::new(matrices, None))
+            .map_err(|e| UniversalSetupIndexError::Other(marlin_error(e)))?;
+        Ok((ProvingKey::new(&pk), VerifyingKey(vk)))
+    }
+}
+
+impl<E: MarlinCurve> UniversalSetupSNARK<E::ScalarField> for Marlin<E> {
+    type ComputationBound = MarlinBound;
+    type PublicParameters = MarlinSrs<E>;
+
+    fn universal_setup<R: RngCore + CryptoRng>(
+        bound: &MarlinBound,
+        rng: &mut R,
+    ) -> Result<MarlinSrs<E>, MarlinError> {
+        let srs = Inner::<E>::universal_setup(
+            bound.num_constraints,
+            bound.num_variables,
+            bound.num_non_zero,
+            rng,
+        )
+        .map_err(marlin_error)?;
+        Ok(MarlinSrs { bound: *bound, srs })
+    }
+
+    fn index<C: ConstraintSynthesizer<E::ScalarField>, R: RngCore + CryptoRng>(
+        pp: &MarlinSrs<E>,
+        circuit: C,
+        _rng: &mut R,
+    ) -> Result<(ProvingKey<E>, VerifyingKey<E>), UniversalSetupIndexError<MarlinBound, MarlinError>>
+    {
+        let (matrices, _) =
+            synthesize(circuit, true).map_err(|e| UniversalSetupIndexError::Other(e.into()))?;
+        Self::index_matrices(pp, &matrices)
+    }
+}
+
+impl<E: MarlinCurve> SNARK<E::ScalarField> for Marlin<E> {
+    type ProvingKey = ProvingKey<E>;
+    type VerifyingKey = VerifyingKey<E>;
+    type Proof = Proof<E>;
+    type ProcessedVerifyingKey = VerifyingKey<E>;
+    type Error = MarlinError;
+
+    /**
+     * A universal setup with the exact bound of the circuit, then its index.
+     */
+    fn circuit_specific_setup<C: ConstraintSynthesizer<E::ScalarField>, R: RngCore + CryptoRng>(
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<(ProvingKey<E>, VerifyingKey<E>), MarlinError> {
+        let (matrices, _) = synthesize(circuit, true)?;
+        let pp = Self::universal_setup(&MarlinBound::of(&matrices), rng)?;
+        Self::index_matrices(&pp, &matrices).map_err(|e| match e {
+            UniversalSetupIndexError::NeedLargerBound(bound) => {
+                MarlinError::Marlin(format!("the circuit needs the larger bound {bound:?}"))
+            }
+            UniversalSetupIndexError::Other(e) => e,
+        })
+    }
+
+    fn prove<C: ConstraintSynthesizer<E::ScalarField>, R: RngCore + CryptoRng>(
+        pk: &ProvingKey<E>,
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<Proof<E>, MarlinError> {
+        let (matrices, witness) = synthesize(circuit, false)?;
+        let witness = witness.ok_or(SynthesisError::AssignmentMissing)?;
+        let pk = pk.decode().map_err(marlin_error)?;
+        Inner::<E>::prove(&pk, Replay::new(&matrices, Some(&witness)), rng)
+            .map(Proof)
+            .map_err(marlin_error)
+    }
+
+    fn process_vk(vk: &VerifyingKey<E>) -> Result<VerifyingKey<E>, MarlinError> {
+        Ok(vk.clone())
+    }
+
+    fn verify_with_processed_vk(
+        pvk: &VerifyingKey<E>,
+        public_input: &[E::ScalarField],
+        proof: &Proof<E>,
+    ) -> Result<bool, MarlinError> {
+        let public_input: Vec<Fr03<E>> = public_input.iter().copied().map(convert).collect();
+        // The verifier only uses its randomness to batch the opening checks.
+        let rng = &mut ChaCha20Rng::from_entropy();
+        Inner::<E>::verify(&pvk.0, &public_input, &proof.0, rng).map_err(marlin_error)
+    }
+}
+
+impl<E: MarlinCurve> Backend<E::ScalarField> for Marlin<E> {
+    const NAME: &'static str = "marlin";
+    const UNIVERSAL: bool = true;
+    type Params = MarlinSrs<E>;
+
+    /**
+     * Index the circuit against the SRS, which must have been generated for a
+     * bound the circuit is within.
+     */
+    fn keygen<C: ConstraintSynthesizer<E::ScalarField>, R: RngCore + CryptoRng>(
+        params: &MarlinSrs<E>,
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<(ProvingKey<E>, VerifyingKey<E>), MarlinError> {
+        Self::index(params, circuit, rng).map_err(|e| match e {
+            UniversalSetupIndexError::NeedLargerBound(bound) => MarlinError::Marlin(format!(
+                "the circuit needs the bound {bound:?}, larger than the {:?} of the setup",
+                params.bound
+            )),
+            UniversalSetupIndexError::Other(e) => e,
+        })
+    }
+
+    /**
+     * Marlin proofs are zero-knowledge but cannot be re-randomized; a fresh
+     * proof is needed instead.
+     */
+    fn rerandomize<R: RngCore + CryptoRng>(
+        _vk: &VerifyingKey<E>,
+        _proof: &Proof<E>,
+        _rng: &mut R,
+    ) -> Option<Proof<E>> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::{Bn254, Fr};
+    use ark_ff::{AdditiveGroup, Field};
+    use ark_relations::{
+        lc,
+        r1cs::{ConstraintSystemRef, Variable},
+    };
+
+    // Proves the knowledge of x such that x^(2^n) is the public input
+    #[derive(Clone)]
+    struct Power {
+        x: Option<Fr>,
+        n: usize,
+    }
+
+    impl Power {
+        fn output(&self) -> Fr {
+            (0..self.n).fold(self.x.unwrap(), |y, _| y.square())
+        }
+    }
+
+    impl ConstraintSynthesizer<Fr> for Power {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            let output = cs.new_input_variable(|| Ok(self.output()))?;
+            let mut y = self.x;
+            let mut var = cs.new_witness_variable(|| y.ok_or(SynthesisError::AssignmentMissing))?;
+            for i in 0..self.n {
+                y = y.map(|y| y.square());
+                let next = if i + 1 == self.n {
+                    output
+                } else {
+                    cs.new_witness_variable(|| y.ok_or(SynthesisError::AssignmentMissing))?
+                };
+                cs.enforce_constraint(lc!() + var, lc!() + var, lc!() + next)?;
+                var = next;
+            }
+            cs.enforce_constraint(lc!() + Variable::One, lc!() + var, lc!() + output)
+        }
+    }
+
+    #[test]
+    fn test_same_scalar_field() {
+        assert_eq!(
+            Fr::characteristic().to_vec(),
+            <Fr03<Bn254> as ark_ff_03::Field>::characteristic().to_vec()
+        );
+        let x = -Fr::from(5u64);
+        let y: Fr03<Bn254> = convert(x);
+        assert_eq!(y, -Fr03::<Bn254>::from(5u64));
+    }
+
+    #[test]
+    fn test_universal_setup() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let small = Power {
+            x: Some(Fr::from(3u64)),
+            n: 2,
+        };
+        let large = Power {
+            x: Some(Fr::from(3u64)),
+            n: 10,
+        };
+        let (matrices, _) = synthesize(large.clone(), true).unwrap();
+        let bound = MarlinBound::of(&matrices);
+        // One setup for both circuits
+        let pp = Marlin::<Bn254>::universal_setup(&bound, rng).unwrap();
+
+        for circuit in [small, large.clone()] {
+            let (pk, vk) = Marlin::<Bn254>::index(&pp, circuit.clone(), rng)
+                .ok()
+                .unwrap();
+            let proof = Marlin::<Bn254>::prove(&pk, circuit.clone(), rng).unwrap();
+            assert!(Marlin::<Bn254>::verify(&vk, &[circuit.output()], &proof).unwrap());
+            assert!(!Marlin::<Bn254>::verify(&vk, &[circuit.output().double()], &proof).unwrap());
+        }
+
+        // The keys and the proof keep working across an ark-serialize 0.5
+        // round trip.
+        let (pk, vk) = Marlin::<Bn254>::index(&pp, large.clone(), rng)
+            .ok()
+            .unwrap();
+        let mut bytes = vec![];
+        pk.serialize_compressed(&mut bytes).unwrap();
+        let pk = ProvingKey::<Bn254>::deserialize_compressed(&bytes[..]).unwrap();
+        bytes.clear();
+        vk.serialize_compressed(&mut bytes).unwrap();
+        let vk = VerifyingKey::<Bn254>::deserialize_compressed(&bytes[..]).unwrap();
+        let proof = Marlin::<Bn254>::prove(&pk, large.clone(), rng).unwrap();
+        bytes.clear();
+        proof.serialize_uncompressed(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), proof.uncompressed_size());
+        let proof = Proof::<Bn254>::deserialize_uncompressed(&bytes[..]).unwrap();
+        assert!(Marlin::<Bn254>::verify(&vk, &[large.output()], &proof.clone()).unwrap());
+
+        // A circuit beyond the bound of the setup
+        let larger = Power {
+            x: Some(Fr::from(3u64)),
+            n: 20,
+        };
+        assert!(matches!(
+            Marlin::<Bn254>::index(&pp, larger, rng),
+            Err(UniversalSetupIndexError::NeedLargerBound(_))
+        ));
+    }
+
+    #[test]
+    fn test_keygen_shared_srs() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let circuits = [2, 5, 10].map(|n| Power {
+            x: Some(Fr::from(3u64)),
+            n,
+        });
+        let bound = circuits
+            .iter()
+            .map(|c| MarlinBound::of_circuit(c.clone()).unwrap())
+            .fold(MarlinBound::default(), |bound, other| bound.join(&other));
+        assert_eq!(bound, MarlinBound::of_circuit(circuits[2].clone()).unwrap());
+        let srs = Marlin::<Bn254>::universal_setup(&bound, rng).unwrap();
+
+        for circuit in circuits {
+            let (pk, vk) = Marlin::<Bn254>::keygen(&srs, circuit.clone(), rng).unwrap();
+            let proof = Marlin::<Bn254>::prove(&pk, circuit.clone(), rng).unwrap();
+            assert!(Marlin::<Bn254>::verify(&vk, &[circuit.output()], &proof).unwrap());
+        }
+        let larger = Power {
+            x: Some(Fr::from(3u64)),
+            n: 20,
+        };
+        assert!(Marlin::<Bn254>::keygen(&srs, larger, rng).is_err());
+    }
+}
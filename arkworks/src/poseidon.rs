@@ -0,0 +1,219 @@
+//! Poseidon parameters shared with circomlib, so that the commitment to a code
+//! is the same in every implementation of the game.
+//!
+//! circomlib's `Poseidon(n)` template hashes n inputs with a permutation of
+//! width t = n + 1:
+//!
+//! - S-box x^5, 8 full rounds and the number of partial rounds listed in
+//!   [`PARTIAL_ROUNDS`] (60 for the 5 inputs of the standard game);
+//! - round constants and MDS matrix generated by the Grain LFSR of the Poseidon
+//!   reference implementation, for a 254-bit prime field;
+//! - the state starts as [0, inputs...] and the hash is the first element of
+//!   the state after a single permutation.
+//!
+//! The parameters are generated for the field the circuit is proven over, so
+//! the hash is only compatible with circom and halo2 over BN254. The BN254
+//! constants for the standard game are checked into
+//! `test-vectors/poseidon-circomlib-bn254.json`, which the halo2 crate reads.
+
+use ark_crypto_primitives::sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig};
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+
+/**
+ * The Poseidon parameters the commitment is computed with.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashScheme {
+    /// The circomlib parameters, shared with the circom and halo2 crates.
+    #[default]
+    Circomlib,
+    /// The original parameters of this crate (rate 2, x^17 S-box, 8 full and 31
+    /// partial rounds, see `get_poseidon_config`), with the hash truncated to
+    /// 64 bits. No other implementation computes this hash.
+    Legacy,
+}
+
+/// Number of full rounds for every width.
+pub const FULL_ROUNDS: usize = 8;
+
+/// Number of partial rounds for 1 to 16 inputs (t = 2 to 17), from circomlib.
+pub const PARTIAL_ROUNDS: [usize; 16] = [
+    56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68,
+];
+
+const ALPHA: u64 = 5;
+
+/**
+ * The parameters of circomlib's Poseidon for the given number of inputs, over
+ * the field F. Panics if the number of inputs is not between 1 and 16.
+ */
+pub fn circomlib_config<F: PrimeField>(num_inputs: usize) -> PoseidonConfig<F> {
+    assert!(
+        (1..=PARTIAL_ROUNDS.len()).contains(&num_inputs),
+        "circomlib Poseidon takes 1 to 16 inputs"
+    );
+    let rate = num_inputs;
+    let partial_rounds = PARTIAL_ROUNDS[num_inputs - 1];
+
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+        F::MODULUS_BIT_SIZE as u64,
+        rate,
+        FULL_ROUNDS as u64,
+        partial_rounds as u64,
+        0,
+    );
+    PoseidonConfig::<F> {
+        full_rounds: FULL_ROUNDS,
+        partial_rounds,
+        alpha: ALPHA,
+        ark,
+        mds,
+        rate,
+        capacity: 1,
+    }
+}
+
+/**
+ * Whether round r of the permutation is a full round.
+ */
+fn is_full_round<F: PrimeField>(config: &PoseidonConfig<F>, r: usize) -> bool {
+    let half = config.full_rounds / 2;
+    r < half || r >= half + config.partial_rounds
+}
+
+/**
+ * Compute circomlib's Poseidon hash of the inputs natively.
+ */
+pub fn circomlib_hash<F: PrimeField>(inputs: &[F]) -> F {
+    let config = circomlib_config::<F>(inputs.len());
+
+    let mut state = vec![F::zero()];
+    state.extend_from_slice(inputs);
+    for r in 0..(config.full_rounds + config.partial_rounds) {
+        for (s, c) in state.iter_mut().zip(&config.ark[r]) {
+            *s += c;
+        }
+        let sbox_width = if is_full_round(&config, r) {
+            state.len()
+        } else {
+            1
+        };
+        for s in &mut state[..sbox_width] {
+            *s = s.pow([config.alpha]);
+        }
+        state = config
+            .mds
+            .iter()
+            .map(|row| row.iter().zip(&state).map(|(m, s)| *m * s).sum())
+            .collect();
+    }
+    state[0]
+}
+
+/**
+ * Compute circomlib's Poseidon hash of the inputs in the circuit.
+ */
+pub fn circomlib_hash_var<F: PrimeField>(inputs: &[FpVar<F>]) -> Result<FpVar<F>, SynthesisError> {
+    let config = circomlib_config::<F>(inputs.len());
+
+    let mut state = vec![FpVar::<F>::zero()];
+    state.extend_from_slice(inputs);
+    for r in 0..(config.full_rounds + config.partial_rounds) {
+        for (s, c) in state.iter_mut().zip(&config.ark[r]) {
+            *s += *c;
+        }
+        let sbox_width = if is_full_round(&config, r) {
+            state.len()
+        } else {
+            1
+        };
+        for s in &mut state[..sbox_width] {
+            *s = s.pow_by_constant([config.alpha])?;
+        }
+        // The MDS matrix is constant, so mixing is linear and costs no constraints.
+        state = config
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&state)
+                    .fold(FpVar::<F>::zero(), |acc, (m, s)| acc + s * *m)
+            })
+            .collect();
+    }
+    Ok(state[0].clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use serde::Deserialize;
+    use std::str::FromStr;
+
+    #[derive(Deserialize)]
+    struct SharedParams {
+        full_rounds: usize,
+        partial_rounds: usize,
+        alpha: u64,
+        round_constants: Vec<Vec<String>>,
+        mds: Vec<Vec<String>>,
+    }
+
+    fn fr(s: &str) -> Fr {
+        Fr::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_known_answers() {
+        // From circomlibjs' test suite.
+        assert_eq!(
+            circomlib_hash(&[Fr::from(1u64), Fr::from(2u64)]),
+            fr("7853200120776062878684798364095072458815029376092732009249414926327459813530")
+        );
+        // From circom/README.md: the commitment of circom/examples/input1.json.
+        assert_eq!(
+            circomlib_hash(&[42u64, 1, 2, 3, 4].map(Fr::from)),
+            fr("14145853328434050885524917836371294524222664259619069142924766963653570759368")
+        );
+    }
+
+    #[test]
+    fn test_gadget_matches_native() {
+        let inputs = [512u64, 0, 3, 3, 5].map(Fr::from);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let vars = inputs
+            .iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+            .collect::<Vec<_>>();
+        let hash = circomlib_hash_var(&vars).unwrap();
+        assert_eq!(hash.value().unwrap(), circomlib_hash(&inputs));
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_shared_params() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../test-vectors/poseidon-circomlib-bn254.json"
+        );
+        let file = std::fs::File::open(path).unwrap();
+        let shared: SharedParams = serde_json::from_reader(file).unwrap();
+
+        let config = circomlib_config::<Fr>(5);
+        assert_eq!(shared.full_rounds, config.full_rounds);
+        assert_eq!(shared.partial_rounds, config.partial_rounds);
+        assert_eq!(shared.alpha, config.alpha);
+        let parse = |rows: &[Vec<String>]| -> Vec<Vec<Fr>> {
+            rows.iter()
+                .map(|row| row.iter().map(|x| fr(x)).collect())
+                .collect()
+        };
+        assert_eq!(parse(&shared.round_constants), config.ark);
+        assert_eq!(parse(&shared.mds), config.mds);
+    }
+}
@@ -0,0 +1,648 @@
+//! Export of a constraint system in the binary formats of the iden3 tools
+//! (circom and snarkjs), so that the same tools can inspect the arkworks
+//! circuits and the circom build, e.g. `snarkjs r1cs info mastermind.r1cs` or
+//! `snarkjs wtns check mastermind.r1cs mastermind.wtns`.
+//!
+//! - `.r1cs`: the constraints, see
+//!   <https://github.com/iden3/r1csfile/blob/master/doc/r1cs_bin_format.md>;
+//! - `.wtns`: the assignment of every wire (version 2 of the format);
+//! - `.sym`: one `label,wire,component,name` line per wire.
+//!
+//! Files produced by circom can be loaded back as an [`R1csCircuit`] and
+//! proven with any arkworks backend.
+//!
+//! The wires are the variables of the arkworks constraint system, in the same
+//! order: the constant one, the public inputs, then the witnesses. Arkworks
+//! does not tell private inputs apart from intermediate values, so every
+//! witness is counted as an intermediate wire, and there are no public outputs.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use ark_ff::{BigInteger, PrimeField};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef,
+    LinearCombination as ArkLinearCombination, SynthesisError, Variable,
+};
+
+const R1CS_MAGIC: &[u8; 4] = b"r1cs";
+const R1CS_VERSION: u32 = 1;
+const WTNS_MAGIC: &[u8; 4] = b"wtns";
+const WTNS_VERSION: u32 = 2;
+
+// Section types of the .r1cs file
+const R1CS_HEADER: u32 = 1;
+const R1CS_CONSTRAINTS: u32 = 2;
+const R1CS_WIRE_TO_LABEL: u32 = 3;
+
+// Section types of the .wtns file
+const WTNS_HEADER: u32 = 1;
+const WTNS_VALUES: u32 = 2;
+
+/// A linear combination of wires, as (wire, coefficient) pairs.
+pub type LinearCombination<F> = Vec<(usize, F)>;
+
+/**
+ * A constraint <a, w> * <b, w> = <c, w> over the wire assignment w.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constraint<F: PrimeField> {
+    pub a: LinearCombination<F>,
+    pub b: LinearCombination<F>,
+    pub c: LinearCombination<F>,
+}
+
+/**
+ * The contents of a `.r1cs` file.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct R1cs<F: PrimeField> {
+    // Number of wires, including the constant one
+    pub num_wires: usize,
+    pub num_public_outputs: usize,
+    pub num_public_inputs: usize,
+    pub num_private_inputs: usize,
+    pub constraints: Vec<Constraint<F>>,
+}
+
+impl<F: PrimeField> R1cs<F> {
+    /**
+     * Read the constraints out of a constraint system the circuit has been
+     * synthesized into. This finalizes the constraint system, and returns None
+     * if it was built without matrices.
+     */
+    pub fn from_constraint_system(cs: &ConstraintSystemRef<F>) -> Option<Self> {
+        cs.finalize();
+        let matrices = cs.to_matrices()?;
+        let lc = |row: &Vec<(F, usize)>| row.iter().map(|&(coeff, wire)| (wire, coeff)).collect();
+        let constraints = (0..matrices.num_constraints)
+            .map(|i| Constraint {
+                a: lc(&matrices.a[i]),
+                b: lc(&matrices.b[i]),
+                c: lc(&matrices.c[i]),
+            })
+            .collect();
+        Some(R1cs {
+            num_wires: matrices.num_instance_variables + matrices.num_witness_variables,
+            num_public_outputs: 0,
+            num_public_inputs: matrices.num_instance_variables - 1,
+            num_private_inputs: 0,
+            constraints,
+        })
+    }
+
+    /**
+     * Number of public wires, i.e. of public inputs of the SNARK: the public
+     * outputs followed by the public inputs.
+     */
+    pub fn num_public(&self) -> usize {
+        self.num_public_outputs + self.num_public_inputs
+    }
+
+    /**
+     * Whether the assignment of every wire satisfies all the constraints.
+     */
+    pub fn is_satisfied(&self, witness: &[F]) -> bool {
+        let eval = |lc: &LinearCombination<F>| -> Option<F> {
+            lc.iter()
+                .map(|&(wire, coeff)| witness.get(wire).map(|w| *w * coeff))
+                .sum()
+        };
+        witness.len() == self.num_wires
+            && witness.first() == Some(&F::one())
+            && self.constraints.iter().all(|c| {
+                matches!((eval(&c.a), eval(&c.b), eval(&c.c)), (Some(a), Some(b), Some(c)) if a * b == c)
+            })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut header = vec![];
+        write_field_header::<F>(&mut header);
+        for n in [
+            self.num_wires,
+            self.num_public_outputs,
+            self.num_public_inputs,
+            self.num_private_inputs,
+        ] {
+            header.extend(to_u32(n)?.to_le_bytes());
+        }
+        header.extend((self.num_wires as u64).to_le_bytes());
+        header.extend(to_u32(self.constraints.len())?.to_le_bytes());
+
+        let mut constraints = vec![];
+        for constraint in &self.constraints {
+            for lc in [&constraint.a, &constraint.b, &constraint.c] {
+                constraints.extend(to_u32(lc.len())?.to_le_bytes());
+                for &(wire, coeff) in lc {
+                    constraints.extend(to_u32(wire)?.to_le_bytes());
+                    constraints.extend(field_to_bytes(coeff));
+                }
+            }
+        }
+
+        // Every wire is its own label.
+        let labels = (0..self.num_wires as u64)
+            .flat_map(u64::to_le_bytes)
+            .collect::<Vec<_>>();
+
+        write_file(
+            &mut writer,
+            R1CS_MAGIC,
+            R1CS_VERSION,
+            &[
+                (R1CS_HEADER, header),
+                (R1CS_CONSTRAINTS, constraints),
+                (R1CS_WIRE_TO_LABEL, labels),
+            ],
+        )
+    }
+
+    pub fn read<R: Read>(reader: R) -> io::Result<Self> {
+        let sections = read_file(reader, R1CS_MAGIC, R1CS_VERSION)?;
+
+        let mut header = Cursor(section(&sections, R1CS_HEADER)?);
+        read_field_header::<F>(&mut header)?;
+        let num_wires = header.u32()? as usize;
+        let num_public_outputs = header.u32()? as usize;
+        let num_public_inputs = header.u32()? as usize;
+        let num_private_inputs = header.u32()? as usize;
+        let _num_labels = header.u64()?;
+        let num_constraints = header.u32()? as usize;
+
+        let mut body = Cursor(section(&sections, R1CS_CONSTRAINTS)?);
+        let mut lc = || -> io::Result<LinearCombination<F>> {
+            let len = body.u32()?;
+            (0..len)
+                .map(|_| Ok((body.u32()? as usize, body.field()?)))
+                .collect()
+        };
+        let constraints = (0..num_constraints)
+            .map(|_| {
+                Ok(Constraint {
+                    a: lc()?,
+                    b: lc()?,
+                    c: lc()?,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(R1cs {
+            num_wires,
+            num_public_outputs,
+            num_public_inputs,
+            num_private_inputs,
+            constraints,
+        })
+    }
+}
+
+/**
+ * The assignment of every wire of a constraint system the circuit has been
+ * synthesized into, or None if it was synthesized in setup mode.
+ */
+pub fn witness<F: PrimeField>(cs: &ConstraintSystemRef<F>) -> Option<Vec<F>> {
+    let cs = cs.borrow()?;
+    if cs.is_in_setup_mode() {
+        return None;
+    }
+    let mut witness = cs.instance_assignment.clone();
+    witness.extend(&cs.witness_assignment);
+    Some(witness)
+}
+
+pub fn write_wtns<F: PrimeField, W: Write>(witness: &[F], mut writer: W) -> io::Result<()> {
+    let mut header = vec![];
+    write_field_header::<F>(&mut header);
+    header.extend(to_u32(witness.len())?.to_le_bytes());
+    let values = witness
+        .iter()
+        .flat_map(|w| field_to_bytes(*w))
+        .collect::<Vec<_>>();
+    write_file(
+        &mut writer,
+        WTNS_MAGIC,
+        WTNS_VERSION,
+        &[(WTNS_HEADER, header), (WTNS_VALUES, values)],
+    )
+}
+
+pub fn read_wtns<F: PrimeField, R: Read>(reader: R) -> io::Result<Vec<F>> {
+    let sections = read_file(reader, WTNS_MAGIC, WTNS_VERSION)?;
+    let mut header = Cursor(section(&sections, WTNS_HEADER)?);
+    read_field_header::<F>(&mut header)?;
+    let len = header.u32()?;
+    let mut values = Cursor(section(&sections, WTNS_VALUES)?);
+    (0..len).map(|_| values.field()).collect()
+}
+
+/**
+ * Write the `.sym` file of a constraint system the circuit has been
+ * synthesized into: the constant one is `main.one`, the public inputs are
+ * `main.public[i]`, and every witness is named after the namespaces of the
+ * first constraint it appears in, when arkworks recorded them (which requires
+ * a `ConstraintLayer` to be installed while synthesizing).
+ */
+pub fn write_sym<F: PrimeField, W: Write>(
+    cs: &ConstraintSystemRef<F>,
+    r1cs: &R1cs<F>,
+    mut writer: W,
+) -> io::Result<()> {
+    let constraint_names = cs.constraint_names();
+    let num_instances = r1cs.num_public() + 1;
+
+    let mut first_use = vec![None; r1cs.num_wires];
+    for (i, constraint) in r1cs.constraints.iter().enumerate() {
+        for &(wire, _) in constraint
+            .a
+            .iter()
+            .chain(&constraint.b)
+            .chain(&constraint.c)
+        {
+            first_use[wire].get_or_insert(i);
+        }
+    }
+
+    for (wire, first_use) in first_use.into_iter().enumerate() {
+        let name = if wire == 0 {
+            "main.one".to_string()
+        } else if wire < num_instances {
+            format!("main.public[{}]", wire - 1)
+        } else {
+            let namespace = constraint_names
+                .as_ref()
+                .zip(first_use)
+                .map(|(names, i)| names[i].replace(',', ";"))
+                .filter(|name| !name.is_empty());
+            match namespace {
+                Some(namespace) => format!("main.{namespace}.witness[{}]", wire - num_instances),
+                None => format!("main.witness[{}]", wire - num_instances),
+            }
+        };
+        writeln!(writer, "{wire},{wire},0,{name}")?;
+    }
+    Ok(())
+}
+
+/**
+ * A circuit given by the contents of a `.r1cs` file, e.g. one compiled by
+ * circom, and the assignment of its wires when proving. The public wires are
+ * the public inputs of the SNARK, in order; the other wires are witnesses.
+ */
+#[derive(Clone, Debug)]
+pub struct R1csCircuit<F: PrimeField> {
+    pub r1cs: R1cs<F>,
+    // Assignment of every wire, including the constant one; None for the setup
+    pub witness: Option<Vec<F>>,
+}
+
+impl<F: PrimeField> R1csCircuit<F> {
+    /**
+     * The public inputs a verifier passes to the SNARK, or None without a
+     * witness.
+     */
+    pub fn public_inputs(&self) -> Option<Vec<F>> {
+        let witness = self.witness.as_ref()?;
+        witness.get(1..=self.r1cs.num_public()).map(<[F]>::to_vec)
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for R1csCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if matches!(&self.witness, Some(w) if w.len() != self.r1cs.num_wires) {
+            return Err(SynthesisError::AssignmentMissing);
+        }
+        let value = |wire: usize| {
+            self.witness
+                .as_ref()
+                .map(|w| w[wire])
+                .ok_or(SynthesisError::AssignmentMissing)
+        };
+
+        let mut wires = vec![Variable::One];
+        for wire in 1..self.r1cs.num_wires {
+            wires.push(if wire <= self.r1cs.num_public() {
+                cs.new_input_variable(|| value(wire))?
+            } else {
+                cs.new_witness_variable(|| value(wire))?
+            });
+        }
+
+        let lc = |terms: &LinearCombination<F>| -> Result<ArkLinearCombination<F>, SynthesisError> {
+            let mut lc = ArkLinearCombination::zero();
+            for &(wire, coeff) in terms {
+                let var = *wires.get(wire).ok_or(SynthesisError::AssignmentMissing)?;
+                lc += (coeff, var);
+            }
+            Ok(lc)
+        };
+        for constraint in &self.r1cs.constraints {
+            cs.enforce_constraint(lc(&constraint.a)?, lc(&constraint.b)?, lc(&constraint.c)?)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Synthesize the circuit and write `<stem>.r1cs`, `<stem>.wtns` and
+ * `<stem>.sym`.
+ */
+pub fn export<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+    stem: impl AsRef<Path>,
+) -> io::Result<()> {
+    let stem = stem.as_ref();
+    let cs = ConstraintSystem::<F>::new_ref();
+    circuit
+        .generate_constraints(cs.clone())
+        .map_err(|e| io::Error::other(format!("synthesis failed: {e}")))?;
+    let r1cs = R1cs::from_constraint_system(&cs)
+        .ok_or_else(|| io::Error::other("the constraint system has no matrices"))?;
+    let witness = witness(&cs).ok_or_else(|| io::Error::other("the witness is missing"))?;
+
+    let create = |ext: &str| File::create(stem.with_extension(ext)).map(BufWriter::new);
+    r1cs.write(create("r1cs")?)?;
+    write_wtns(&witness, create("wtns")?)?;
+    write_sym(&cs, &r1cs, create("sym")?)?;
+    Ok(())
+}
+
+/**
+ * Read `<stem>.r1cs` and `<stem>.wtns`, e.g. the output of
+ * `circom <stem>.circom --r1cs` and of its witness generator.
+ */
+pub fn import<F: PrimeField>(stem: impl AsRef<Path>) -> io::Result<R1csCircuit<F>> {
+    let stem = stem.as_ref();
+    let open = |ext: &str| File::open(stem.with_extension(ext)).map(BufReader::new);
+    Ok(R1csCircuit {
+        r1cs: R1cs::read(open("r1cs")?)?,
+        witness: Some(read_wtns(open("wtns")?)?),
+    })
+}
+
+/**
+ * Size in bytes of a field element in the files: the modulus, rounded up to
+ * 64-bit words.
+ */
+fn field_size<F: PrimeField>() -> usize {
+    F::MODULUS.to_bytes_le().len()
+}
+
+fn field_to_bytes<F: PrimeField>(f: F) -> Vec<u8> {
+    f.into_bigint().to_bytes_le()
+}
+
+fn to_u32(n: usize) -> io::Result<u32> {
+    u32::try_from(n).map_err(|_| invalid("value does not fit in 32 bits"))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn write_field_header<F: PrimeField>(out: &mut Vec<u8>) {
+    out.extend((field_size::<F>() as u32).to_le_bytes());
+    out.extend(F::MODULUS.to_bytes_le());
+}
+
+fn read_field_header<F: PrimeField>(cursor: &mut Cursor) -> io::Result<()> {
+    let size = cursor.u32()? as usize;
+    if size != field_size::<F>() || cursor.bytes(size)? != F::MODULUS.to_bytes_le() {
+        return Err(invalid("the file is for another field"));
+    }
+    Ok(())
+}
+
+fn write_file<W: Write>(
+    writer: &mut W,
+    magic: &[u8; 4],
+    version: u32,
+    sections: &[(u32, Vec<u8>)],
+) -> io::Result<()> {
+    writer.write_all(magic)?;
+    writer.write_all(&version.to_le_bytes())?;
+    writer.write_all(&(sections.len() as u32).to_le_bytes())?;
+    for (kind, content) in sections {
+        writer.write_all(&kind.to_le_bytes())?;
+        writer.write_all(&(content.len() as u64).to_le_bytes())?;
+        writer.write_all(content)?;
+    }
+    writer.flush()
+}
+
+/**
+ * Read the sections of a file, in the order they appear in.
+ */
+fn read_file<R: Read>(
+    mut reader: R,
+    magic: &[u8; 4],
+    version: u32,
+) -> io::Result<Vec<(u32, Vec<u8>)>> {
+    let mut contents = vec![];
+    reader.read_to_end(&mut contents)?;
+    let mut cursor = Cursor(&contents);
+    if cursor.bytes(4)? != magic {
+        return Err(invalid("wrong magic number"));
+    }
+    if cursor.u32()? != version {
+        return Err(invalid("unsupported version"));
+    }
+    let num_sections = cursor.u32()?;
+    (0..num_sections)
+        .map(|_| {
+            let kind = cursor.u32()?;
+            let size = usize::try_from(cursor.u64()?).map_err(|_| invalid("section too large"))?;
+            Ok((kind, cursor.bytes(size)?.to_vec()))
+        })
+        .collect()
+}
+
+fn section(sections: &[(u32, Vec<u8>)], kind: u32) -> io::Result<&[u8]> {
+    sections
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, content)| content.as_slice())
+        .ok_or_else(|| invalid("missing section"))
+}
+
+/**
+ * Little-endian reader over the bytes of a section.
+ */
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn bytes(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(invalid("unexpected end of file"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    /// A field element, which must be reduced.
+    fn field<F: PrimeField>(&mut self) -> io::Result<F> {
+        let bytes = self.bytes(field_size::<F>())?;
+        let f = F::from_le_bytes_mod_order(bytes);
+        if field_to_bytes(f) != bytes {
+            return Err(invalid("field element out of range"));
+        }
+        Ok(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{secret::Secret, GameVariant, InputEncoding, MastermindCircuit};
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::Groth16;
+    use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use std::str::FromStr;
+
+    fn circuit() -> MastermindCircuit<Fr, 6, 4> {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let secret = Secret::<Fr, 4>::new([1, 2, 3, 4], rng);
+        MastermindCircuit {
+            code: secret.code.map(Some),
+            nonce: Some(secret.nonce),
+            hash: Some(secret.commitment(GameVariant::CLASSIC.commitment)),
+            num_partial_correct: Some(2),
+            num_fully_correct: Some(2),
+            guess: [Some(1), Some(2), Some(4), Some(3)],
+            variant: GameVariant::CLASSIC,
+            encoding: InputEncoding::Packed,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let circuit = circuit();
+        let public_inputs = circuit.public_inputs().unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        let r1cs = R1cs::from_constraint_system(&cs).unwrap();
+        let witness = witness(&cs).unwrap();
+        assert_eq!(r1cs.num_public_inputs, public_inputs.len());
+        assert_eq!(r1cs.constraints.len(), cs.num_constraints());
+        assert_eq!(witness[1..=public_inputs.len()], public_inputs);
+        assert!(r1cs.is_satisfied(&witness));
+
+        let mut r1cs_bytes = vec![];
+        r1cs.write(&mut r1cs_bytes).unwrap();
+        let mut wtns_bytes = vec![];
+        write_wtns(&witness, &mut wtns_bytes).unwrap();
+        assert_eq!(&r1cs_bytes[..4], b"r1cs");
+        assert_eq!(&wtns_bytes[..4], b"wtns");
+
+        let read = R1cs::<Fr>::read(r1cs_bytes.as_slice()).unwrap();
+        let read_witness = read_wtns::<Fr, _>(wtns_bytes.as_slice()).unwrap();
+        assert_eq!(read, r1cs);
+        assert_eq!(read_witness, witness);
+
+        // A wrong assignment of any public input is caught.
+        let mut tampered = read_witness.clone();
+        tampered[1] += Fr::from(1u64);
+        assert!(!read.is_satisfied(&tampered));
+
+        let mut sym = vec![];
+        write_sym(&cs, &r1cs, &mut sym).unwrap();
+        let sym = String::from_utf8(sym).unwrap();
+        assert_eq!(sym.lines().count(), r1cs.num_wires);
+        assert!(sym.starts_with("0,0,0,main.one\n1,1,0,main.public[0]\n"));
+    }
+
+    #[test]
+    fn test_wrong_field() {
+        let mut bytes = vec![];
+        write_wtns(&[Fr::from(1u64)], &mut bytes).unwrap();
+        assert!(read_wtns::<ark_bls12_381::Fr, _>(bytes.as_slice()).is_err());
+        assert!(read_wtns::<Fr, _>(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        let stem =
+            std::env::temp_dir().join(format!("mastermind-test-export-{}", std::process::id()));
+        export(circuit(), &stem).unwrap();
+        let circuit = import::<Fr>(&stem).unwrap();
+        assert!(circuit.r1cs.is_satisfied(circuit.witness.as_ref().unwrap()));
+        for ext in ["r1cs", "wtns", "sym"] {
+            std::fs::remove_file(stem.with_extension(ext)).unwrap();
+        }
+    }
+
+    fn prove_and_verify(circuit: &R1csCircuit<Fr>) {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let public_inputs = circuit.public_inputs().unwrap();
+        let setup = R1csCircuit {
+            witness: None,
+            ..circuit.clone()
+        };
+        let (pk, vk) = Groth16::<Bn254>::setup(setup, rng).unwrap();
+        let proof = Groth16::<Bn254>::prove(&pk, circuit.clone(), rng).unwrap();
+        assert!(Groth16::<Bn254>::verify(&vk, &public_inputs, &proof).unwrap());
+
+        let mut tampered = public_inputs;
+        tampered[0] += Fr::from(1u64);
+        assert!(!Groth16::<Bn254>::verify(&vk, &tampered, &proof).unwrap());
+    }
+
+    #[test]
+    fn test_r1cs_circuit() {
+        let game = circuit();
+        let public_inputs = game.public_inputs().unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        game.generate_constraints(cs.clone()).unwrap();
+        let circuit = R1csCircuit {
+            r1cs: R1cs::from_constraint_system(&cs).unwrap(),
+            witness: witness(&cs),
+        };
+        assert_eq!(circuit.public_inputs().unwrap(), public_inputs);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.clone().generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(cs.num_instance_variables(), public_inputs.len() + 1);
+        prove_and_verify(&circuit);
+
+        // A witness of the wrong size is rejected.
+        let mut short = circuit;
+        short.witness.as_mut().unwrap().pop();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(short.generate_constraints(cs).is_err());
+    }
+
+    /**
+     * Proves the circom circuit on `examples/input1.json`. The fixtures are
+     * generated with circom by `test-vectors/circom/generate.sh`, which the CI
+     * runs before this test.
+     */
+    #[test]
+    #[ignore = "needs circom to generate the fixtures with test-vectors/circom/generate.sh"]
+    fn test_circom_fixture() {
+        let stem = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../test-vectors/circom/mastermind"
+        );
+        let circuit = import::<Fr>(stem).unwrap();
+        // Outputs hash, correct_pegs and partial_pegs, then the public guess.
+        let hash = "14145853328434050885524917836371294524222664259619069142924766963653570759368";
+        let mut expected = vec![Fr::from_str(hash).unwrap()];
+        expected.extend([1u64, 2, 0, 2, 4, 3].map(Fr::from));
+        assert_eq!(circuit.r1cs.num_public_outputs, 3);
+        assert_eq!(circuit.public_inputs().unwrap(), expected);
+        assert!(circuit.r1cs.is_satisfied(circuit.witness.as_ref().unwrap()));
+        prove_and_verify(&circuit);
+    }
+}
@@ -0,0 +1,216 @@
+//! The codemaker's secret: the code and the nonce it is committed with.
+//!
+//! The nonce is the only thing that keeps the commitment hiding: a standard
+//! game has 6^4 = 1296 codes, so anyone can hash every code under a known or
+//! guessable nonce. The nonce must therefore be a uniformly random field
+//! element, drawn from a CSPRNG or derived from a seed phrase with
+//! [`derive_nonce`].
+//!
+//! A secret is stored as a JSON file with the nonce as a decimal string and the
+//! pegs as strings, in the same format as the halo2 crate's `data/init.in`:
+//!
+//! ```json
+//! { "nonce": "1234", "pegs": ["1", "2", "3", "4"] }
+//! ```
+//!
+//! Other fields, such as the halo2 `variant`, are ignored.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_std::rand::{CryptoRng, Rng, RngCore};
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{compute_commitment, GameVariant, HashScheme};
+
+/// Number of PBKDF2 iterations when deriving a nonce from a seed phrase.
+pub const NONCE_KDF_ITERATIONS: u32 = 100_000;
+
+/// Prefix of the PBKDF2 salt, followed by the game number in decimal.
+pub const NONCE_KDF_SALT: &str = "mastermind/nonce/";
+
+/**
+ * Derive the nonce of the given game from a seed phrase: 64 bytes of
+ * PBKDF2-HMAC-SHA256 of the phrase, with the salt "mastermind/nonce/<game>",
+ * read as a little-endian integer and reduced modulo the field order. The
+ * halo2 crate derives the same nonce over BN254.
+ *
+ * Every game must use its own number, as a nonce reused with the same code
+ * gives the same commitment.
+ */
+pub fn derive_nonce<F: PrimeField>(phrase: &str, game: u64) -> F {
+    let salt = format!("{NONCE_KDF_SALT}{game}");
+    let mut bytes = [0u8; 64];
+    pbkdf2_hmac::<Sha256>(
+        phrase.as_bytes(),
+        salt.as_bytes(),
+        NONCE_KDF_ITERATIONS,
+        &mut bytes,
+    );
+    F::from_le_bytes_mod_order(&bytes)
+}
+
+/**
+ * A code and the nonce it is committed with.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Secret<F: PrimeField, const SZ: usize> {
+    pub code: [u64; SZ],
+    pub nonce: F,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SecretFile {
+    nonce: String,
+    pegs: Vec<String>,
+}
+
+impl<F: PrimeField, const SZ: usize> Secret<F, SZ> {
+    /**
+     * Pair the code with a fresh nonce drawn from the RNG.
+     */
+    pub fn new<R: RngCore + CryptoRng>(code: [u64; SZ], rng: &mut R) -> Self {
+        Secret {
+            code,
+            nonce: F::rand(rng),
+        }
+    }
+
+    /**
+     * Draw a legal code for the variant and a nonce from the RNG.
+     */
+    pub fn random<const NPEGS: usize, R: RngCore + CryptoRng>(
+        variant: &GameVariant,
+        rng: &mut R,
+    ) -> Self {
+        let num_colors = variant.num_colors::<NPEGS>();
+        let code = loop {
+            let code = [(); SZ].map(|_| rng.gen_range(0..num_colors));
+            if variant.is_legal_code::<NPEGS>(&code) {
+                break code;
+            }
+        };
+        Self::new(code, rng)
+    }
+
+    /**
+     * Pair the code with the nonce derived from the seed phrase for the given
+     * game, see `derive_nonce`.
+     */
+    pub fn from_seed_phrase(code: [u64; SZ], phrase: &str, game: u64) -> Self {
+        Secret {
+            code,
+            nonce: derive_nonce(phrase, game),
+        }
+    }
+
+    /**
+     * The public commitment to the code, under the given hash scheme.
+     */
+    pub fn commitment(&self, scheme: HashScheme) -> F
+    where
+        F: Absorb,
+    {
+        compute_commitment(self.nonce, &self.code, scheme)
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let parsed: SecretFile = serde_json::from_reader(BufReader::new(file))?;
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let nonce =
+            F::from_str(&parsed.nonce).map_err(|_| invalid("nonce is not a field element"))?;
+        let code = parsed
+            .pegs
+            .iter()
+            .map(|peg| peg.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid("pegs must be integers"))?
+            .try_into()
+            .map_err(|_| invalid("wrong number of pegs"))?;
+        Ok(Secret { code, nonce })
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = File::create(path)?;
+        let secret = SecretFile {
+            nonce: self.nonce.to_string(),
+            pegs: self.code.iter().map(|peg| peg.to_string()).collect(),
+        };
+        serde_json::to_writer_pretty(BufWriter::new(file), &secret)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_ff::BigInteger;
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use std::str::FromStr;
+
+    #[derive(Deserialize)]
+    struct NonceVector {
+        phrase: String,
+        game: u64,
+        nonce: String,
+    }
+
+    #[derive(Deserialize)]
+    struct NonceVectorFile {
+        vectors: Vec<NonceVector>,
+    }
+
+    #[test]
+    fn test_random() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let a = Secret::<Fr, 4>::random::<6, _>(&GameVariant::BULLS_AND_COWS, rng);
+        let b = Secret::<Fr, 4>::random::<6, _>(&GameVariant::BULLS_AND_COWS, rng);
+        assert!(GameVariant::BULLS_AND_COWS.is_legal_code::<6>(&a.code));
+        assert_ne!(a.nonce, b.nonce);
+        // The nonce uses the whole field, not just 64 bits.
+        assert!(a.nonce.into_bigint().num_bits() > 64);
+    }
+
+    #[test]
+    fn test_derive_nonce() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../test-vectors/secret.json");
+        let file: NonceVectorFile = serde_json::from_reader(File::open(path).unwrap()).unwrap();
+        for vector in file.vectors {
+            assert_eq!(
+                derive_nonce::<Fr>(&vector.phrase, vector.game),
+                Fr::from_str(&vector.nonce).unwrap()
+            );
+        }
+        assert_ne!(
+            derive_nonce::<Fr>("correct horse", 0),
+            derive_nonce::<Fr>("correct horse", 1)
+        );
+    }
+
+    #[test]
+    fn test_read_write() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let secret = Secret::<Fr, 4>::new([1, 2, 3, 4], rng);
+        let path = std::env::temp_dir().join(format!(
+            "mastermind-test-secret-{}.json",
+            std::process::id()
+        ));
+        secret.write(&path).unwrap();
+        assert_eq!(Secret::read(&path).unwrap(), secret);
+        std::fs::remove_file(&path).unwrap();
+
+        // The halo2 crate's secret files can be read too.
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../halo2/data/init.in");
+        let secret = Secret::<Fr, 4>::read(path).unwrap();
+        assert_eq!(secret.code, [0, 3, 3, 5]);
+    }
+}
@@ -0,0 +1,506 @@
+//! Export of the constraints of a circuit as SMT-LIB, to ask a solver whether
+//! its outputs are unique.
+//!
+//! The constraints are written in the finite field theory of cvc5 (`QF_FF`):
+//! every wire is a constant of the sort `F`, the field of the circuit, and
+//! every constraint `<a, w> * <b, w> = <c, w>` an assertion. A uniqueness query
+//! declares two copies of the wires, which share the input wires, asserts the
+//! constraints on both copies, and asserts that some output wire differs. The
+//! query is unsatisfiable exactly when the inputs determine the outputs: a
+//! `sat` answer is a witness of two valid proofs with different outputs for
+//! the same inputs.
+//!
+//! For the mastermind circuit, the inputs are the hash, the guess, the code and
+//! the nonce, and the outputs are the two counts of the response. This is the
+//! property the under-constrained analysis of `underconstrained` approximates,
+//! decided over the whole circuit at once.
+
+use std::fmt::Write as _;
+use std::io::{self, Write as _};
+use std::process::{Command, Stdio};
+
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisError, SynthesisMode};
+
+use crate::r1cs_file::{self, LinearCombination, R1cs};
+use crate::{response_public_inputs, InputEncoding, MastermindCircuit};
+
+/// The solver `solve` runs, which must be on the PATH.
+pub const SOLVER: &str = "cvc5";
+
+/**
+ * What the solver answered to a uniqueness query.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    // unsat: the inputs determine the outputs
+    Unique,
+    // sat: two assignments share the inputs but not the outputs
+    NotUnique,
+    // Anything else, with the output of the solver
+    Unknown(String),
+}
+
+/**
+ * A field element as an SMT-LIB literal of the sort `F`.
+ */
+fn literal<F: PrimeField>(value: F) -> String {
+    format!("(as ff{} F)", value.into_bigint())
+}
+
+/**
+ * A linear combination as an SMT-LIB term, the name of each wire given by
+ * `name`. The constant one wire is written as a literal.
+ */
+fn linear_combination<F: PrimeField>(
+    lc: &LinearCombination<F>,
+    name: impl Fn(usize) -> String,
+) -> String {
+    let terms: Vec<String> = lc
+        .iter()
+        .map(|&(wire, coeff)| match (wire, coeff.is_one()) {
+            (0, _) => literal(coeff),
+            (_, true) => name(wire),
+            (_, false) => format!("(ff.mul {} {})", literal(coeff), name(wire)),
+        })
+        .collect();
+    match terms.len() {
+        0 => literal(F::zero()),
+        1 => terms[0].clone(),
+        _ => format!("(ff.add {})", terms.join(" ")),
+    }
+}
+
+/**
+ * The SMT-LIB query asking for two assignments of the wires which both satisfy
+ * the constraints, agree on the `shared` wires, and differ on at least one of
+ * the `outputs` wires. The shared wires are named `w<wire>`, and the others
+ * `a<wire>` in the first copy and `b<wire>` in the second.
+ *
+ * The wires of `fixed` are asserted to have the given value in both copies,
+ * which asks the question for one set of inputs instead of all of them. The
+ * query is unsatisfiable if and only if the outputs are unique.
+ */
+pub fn uniqueness_query<F: PrimeField>(
+    r1cs: &R1cs<F>,
+    shared: &[usize],
+    outputs: &[usize],
+    fixed: &[(usize, F)],
+) -> String {
+    let mut is_shared = vec![false; r1cs.num_wires];
+    for &wire in shared {
+        is_shared[wire] = true;
+    }
+    let name = |copy: char, wire: usize| {
+        if is_shared[wire] {
+            format!("w{wire}")
+        } else {
+            format!("{copy}{wire}")
+        }
+    };
+
+    let mut query = String::new();
+    writeln!(
+        query,
+        "; {} wires, {} constraints, {} shared wires, {} outputs",
+        r1cs.num_wires,
+        r1cs.constraints.len(),
+        shared.len(),
+        outputs.len()
+    )
+    .unwrap();
+    writeln!(query, "(set-logic QF_FF)").unwrap();
+    writeln!(query, "(define-sort F () (_ FiniteField {}))", F::MODULUS).unwrap();
+    for wire in (1..r1cs.num_wires).filter(|&wire| is_shared[wire]) {
+        writeln!(query, "(declare-const w{wire} F)").unwrap();
+    }
+    for copy in ['a', 'b'] {
+        for wire in (1..r1cs.num_wires).filter(|&wire| !is_shared[wire]) {
+            writeln!(query, "(declare-const {copy}{wire} F)").unwrap();
+        }
+    }
+
+    for copy in ['a', 'b'] {
+        writeln!(query, "; constraints of the copy {copy}").unwrap();
+        for constraint in &r1cs.constraints {
+            let lc = |lc| linear_combination(lc, |wire| name(copy, wire));
+            writeln!(
+                query,
+                "(assert (= (ff.mul {} {}) {}))",
+                lc(&constraint.a),
+                lc(&constraint.b),
+                lc(&constraint.c)
+            )
+            .unwrap();
+        }
+    }
+
+    if !fixed.is_empty() {
+        writeln!(query, "; fixed wires").unwrap();
+    }
+    for &(wire, value) in fixed {
+        let copies: &[char] = if is_shared[wire] { &['a'] } else { &['a', 'b'] };
+        for &copy in copies {
+            writeln!(
+                query,
+                "(assert (= {} {}))",
+                name(copy, wire),
+                literal(value)
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(query, "; some output differs").unwrap();
+    let differences: Vec<String> = outputs
+        .iter()
+        .map(|&wire| format!("(not (= {} {}))", name('a', wire), name('b', wire)))
+        .collect();
+    let differs = match differences.len() {
+        0 => "false".to_string(),
+        1 => differences[0].clone(),
+        _ => format!("(or {})", differences.join(" ")),
+    };
+    writeln!(query, "(assert {differs})").unwrap();
+    writeln!(query, "(check-sat)").unwrap();
+    query
+}
+
+/**
+ * The uniqueness query of the response of the mastermind circuit: can a prover
+ * give two different responses for the same code, nonce, hash and guess? The
+ * circuit is synthesized with the bits encoding, where the response has public
+ * inputs of its own.
+ *
+ * With `fix_inputs`, the inputs are fixed to the values of the circuit, which
+ * must then all be set. Otherwise the query is over all inputs, and the values
+ * are not needed.
+ */
+pub fn mastermind_query<F: PrimeField + Absorb, const NPEGS: usize, const SZ: usize>(
+    circuit: MastermindCircuit<F, NPEGS, SZ>,
+    fix_inputs: bool,
+) -> Result<String, SynthesisError> {
+    let circuit = MastermindCircuit {
+        encoding: InputEncoding::Bits,
+        ..circuit
+    };
+    let cs = ConstraintSystem::<F>::new_ref();
+    if !fix_inputs {
+        cs.set_mode(SynthesisMode::Setup);
+    }
+    circuit.generate_constraints(cs.clone())?;
+    let r1cs = R1cs::from_constraint_system(&cs).ok_or(SynthesisError::MissingCS)?;
+
+    // The outputs are the bits of the response, after the hash; the guess
+    // follows them. Wire 0 is the constant one, so public input i is wire
+    // i + 1. The private inputs are the first witnesses.
+    let (partial, fully) = response_public_inputs();
+    let response = partial.start + 1..fully.end + 1;
+    let first_witness = 1 + r1cs.num_public();
+    let private =
+        first_witness..first_witness + MastermindCircuit::<F, NPEGS, SZ>::NUM_PRIVATE_INPUTS;
+    let shared: Vec<usize> = std::iter::once(1)
+        .chain(response.end..first_witness)
+        .chain(private)
+        .collect();
+    let outputs: Vec<usize> = response.collect();
+
+    let fixed = if fix_inputs {
+        let witness = r1cs_file::witness(&cs).ok_or(SynthesisError::AssignmentMissing)?;
+        shared.iter().map(|&wire| (wire, witness[wire])).collect()
+    } else {
+        vec![]
+    };
+    Ok(uniqueness_query(&r1cs, &shared, &outputs, &fixed))
+}
+
+/**
+ * Run the query with the solver on the PATH, or return None if there is none.
+ */
+pub fn solve(query: &str) -> io::Result<Option<Verdict>> {
+    let mut child = match Command::new(SOLVER)
+        .arg("--lang=smt2")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        child => child?,
+    };
+    // The answer is a single line, so the solver does not block on its output
+    // while the query is written.
+    child.stdin.take().unwrap().write_all(query.as_bytes())?;
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(Some(match stdout.trim() {
+        "unsat" => Verdict::Unique,
+        "sat" => Verdict::NotUnique,
+        _ => Verdict::Unknown(format!(
+            "{}{}",
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        )),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::r1cs_file::Constraint;
+    use crate::{secret::Secret, GameVariant};
+    use ark_bn254::Fr;
+    use ark_std::rand::SeedableRng;
+    use ark_std::{One, Zero};
+    use rand_chacha::ChaCha20Rng;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    /**
+     * An S-expression of the query.
+     */
+    #[derive(Debug)]
+    enum Sexp {
+        Atom(String),
+        List(Vec<Sexp>),
+    }
+
+    fn parse(text: &str) -> Vec<Sexp> {
+        let uncommented: String = text
+            .lines()
+            .map(|line| line.split(';').next().unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let spaced = uncommented.replace('(', " ( ").replace(')', " ) ");
+        let mut stack = vec![vec![]];
+        for token in spaced.split_whitespace() {
+            match token {
+                "(" => stack.push(vec![]),
+                ")" => {
+                    let list = stack.pop().unwrap();
+                    stack
+                        .last_mut()
+                        .expect("unbalanced )")
+                        .push(Sexp::List(list));
+                }
+                atom => stack.last_mut().unwrap().push(Sexp::Atom(atom.to_string())),
+            }
+        }
+        assert_eq!(stack.len(), 1, "unbalanced (");
+        stack.pop().unwrap()
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Value {
+        Field(Fr),
+        Bool(bool),
+    }
+
+    fn field(value: Value) -> Fr {
+        match value {
+            Value::Field(x) => x,
+            Value::Bool(_) => panic!("expected a field element"),
+        }
+    }
+
+    fn boolean(value: Value) -> bool {
+        match value {
+            Value::Bool(b) => b,
+            Value::Field(_) => panic!("expected a boolean"),
+        }
+    }
+
+    /**
+     * Evaluate a term of the query, with the constants declared in `env`.
+     */
+    fn eval(sexp: &Sexp, env: &HashMap<String, Fr>) -> Value {
+        let list = match sexp {
+            Sexp::Atom(atom) if atom == "false" => return Value::Bool(false),
+            Sexp::Atom(atom) => return Value::Field(*env.get(atom).expect("undeclared")),
+            Sexp::List(list) => list,
+        };
+        let Some((Sexp::Atom(head), args)) = list.split_first() else {
+            panic!("unexpected term {sexp:?}");
+        };
+        let mut args = args.iter().map(|arg| eval(arg, env));
+        match head.as_str() {
+            "as" => {
+                let [Sexp::Atom(literal), Sexp::Atom(sort)] = &list[1..] else {
+                    panic!("unexpected literal {sexp:?}");
+                };
+                assert_eq!(sort, "F");
+                Value::Field(Fr::from_str(literal.strip_prefix("ff").unwrap()).unwrap())
+            }
+            "ff.add" => Value::Field(args.map(field).sum()),
+            "ff.mul" => Value::Field(args.map(field).product()),
+            "=" => Value::Bool(field(args.next().unwrap()) == field(args.next().unwrap())),
+            "not" => Value::Bool(!boolean(args.next().unwrap())),
+            "or" => Value::Bool(args.map(boolean).collect::<Vec<_>>().contains(&true)),
+            _ => panic!("unexpected term {sexp:?}"),
+        }
+    }
+
+    /**
+     * Check the commands of the query, and evaluate its assertions with the
+     * value `value(copy, wire)` for every declared constant.
+     */
+    fn evaluate(query: &str, value: impl Fn(char, usize) -> Fr) -> Vec<bool> {
+        let commands = parse(query);
+        let mut env = HashMap::new();
+        let mut assertions = vec![];
+        for (i, command) in commands.iter().enumerate() {
+            let Sexp::List(list) = command else {
+                panic!("unexpected command {command:?}");
+            };
+            let atoms: Vec<&str> = list
+                .iter()
+                .filter_map(|sexp| match sexp {
+                    Sexp::Atom(atom) => Some(atom.as_str()),
+                    Sexp::List(_) => None,
+                })
+                .collect();
+            match atoms[0] {
+                "set-logic" => assert_eq!(atoms, ["set-logic", "QF_FF"]),
+                "define-sort" => {
+                    let Sexp::List(sort) = &list[3] else {
+                        panic!("unexpected sort {command:?}");
+                    };
+                    let Sexp::Atom(modulus) = &sort[2] else {
+                        panic!("unexpected sort {command:?}");
+                    };
+                    assert_eq!(modulus, &Fr::MODULUS.to_string());
+                }
+                "declare-const" => {
+                    let name = atoms[1];
+                    let (copy, wire) = name.split_at(1);
+                    let value = value(copy.chars().next().unwrap(), wire.parse().unwrap());
+                    assert!(
+                        env.insert(name.to_string(), value).is_none(),
+                        "{name} twice"
+                    );
+                }
+                "assert" => assertions.push(boolean(eval(&list[1], &env))),
+                "check-sat" => assert_eq!(i, commands.len() - 1),
+                _ => panic!("unexpected command {command:?}"),
+            }
+        }
+        assertions
+    }
+
+    fn tiny_game() -> MastermindCircuit<Fr, 2, 2> {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let secret = Secret::<Fr, 2>::new([0, 1], rng);
+        MastermindCircuit {
+            code: secret.code.map(Some),
+            nonce: Some(secret.nonce),
+            hash: Some(secret.commitment(GameVariant::CLASSIC.commitment)),
+            num_partial_correct: Some(0),
+            num_fully_correct: Some(1),
+            guess: [Some(1), Some(1)],
+            variant: GameVariant::CLASSIC,
+            encoding: InputEncoding::Bits,
+        }
+    }
+
+    fn witness(circuit: MastermindCircuit<Fr, 2, 2>) -> Vec<Fr> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        cs.finalize();
+        r1cs_file::witness(&cs).unwrap()
+    }
+
+    #[test]
+    fn test_mastermind_query() {
+        let witness = witness(tiny_game());
+        for fix_inputs in [false, true] {
+            let query = mastermind_query(tiny_game(), fix_inputs).unwrap();
+
+            // Both copies satisfied by the witness of the game: every assertion
+            // holds but the difference of the outputs.
+            let assertions = evaluate(&query, |_, wire| witness[wire]);
+            let (differs, constraints) = assertions.split_last().unwrap();
+            assert!(constraints.iter().all(|&holds| holds));
+            assert!(!differs);
+
+            // A different response in the second copy: the outputs differ, and
+            // the second copy violates a constraint. The game has one fully
+            // correct peg, whose lowest bit is cleared.
+            let fully = response_public_inputs().1.start + 1;
+            let assertions = evaluate(&query, |copy, wire| match copy {
+                'b' if wire == fully => Fr::zero(),
+                _ => witness[wire],
+            });
+            let (differs, constraints) = assertions.split_last().unwrap();
+            assert!(*differs);
+            assert!(constraints.iter().any(|&holds| !holds));
+        }
+
+        // The symbolic query does not need the values of the game.
+        let empty = MastermindCircuit::<Fr, 2, 2> {
+            code: [None; 2],
+            nonce: None,
+            hash: None,
+            num_partial_correct: None,
+            num_fully_correct: None,
+            guess: [None; 2],
+            ..tiny_game()
+        };
+        assert_eq!(
+            mastermind_query(empty.clone(), false).unwrap(),
+            mastermind_query(tiny_game(), false).unwrap()
+        );
+        assert!(mastermind_query(empty, true).is_err());
+    }
+
+    /**
+     * x·x = y, with y shared: x is unique only up to its sign.
+     */
+    fn square() -> R1cs<Fr> {
+        R1cs {
+            num_wires: 3,
+            num_public_outputs: 0,
+            num_public_inputs: 1,
+            num_private_inputs: 0,
+            constraints: vec![Constraint {
+                a: vec![(2, Fr::one())],
+                b: vec![(2, Fr::one())],
+                c: vec![(1, Fr::one())],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_uniqueness_query() {
+        let query = uniqueness_query(&square(), &[1], &[2], &[(1, Fr::from(4u64))]);
+        let value = |x: i64| move |_, wire| [Fr::one(), Fr::from(4u64), Fr::from(x)][wire];
+        assert_eq!(evaluate(&query, value(2)), [true, true, true, false]);
+        // b2 is the other square root of 4.
+        let assertions = evaluate(&query, |copy, wire| match copy {
+            'b' => -value(2)(copy, wire),
+            _ => value(2)(copy, wire),
+        });
+        assert_eq!(assertions, [true, true, true, true]);
+        assert_eq!(evaluate(&query, value(3)), [false, false, true, false]);
+    }
+
+    #[test]
+    #[ignore = "needs cvc5 on the PATH"]
+    fn test_solve() {
+        let query = uniqueness_query(&square(), &[1], &[2], &[(1, Fr::from(4u64))]);
+        assert_eq!(solve(&query).unwrap(), Some(Verdict::NotUnique));
+        // With x a bit, the square determines it.
+        let mut bit = square();
+        bit.constraints.push(Constraint {
+            a: vec![(2, Fr::one())],
+            b: vec![(0, Fr::one()), (2, -Fr::one())],
+            c: vec![],
+        });
+        let query = uniqueness_query(&bit, &[1], &[2], &[]);
+        assert_eq!(solve(&query).unwrap(), Some(Verdict::Unique));
+
+        let query = mastermind_query(tiny_game(), true).unwrap();
+        assert_eq!(solve(&query).unwrap(), Some(Verdict::Unique));
+    }
+}
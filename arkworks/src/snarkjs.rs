@@ -0,0 +1,465 @@
+//! Groth16 proofs, verifying keys and public inputs in the JSON formats of
+//! snarkjs, so that a proof made by the arkworks codemaker can be checked with
+//! `snarkjs groth16 verify verification_key.json public.json proof.json`, and
+//! a proof made with snarkjs from the circom build can be checked here.
+//!
+//! - `proof.json`: the points `pi_a`, `pi_b` and `pi_c`;
+//! - `verification_key.json`: `vk_alpha_1`, `vk_beta_2`, `vk_gamma_2`,
+//!   `vk_delta_2`, the pairing `vk_alphabeta_12` and the points `IC` of the
+//!   public inputs;
+//! - `public.json`: the public inputs.
+//!
+//! Field elements are decimal strings, and the points are in projective
+//! coordinates with z = 1, or the point at infinity with z = 0. An element of
+//! a quadratic extension is the pair `[c0, c1]`. Both verifiers check the same
+//! pairing equation, so the keys and proofs carry over unchanged.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::pairing::Pairing;
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+use ark_ec::AffineRepr;
+use ark_ff::{Field, One, PrimeField, Zero};
+use ark_groth16::{Proof, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::ser::PrettyFormatter;
+use serde_json::Value;
+
+const PROTOCOL: &str = "groth16";
+
+/// The contents of a snarkjs proof directory: the verifying key, the proof
+/// and the public inputs.
+pub type ProofFiles<E> = (VerifyingKey<E>, Proof<E>, Vec<<E as Pairing>::ScalarField>);
+
+/**
+ * A pairing-friendly curve known to snarkjs, by the name it uses for it.
+ */
+pub trait SnarkjsCurve:
+    Pairing<G1Affine = Affine<Self::G1Config>, G2Affine = Affine<Self::G2Config>>
+{
+    type G1Config: SWCurveConfig;
+    type G2Config: SWCurveConfig;
+
+    const NAME: &'static str;
+}
+
+impl SnarkjsCurve for Bn254 {
+    type G1Config = ark_bn254::g1::Config;
+    type G2Config = ark_bn254::g2::Config;
+
+    const NAME: &'static str = "bn128";
+}
+
+impl SnarkjsCurve for Bls12_381 {
+    type G1Config = ark_bls12_381::g1::Config;
+    type G2Config = ark_bls12_381::g2::Config;
+
+    const NAME: &'static str = "bls12381";
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProofFile {
+    pi_a: Value,
+    pi_b: Value,
+    pi_c: Value,
+    protocol: String,
+    curve: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VerifyingKeyFile {
+    protocol: String,
+    curve: String,
+    #[serde(rename = "nPublic")]
+    num_public: usize,
+    vk_alpha_1: Value,
+    vk_beta_2: Value,
+    vk_gamma_2: Value,
+    vk_delta_2: Value,
+    vk_alphabeta_12: Value,
+    #[serde(rename = "IC")]
+    ic: Vec<Value>,
+}
+
+pub fn write_proof<E: SnarkjsCurve, W: Write>(proof: &Proof<E>, writer: W) -> io::Result<()> {
+    let file = ProofFile {
+        pi_a: point_to_json(&proof.a),
+        pi_b: point_to_json(&proof.b),
+        pi_c: point_to_json(&proof.c),
+        protocol: PROTOCOL.to_string(),
+        curve: E::NAME.to_string(),
+    };
+    write_json(&file, writer)
+}
+
+pub fn read_proof<E: SnarkjsCurve, R: Read>(reader: R) -> io::Result<Proof<E>> {
+    let file: ProofFile = serde_json::from_reader(reader)?;
+    check_protocol::<E>(&file.protocol, &file.curve)?;
+    Ok(Proof {
+        a: point_from_json(&file.pi_a)?,
+        b: point_from_json(&file.pi_b)?,
+        c: point_from_json(&file.pi_c)?,
+    })
+}
+
+pub fn write_verifying_key<E: SnarkjsCurve, W: Write>(
+    vk: &VerifyingKey<E>,
+    writer: W,
+) -> io::Result<()> {
+    let alpha_beta = E::pairing(vk.alpha_g1, vk.beta_g2).0;
+    let file = VerifyingKeyFile {
+        protocol: PROTOCOL.to_string(),
+        curve: E::NAME.to_string(),
+        num_public: vk.gamma_abc_g1.len() - 1,
+        vk_alpha_1: point_to_json(&vk.alpha_g1),
+        vk_beta_2: point_to_json(&vk.beta_g2),
+        vk_gamma_2: point_to_json(&vk.gamma_g2),
+        vk_delta_2: point_to_json(&vk.delta_g2),
+        vk_alphabeta_12: target_to_json(&alpha_beta),
+        ic: vk.gamma_abc_g1.iter().map(point_to_json).collect(),
+    };
+    write_json(&file, writer)
+}
+
+/**
+ * Read a verifying key. `vk_alphabeta_12` is only a precomputation of
+ * snarkjs, derived from the other points, and is not read.
+ */
+pub fn read_verifying_key<E: SnarkjsCurve, R: Read>(reader: R) -> io::Result<VerifyingKey<E>> {
+    let file: VerifyingKeyFile = serde_json::from_reader(reader)?;
+    check_protocol::<E>(&file.protocol, &file.curve)?;
+    if file.ic.len() != file.num_public + 1 {
+        return Err(invalid("IC does not match nPublic"));
+    }
+    Ok(VerifyingKey {
+        alpha_g1: point_from_json(&file.vk_alpha_1)?,
+        beta_g2: point_from_json(&file.vk_beta_2)?,
+        gamma_g2: point_from_json(&file.vk_gamma_2)?,
+        delta_g2: point_from_json(&file.vk_delta_2)?,
+        gamma_abc_g1: file
+            .ic
+            .iter()
+            .map(point_from_json)
+            .collect::<io::Result<_>>()?,
+    })
+}
+
+pub fn write_public_inputs<F: PrimeField, W: Write>(inputs: &[F], writer: W) -> io::Result<()> {
+    let inputs: Vec<String> = inputs.iter().map(F::to_string).collect();
+    write_json(&inputs, writer)
+}
+
+pub fn read_public_inputs<F: PrimeField, R: Read>(reader: R) -> io::Result<Vec<F>> {
+    let inputs: Vec<String> = serde_json::from_reader(reader)?;
+    inputs.iter().map(|input| parse_element(input)).collect()
+}
+
+/**
+ * Write `proof.json`, `verification_key.json` and `public.json` to the
+ * directory, creating it if needed.
+ */
+pub fn export<E: SnarkjsCurve>(
+    dir: impl AsRef<Path>,
+    vk: &VerifyingKey<E>,
+    proof: &Proof<E>,
+    public_inputs: &[E::ScalarField],
+) -> io::Result<()> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let create = |name: &str| File::create(dir.join(name)).map(BufWriter::new);
+    write_proof(proof, create("proof.json")?)?;
+    write_verifying_key(vk, create("verification_key.json")?)?;
+    write_public_inputs(public_inputs, create("public.json")?)
+}
+
+/**
+ * Read `proof.json`, `verification_key.json` and `public.json` from the
+ * directory.
+ */
+pub fn import<E: SnarkjsCurve>(dir: impl AsRef<Path>) -> io::Result<ProofFiles<E>> {
+    let dir = dir.as_ref();
+    let open = |name: &str| File::open(dir.join(name)).map(BufReader::new);
+    Ok((
+        read_verifying_key(open("verification_key.json")?)?,
+        read_proof(open("proof.json")?)?,
+        read_public_inputs(open("public.json")?)?,
+    ))
+}
+
+/**
+ * Write the value the way snarkjs does, i.e. `JSON.stringify(value, null, 1)`.
+ */
+fn write_json<T: Serialize, W: Write>(value: &T, mut writer: W) -> io::Result<()> {
+    let mut serializer =
+        serde_json::Serializer::with_formatter(&mut writer, PrettyFormatter::with_indent(b" "));
+    value.serialize(&mut serializer)?;
+    writer.flush()
+}
+
+fn check_protocol<E: SnarkjsCurve>(protocol: &str, curve: &str) -> io::Result<()> {
+    if protocol != PROTOCOL {
+        return Err(invalid("not a groth16 file"));
+    }
+    if curve != E::NAME {
+        return Err(invalid("the file is for another curve"));
+    }
+    Ok(())
+}
+
+/**
+ * An element of a prime field as a string, and of an extension as the list of
+ * its coefficients.
+ */
+fn element_to_json<F: Field>(f: &F) -> Value {
+    let mut coeffs: Vec<Value> = f
+        .to_base_prime_field_elements()
+        .map(|c| Value::String(c.to_string()))
+        .collect();
+    if coeffs.len() == 1 {
+        coeffs.pop().unwrap()
+    } else {
+        Value::Array(coeffs)
+    }
+}
+
+fn element_from_json<F: Field>(value: &Value) -> io::Result<F> {
+    let coeffs = match value {
+        Value::String(s) => vec![parse_element(s)?],
+        Value::Array(coeffs) => coeffs
+            .iter()
+            .map(|c| parse_element(c.as_str().ok_or_else(|| invalid("expected a string"))?))
+            .collect::<io::Result<_>>()?,
+        _ => return Err(invalid("expected a field element")),
+    };
+    F::from_base_prime_field_elems(coeffs).ok_or_else(|| invalid("wrong field extension degree"))
+}
+
+fn parse_element<F: PrimeField>(s: &str) -> io::Result<F> {
+    let invalid_element = || invalid(&format!("{s} is not a field element"));
+    if s.is_empty() || s.len() > 1 && s.starts_with('0') {
+        return Err(invalid_element());
+    }
+    let f = F::from_str(s).map_err(|_| invalid_element())?;
+    // from_str reduces modulo the field order; the files must not.
+    if f.to_string() != s {
+        return Err(invalid_element());
+    }
+    Ok(f)
+}
+
+fn point_to_json<P: SWCurveConfig>(point: &Affine<P>) -> Value {
+    let (x, y, z) = match point.xy() {
+        Some((x, y)) => (x, y, P::BaseField::one()),
+        None => (
+            P::BaseField::zero(),
+            P::BaseField::one(),
+            P::BaseField::zero(),
+        ),
+    };
+    Value::Array(vec![
+        element_to_json(&x),
+        element_to_json(&y),
+        element_to_json(&z),
+    ])
+}
+
+/**
+ * Read a point, checking that it is on the curve and in the prime order
+ * subgroup.
+ */
+fn point_from_json<P: SWCurveConfig>(value: &Value) -> io::Result<Affine<P>> {
+    let coords = match value.as_array().map(Vec::as_slice) {
+        Some([x, y, z]) => [x, y, z].map(element_from_json::<P::BaseField>),
+        _ => return Err(invalid("expected a point [x, y, z]")),
+    };
+    let [x, y, z] = coords;
+    let (x, y, z) = (x?, y?, z?);
+    if z.is_zero() {
+        return Ok(Affine::identity());
+    }
+    if !z.is_one() {
+        return Err(invalid("the point is not normalized"));
+    }
+    let point = Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(invalid("the point is not in the group"));
+    }
+    Ok(point)
+}
+
+/**
+ * An element of the degree 12 target field, nested as snarkjs does: two
+ * cubic extension coefficients of three quadratic extension coefficients.
+ */
+fn target_to_json<F: Field>(f: &F) -> Value {
+    let coeffs: Vec<Value> = f
+        .to_base_prime_field_elements()
+        .map(|c| Value::String(c.to_string()))
+        .collect();
+    let nest = |values: &[Value], size: usize| -> Vec<Value> {
+        values
+            .chunks(size)
+            .map(|chunk| Value::Array(chunk.to_vec()))
+            .collect()
+    };
+    Value::Array(nest(&nest(&coeffs, 2), 3))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_groth16::Groth16;
+    use ark_snark::SNARK;
+    use ark_std::rand::SeedableRng;
+    use ark_std::UniformRand;
+    use rand_chacha::ChaCha20Rng;
+
+    const FIXTURES: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../test-vectors/snarkjs/mastermind-bn254"
+    );
+    const SNARKJS_FIXTURES: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../test-vectors/snarkjs/circom-mastermind"
+    );
+
+    fn random_proof<E: SnarkjsCurve>(rng: &mut ChaCha20Rng) -> (VerifyingKey<E>, Proof<E>) {
+        let vk = VerifyingKey {
+            alpha_g1: E::G1Affine::rand(rng),
+            beta_g2: E::G2Affine::rand(rng),
+            gamma_g2: E::G2Affine::rand(rng),
+            delta_g2: E::G2Affine::rand(rng),
+            gamma_abc_g1: vec![E::G1Affine::rand(rng), E::G1Affine::zero()],
+        };
+        let proof = Proof {
+            a: E::G1Affine::rand(rng),
+            b: E::G2Affine::rand(rng),
+            c: E::G1Affine::rand(rng),
+        };
+        (vk, proof)
+    }
+
+    fn round_trip<E: SnarkjsCurve>() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let (vk, proof) = random_proof::<E>(rng);
+        let inputs = vec![E::ScalarField::rand(rng), E::ScalarField::zero()];
+
+        let mut bytes = vec![];
+        write_proof(&proof, &mut bytes).unwrap();
+        assert_eq!(read_proof::<E, _>(bytes.as_slice()).unwrap(), proof);
+        let mut bytes = vec![];
+        write_verifying_key(&vk, &mut bytes).unwrap();
+        assert_eq!(read_verifying_key::<E, _>(bytes.as_slice()).unwrap(), vk);
+        let mut bytes = vec![];
+        write_public_inputs(&inputs, &mut bytes).unwrap();
+        assert_eq!(
+            read_public_inputs::<E::ScalarField, _>(bytes.as_slice()).unwrap(),
+            inputs
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip::<Bn254>();
+        round_trip::<Bls12_381>();
+    }
+
+    /**
+     * The fixtures are a proof of the packed mastermind game on BN254, written
+     * by `cargo run -- --snarkjs ../test-vectors/snarkjs` in the layout of
+     * `snarkjs groth16 prove` and `snarkjs zkey export verificationkey`.
+     */
+    #[test]
+    fn test_fixtures() {
+        let (vk, proof, inputs) = import::<Bn254>(FIXTURES).unwrap();
+        assert_eq!(inputs.len(), 2);
+        assert!(Groth16::<Bn254>::verify(&vk, &inputs, &proof).unwrap());
+
+        let mut tampered = inputs.clone();
+        tampered[1] += ark_bn254::Fr::one();
+        assert!(!Groth16::<Bn254>::verify(&vk, &tampered, &proof).unwrap());
+
+        // Written back byte for byte.
+        let fixture = |name: &str| fs::read(Path::new(FIXTURES).join(name)).unwrap();
+        let mut bytes = vec![];
+        write_proof(&proof, &mut bytes).unwrap();
+        assert_eq!(bytes, fixture("proof.json"));
+        let mut bytes = vec![];
+        write_verifying_key(&vk, &mut bytes).unwrap();
+        assert_eq!(bytes, fixture("verification_key.json"));
+        let mut bytes = vec![];
+        write_public_inputs(&inputs, &mut bytes).unwrap();
+        assert_eq!(bytes, fixture("public.json"));
+    }
+
+    /**
+     * The fixtures are a proof of the circom circuit on
+     * `examples/input1.json`, made by snarkjs itself with
+     * `test-vectors/snarkjs/generate.sh` from the circom fixtures.
+     */
+    #[test]
+    #[ignore = "needs snarkjs to generate the fixtures with test-vectors/snarkjs/generate.sh"]
+    fn test_snarkjs_fixtures() {
+        let (vk, proof, inputs) = import::<Bn254>(SNARKJS_FIXTURES).unwrap();
+        // Outputs hash, correct_pegs and partial_pegs, then the public guess.
+        let hash = "14145853328434050885524917836371294524222664259619069142924766963653570759368";
+        let mut expected = vec![hash.parse::<ark_bn254::Fr>().unwrap()];
+        expected.extend([1u64, 2, 0, 2, 4, 3].map(ark_bn254::Fr::from));
+        assert_eq!(inputs, expected);
+        assert!(Groth16::<Bn254>::verify(&vk, &inputs, &proof).unwrap());
+
+        let mut tampered = inputs.clone();
+        tampered[1] += ark_bn254::Fr::one();
+        assert!(!Groth16::<Bn254>::verify(&vk, &tampered, &proof).unwrap());
+
+        // Written back as the same JSON, including the vk_alphabeta_12
+        // computed by snarkjs.
+        let fixture = |name: &str| -> Value {
+            let bytes = fs::read(Path::new(SNARKJS_FIXTURES).join(name)).unwrap();
+            serde_json::from_slice(&bytes).unwrap()
+        };
+        let mut bytes = vec![];
+        write_proof(&proof, &mut bytes).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&bytes).unwrap(),
+            fixture("proof.json")
+        );
+        let mut bytes = vec![];
+        write_verifying_key(&vk, &mut bytes).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&bytes).unwrap(),
+            fixture("verification_key.json")
+        );
+        let mut bytes = vec![];
+        write_public_inputs(&inputs, &mut bytes).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&bytes).unwrap(),
+            fixture("public.json")
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let proof = fs::read(Path::new(FIXTURES).join("proof.json")).unwrap();
+        assert!(read_proof::<Bls12_381, _>(proof.as_slice()).is_err());
+
+        // A point off the curve.
+        let mut file: Value = serde_json::from_slice(&proof).unwrap();
+        file["pi_a"][1] = Value::String("1".to_string());
+        let bytes = serde_json::to_vec(&file).unwrap();
+        assert!(read_proof::<Bn254, _>(bytes.as_slice()).is_err());
+
+        // Public inputs must be reduced.
+        let modulus = ark_bn254::Fr::MODULUS.to_string();
+        let bytes = serde_json::to_vec(&[modulus]).unwrap();
+        assert!(read_public_inputs::<ark_bn254::Fr, _>(bytes.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,791 @@
+//! Solidity contracts to check the mastermind proofs on chain.
+//!
+//! - `Groth16Verifier`: generated from a verifying key, it checks a Groth16
+//!   proof with the BN254 precompiles of EIP-196 (`ecAdd`, `ecMul`) and
+//!   EIP-197 (the pairing check). It has the same interface as the verifier
+//!   exported by snarkjs, `verifyProof(a, b, c, input)`.
+//! - `MastermindGame`: stores the codemaker's commitment, takes the
+//!   codebreaker's guesses and accepts a response only with a proof of the
+//!   packed mastermind circuit (see `InputEncoding::Packed`) for the committed
+//!   code, the current guess and the response. It deploys its own
+//!   `Groth16Verifier`, so the key is part of the game's code and cannot be
+//!   changed once the game is deployed.
+//!
+//! A verifier is only as trustworthy as its key. Whoever knows the randomness
+//! of the setup can prove any response, so the codebreaker must not accept a
+//! key generated by the codemaker: before playing, they compile the published
+//! sources for a key they trust and compare the code of the deployed game with
+//! the result.
+//!
+//! The rules `makeGuess` enforces and the packing of `packedInput` come from
+//! the variant of the game given to `export`, which checks that the key proves
+//! that game, so that the contract cannot be generated for a key of another
+//! variant.
+//!
+//! The EVM has no precompiles for other curves, so only BN254 keys are
+//! supported. A point of G2 is given to the precompile with the coefficients
+//! of its extension field elements in the order `[c1, c0]`.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use ark_bn254::Bn254;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ec::pairing::Pairing;
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, Field, PrimeField};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_snark::SNARK;
+use ark_std::rand::{CryptoRng, RngCore};
+
+use crate::snarkjs::SnarkjsCurve;
+use crate::{InputEncoding, MastermindCircuit};
+
+const VERIFIER_TEMPLATE: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.4;
+
+/**
+ * Groth16 verifier for the mastermind circuit, generated by the arkworks crate.
+ */
+contract Groth16Verifier {
+    // Scalar field order: the public inputs must be reduced
+    uint256 constant R = %R%;
+    // Base field order
+    uint256 constant Q = %Q%;
+
+    uint256 constant NUM_INPUTS = %NUM_INPUTS%;
+
+%KEY%    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c,
+        uint256[NUM_INPUTS] calldata input
+    ) external view returns (bool) {
+        for (uint256 i = 0; i < NUM_INPUTS; i++) {
+            if (input[i] >= R) {
+                return false;
+            }
+        }
+
+        // Linear combination of the points of the public inputs
+        uint256[2] memory x = [IC0_X, IC0_Y];
+%LINEAR_COMBINATION%
+        // e(-a, b) e(alpha, beta) e(x, gamma) e(c, delta) == 1
+        uint256[24] memory p;
+        p[0] = a[0];
+        p[1] = (Q - (a[1] % Q)) % Q;
+        p[2] = b[0][0];
+        p[3] = b[0][1];
+        p[4] = b[1][0];
+        p[5] = b[1][1];
+        p[6] = ALPHA_X;
+        p[7] = ALPHA_Y;
+        p[8] = BETA_X1;
+        p[9] = BETA_X0;
+        p[10] = BETA_Y1;
+        p[11] = BETA_Y0;
+        p[12] = x[0];
+        p[13] = x[1];
+        p[14] = GAMMA_X1;
+        p[15] = GAMMA_X0;
+        p[16] = GAMMA_Y1;
+        p[17] = GAMMA_Y0;
+        p[18] = c[0];
+        p[19] = c[1];
+        p[20] = DELTA_X1;
+        p[21] = DELTA_X0;
+        p[22] = DELTA_Y1;
+        p[23] = DELTA_Y0;
+
+        uint256[1] memory out;
+        bool success;
+        assembly {
+            success := staticcall(gas(), 8, p, 768, out, 32)
+        }
+        return success && out[0] == 1;
+    }
+
+    function ecAdd(uint256[2] memory p1, uint256[2] memory p2) internal view returns (uint256[2] memory r) {
+        uint256[4] memory input = [p1[0], p1[1], p2[0], p2[1]];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 6, input, 128, r, 64)
+        }
+        require(success, "ecAdd failed");
+    }
+
+    function ecMul(uint256[2] memory p, uint256 s) internal view returns (uint256[2] memory r) {
+        uint256[3] memory input = [p[0], p[1], s];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 7, input, 96, r, 64)
+        }
+        require(success, "ecMul failed");
+    }
+}
+"#;
+
+const GAME_TEMPLATE: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.4;
+
+import "./Groth16Verifier.sol";
+
+/**
+ * A game of mastermind between the codemaker, who deploys the contract with
+ * the commitment to their code, and the codebreaker. Every response comes with
+ * a proof of the packed mastermind circuit that it is the response to the
+ * guess for the committed code.
+ */
+contract MastermindGame {
+    uint256 public constant NUM_COLORS = %NUM_COLORS%;
+    uint256 public constant CODE_SIZE = %CODE_SIZE%;
+    bool public constant DISTINCT_GUESS = %DISTINCT_GUESS%;
+
+    Groth16Verifier public immutable verifier;
+    address public immutable codemaker;
+    address public immutable codebreaker;
+    uint256 public immutable commitment;
+
+    uint256[CODE_SIZE] public guess;
+    bool public awaitingResponse;
+    uint256 public rounds;
+    bool public solved;
+
+    event Guess(uint256 round, uint256[CODE_SIZE] pegs);
+    event Response(uint256 round, uint256 fullyCorrect, uint256 partiallyCorrect);
+
+    constructor(uint256 commitment_, address codebreaker_) {
+        verifier = new Groth16Verifier();
+        codemaker = msg.sender;
+        codebreaker = codebreaker_;
+        commitment = commitment_;
+    }
+
+    function makeGuess(uint256[CODE_SIZE] calldata pegs) external {
+        require(msg.sender == codebreaker, "not the codebreaker");
+        require(!solved, "the code was found");
+        require(!awaitingResponse, "awaiting a response");
+        // The same rules as `assert_guess_is_valid`: a proof for any other
+        // guess could never be made.
+        for (uint256 i = 0; i < CODE_SIZE; i++) {
+            require(pegs[i] < NUM_COLORS, "not a color");
+            if (DISTINCT_GUESS) {
+                for (uint256 j = 0; j < i; j++) {
+                    require(pegs[i] != pegs[j], "repeated color");
+                }
+            }
+        }
+        guess = pegs;
+        awaitingResponse = true;
+        emit Guess(rounds, pegs);
+    }
+
+    function respond(
+        uint256 fullyCorrect,
+        uint256 partiallyCorrect,
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c
+    ) external {
+        require(msg.sender == codemaker, "not the codemaker");
+        require(awaitingResponse, "no guess to respond to");
+        require(fullyCorrect + partiallyCorrect <= CODE_SIZE, "not a response");
+        uint256[2] memory input = [commitment, packedInput(fullyCorrect, partiallyCorrect)];
+        require(verifier.verifyProof(a, b, c, input), "invalid proof");
+
+        awaitingResponse = false;
+        emit Response(rounds, fullyCorrect, partiallyCorrect);
+        rounds += 1;
+        if (fullyCorrect == CODE_SIZE) {
+            solved = true;
+        }
+    }
+
+    /**
+     * The second public input of the packed circuit: the pegs of the guess and
+     * the response as the digits of a single number.
+     */
+    function packedInput(uint256 fullyCorrect, uint256 partiallyCorrect) public view returns (uint256 packed) {
+        uint256 weight = 1;
+        for (uint256 i = 0; i < CODE_SIZE; i++) {
+            packed += guess[i] * weight;
+            weight *= NUM_COLORS;
+        }
+        packed += fullyCorrect * weight;
+        packed += partiallyCorrect * weight * (CODE_SIZE + 1);
+    }
+}
+"#;
+
+/**
+ * The Solidity source of a Groth16 verifier for the key.
+ */
+pub fn verifier_contract<E: SnarkjsCurve>(vk: &VerifyingKey<E>) -> io::Result<String> {
+    if E::NAME != Bn254::NAME {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the EVM only has precompiles for BN254",
+        ));
+    }
+
+    let mut key = String::new();
+    let mut constant = |name: &str, words: Vec<String>| {
+        for (suffix, word) in point_constant_names(words.len()).iter().zip(words) {
+            key += &format!("    uint256 constant {name}_{suffix} = {word};\n");
+        }
+        key += "\n";
+    };
+    constant("ALPHA", point_to_decimal(&vk.alpha_g1));
+    constant("BETA", point_to_decimal(&vk.beta_g2));
+    constant("GAMMA", point_to_decimal(&vk.gamma_g2));
+    constant("DELTA", point_to_decimal(&vk.delta_g2));
+    for (i, point) in vk.gamma_abc_g1.iter().enumerate() {
+        constant(&format!("IC{i}"), point_to_decimal(point));
+    }
+
+    let linear_combination: String = (1..vk.gamma_abc_g1.len())
+        .map(|i| {
+            format!(
+                "        x = ecAdd(x, ecMul([IC{i}_X, IC{i}_Y], input[{}]));\n",
+                i - 1
+            )
+        })
+        .collect();
+
+    Ok(VERIFIER_TEMPLATE
+        .replace("%R%", &E::ScalarField::MODULUS.to_string())
+        .replace("%Q%", &E::BaseField::MODULUS.to_string())
+        .replace("%NUM_INPUTS%", &(vk.gamma_abc_g1.len() - 1).to_string())
+        .replace("%KEY%", &key)
+        .replace("%LINEAR_COMBINATION%", &linear_combination))
+}
+
+/**
+ * The Solidity source of the game contract, for codes of SZ pegs out of the
+ * colors of the variant. It imports the verifier from `Groth16Verifier.sol`.
+ */
+pub fn game_contract<const NPEGS: usize, const SZ: usize>(variant: &crate::GameVariant) -> String {
+    GAME_TEMPLATE
+        .replace("%NUM_COLORS%", &variant.num_colors::<NPEGS>().to_string())
+        .replace("%CODE_SIZE%", &SZ.to_string())
+        .replace("%DISTINCT_GUESS%", &variant.distinct_guess().to_string())
+}
+
+/**
+ * Check that the keys prove the game, a game of the packed circuit: keys of
+ * another variant, or of another circuit, make a proof which does not verify.
+ */
+fn check_keys<E: Pairing, const NPEGS: usize, const SZ: usize, R: RngCore + CryptoRng>(
+    pk: &ProvingKey<E>,
+    game: &MastermindCircuit<E::ScalarField, NPEGS, SZ>,
+    rng: &mut R,
+) -> io::Result<()>
+where
+    E::ScalarField: Absorb,
+{
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    if game.encoding != InputEncoding::Packed {
+        return Err(invalid(
+            "the game contract only computes packed public inputs",
+        ));
+    }
+    let inputs = game
+        .public_inputs()
+        .ok_or_else(|| invalid("the game has missing public values"))?;
+    let verified = Groth16::<E>::prove(pk, game.clone(), rng)
+        .and_then(|proof| Groth16::<E>::verify(&pk.vk, &inputs, &proof));
+    match verified {
+        Ok(true) => Ok(()),
+        _ => Err(invalid("the keys do not prove the game of this variant")),
+    }
+}
+
+/**
+ * Write `Groth16Verifier.sol` and `MastermindGame.sol` to the directory,
+ * creating it if needed. The verifier is the one of the keys, and the game
+ * contract follows the variant of `game`, an example game of the packed
+ * circuit, which the keys must prove.
+ */
+pub fn export<E: SnarkjsCurve, const NPEGS: usize, const SZ: usize, R: RngCore + CryptoRng>(
+    dir: impl AsRef<Path>,
+    pk: &ProvingKey<E>,
+    game: &MastermindCircuit<E::ScalarField, NPEGS, SZ>,
+    rng: &mut R,
+) -> io::Result<()>
+where
+    E::ScalarField: Absorb,
+{
+    let verifier = verifier_contract(&pk.vk)?;
+    check_keys(pk, game, rng)?;
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    File::create(dir.join("Groth16Verifier.sol"))?.write_all(verifier.as_bytes())?;
+    File::create(dir.join("MastermindGame.sol"))?
+        .write_all(game_contract::<NPEGS, SZ>(&game.variant).as_bytes())
+}
+
+/**
+ * The ABI encoding of the proof arguments `a`, `b` and `c` of `verifyProof`
+ * and `respond`: eight 32-byte big-endian words.
+ */
+pub fn encode_proof<E: SnarkjsCurve>(proof: &Proof<E>) -> Vec<u8> {
+    [
+        point_to_words(&proof.a),
+        point_to_words(&proof.b),
+        point_to_words(&proof.c),
+    ]
+    .concat()
+}
+
+/**
+ * The ABI encoding of the public inputs, one 32-byte big-endian word each.
+ */
+pub fn encode_inputs<F: PrimeField>(inputs: &[F]) -> Vec<u8> {
+    inputs.iter().flat_map(|input| to_word(*input)).collect()
+}
+
+fn to_word<F: PrimeField>(f: F) -> [u8; 32] {
+    let bytes = f.into_bigint().to_bytes_be();
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+    word
+}
+
+/**
+ * The coordinates of a point in the precompile order, [c1, c0] for an element
+ * of a quadratic extension. The point at infinity is all zeros.
+ */
+fn point_coordinates<P: SWCurveConfig>(
+    point: &Affine<P>,
+) -> Vec<<P::BaseField as Field>::BasePrimeField> {
+    let Some((x, y)) = point.xy() else {
+        let degree = P::BaseField::extension_degree() as usize;
+        return vec![Default::default(); 2 * degree];
+    };
+    [x, y]
+        .iter()
+        .flat_map(|c| {
+            let mut coeffs: Vec<_> = c.to_base_prime_field_elements().collect();
+            coeffs.reverse();
+            coeffs
+        })
+        .collect()
+}
+
+fn point_to_words<P: SWCurveConfig>(point: &Affine<P>) -> Vec<u8> {
+    point_coordinates(point)
+        .into_iter()
+        .flat_map(to_word)
+        .collect()
+}
+
+fn point_to_decimal<P: SWCurveConfig>(point: &Affine<P>) -> Vec<String> {
+    point_coordinates(point)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+fn point_constant_names(num_words: usize) -> &'static [&'static str] {
+    if num_words == 2 {
+        &["X", "Y"]
+    } else {
+        &["X1", "X0", "Y1", "Y0"]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{compute_commitment, snarkjs, GameVariant};
+    use ark_bn254::{Fr, G1Projective};
+    use ark_ec::CurveGroup;
+    use ark_ff::One;
+    use ark_snark::CircuitSpecificSetupSNARK;
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use revm::db::{CacheDB, EmptyDB};
+    use revm::primitives::{keccak256, Address, Bytes, ExecutionResult, Output, TxKind};
+    use revm::Evm;
+    use std::process::Command;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const FIXTURES: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../test-vectors/snarkjs/mastermind-bn254"
+    );
+
+    const CODEMAKER: Address = Address::repeat_byte(0x11);
+    const CODEBREAKER: Address = Address::repeat_byte(0x22);
+
+    /**
+     * An in-process EVM with an empty state.
+     */
+    struct Chain {
+        evm: Evm<'static, (), CacheDB<EmptyDB>>,
+    }
+
+    impl Chain {
+        fn new() -> Self {
+            let evm = Evm::builder()
+                .with_db(CacheDB::new(EmptyDB::default()))
+                .build();
+            Chain { evm }
+        }
+
+        fn transact(&mut self, from: Address, to: TxKind, data: Vec<u8>) -> Result<Output, String> {
+            let tx = self.evm.tx_mut();
+            tx.caller = from;
+            tx.transact_to = to;
+            tx.data = Bytes::from(data);
+            tx.gas_limit = 30_000_000;
+            match self.evm.transact_commit().map_err(|e| format!("{e:?}"))? {
+                ExecutionResult::Success { output, .. } => Ok(output),
+                result => Err(format!("{result:?}")),
+            }
+        }
+
+        fn call(&mut self, from: Address, to: Address, data: Vec<u8>) -> Result<Vec<u8>, String> {
+            match self.transact(from, TxKind::Call(to), data)? {
+                Output::Call(output) => Ok(output.to_vec()),
+                output => Err(format!("unexpected output {output:?}")),
+            }
+        }
+
+        fn deploy(&mut self, from: Address, code: Vec<u8>) -> Address {
+            match self.transact(from, TxKind::Create, code).unwrap() {
+                Output::Create(_, Some(address)) => address,
+                output => panic!("deployment failed: {output:?}"),
+            }
+        }
+    }
+
+    fn call_data(signature: &str, args: &[&[u8]]) -> Vec<u8> {
+        let mut data = keccak256(signature.as_bytes())[..4].to_vec();
+        for arg in args {
+            data.extend_from_slice(arg);
+        }
+        data
+    }
+
+    fn word(n: u64) -> [u8; 32] {
+        to_word(Fr::from(n))
+    }
+
+    fn address_word(address: Address) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[12..].copy_from_slice(address.as_slice());
+        word
+    }
+
+    fn is_true(output: &[u8]) -> bool {
+        output == word(1)
+    }
+
+    /**
+     * The input of the pairing precompile the verifier builds for the proof.
+     */
+    fn pairing_input(vk: &VerifyingKey<Bn254>, proof: &Proof<Bn254>, inputs: &[Fr]) -> Vec<u8> {
+        let x: G1Projective = vk.gamma_abc_g1[0].into_group()
+            + vk.gamma_abc_g1[1..]
+                .iter()
+                .zip(inputs)
+                .map(|(point, input)| *point * input)
+                .sum::<G1Projective>();
+        [
+            point_to_words(&-proof.a),
+            point_to_words(&proof.b),
+            point_to_words(&vk.alpha_g1),
+            point_to_words(&vk.beta_g2),
+            point_to_words(&x.into_affine()),
+            point_to_words(&vk.gamma_g2),
+            point_to_words(&proof.c),
+            point_to_words(&vk.delta_g2),
+        ]
+        .concat()
+    }
+
+    /**
+     * Compile the contracts with the solc on the PATH and return the bytecode
+     * of the named contract.
+     */
+    fn compile(sources: &[(&str, String)], contract: &str) -> Vec<u8> {
+        // A directory per call, as the tests compile different sources under
+        // the same names in parallel.
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "mastermind-test-solidity-{}-{}",
+            std::process::id(),
+            CALLS.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let paths: Vec<_> = sources
+            .iter()
+            .map(|(name, source)| {
+                let path = dir.join(name);
+                fs::write(&path, source).unwrap();
+                path
+            })
+            .collect();
+        let output = Command::new("solc")
+            .args(["--combined-json", "bin", "--optimize"])
+            .args(&paths)
+            .output()
+            .expect("solc is not on the PATH");
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let (_, compiled) = json["contracts"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .find(|(name, _)| name.ends_with(&format!(":{contract}")))
+            .unwrap();
+        let bin = compiled["bin"].as_str().unwrap();
+        (0..bin.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&bin[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn deploy_verifier(chain: &mut Chain, vk: &VerifyingKey<Bn254>) -> Address {
+        let source = verifier_contract(vk).unwrap();
+        let code = compile(&[("Groth16Verifier.sol", source)], "Groth16Verifier");
+        chain.deploy(CODEMAKER, code)
+    }
+
+    /**
+     * Deploy the game of the variant for the commitment, with its verifier for
+     * the key.
+     */
+    fn deploy_game(
+        chain: &mut Chain,
+        vk: &VerifyingKey<Bn254>,
+        variant: &GameVariant,
+        commitment: Fr,
+    ) -> Address {
+        let sources = [
+            ("Groth16Verifier.sol", verifier_contract(vk).unwrap()),
+            ("MastermindGame.sol", game_contract::<6, 4>(variant)),
+        ];
+        let mut code = compile(&sources, "MastermindGame");
+        code.extend(to_word(commitment));
+        code.extend(address_word(CODEBREAKER));
+        chain.deploy(CODEMAKER, code)
+    }
+
+    fn guess(pegs: [u64; 4]) -> Vec<u8> {
+        let pegs: Vec<u8> = pegs.iter().flat_map(|&p| word(p)).collect();
+        call_data("makeGuess(uint256[4])", &[&pegs])
+    }
+
+    const VERIFY_PROOF: &str = "verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[2])";
+
+    #[test]
+    fn test_generate() {
+        let (vk, _, _) = snarkjs::import::<Bn254>(FIXTURES).unwrap();
+        let verifier = verifier_contract(&vk).unwrap();
+        assert!(verifier.contains("uint256 constant NUM_INPUTS = 2;"));
+        assert!(verifier.contains("x = ecAdd(x, ecMul([IC2_X, IC2_Y], input[1]));"));
+        for placeholder in [
+            "%R%",
+            "%Q%",
+            "%NUM_INPUTS%",
+            "%KEY%",
+            "%LINEAR_COMBINATION%",
+        ] {
+            assert!(!verifier.contains(placeholder));
+        }
+        let (x, _) = vk.gamma_abc_g1[0].xy().unwrap();
+        assert!(verifier.contains(&format!("uint256 constant IC0_X = {x};")));
+
+        let game = game_contract::<6, 4>(&GameVariant::CLASSIC);
+        assert!(game.contains("NUM_COLORS = 6;"));
+        assert!(game.contains("CODE_SIZE = 4;"));
+        assert!(game.contains("DISTINCT_GUESS = false;"));
+        assert!(!game.contains('%'));
+        let game = game_contract::<6, 4>(&GameVariant::BULLS_AND_COWS);
+        assert!(game.contains("DISTINCT_GUESS = true;"));
+        let blanks = GameVariant {
+            blanks: true,
+            ..GameVariant::CLASSIC
+        };
+        assert!(game_contract::<6, 4>(&blanks).contains("NUM_COLORS = 7;"));
+
+        // No precompiles for BLS12-381.
+        let vk = VerifyingKey::<ark_bls12_381::Bls12_381>::default();
+        assert!(verifier_contract(&vk).is_err());
+    }
+
+    fn packed_game(variant: GameVariant) -> MastermindCircuit<Fr, 6, 4> {
+        let nonce = Fr::from(42u64);
+        MastermindCircuit {
+            code: [1, 2, 3, 4].map(Some),
+            nonce: Some(nonce),
+            hash: Some(compute_commitment(nonce, &[1, 2, 3, 4], variant.commitment)),
+            num_partial_correct: Some(2),
+            num_fully_correct: Some(2),
+            guess: [1, 2, 4, 3].map(Some),
+            variant,
+            encoding: InputEncoding::Packed,
+        }
+    }
+
+    #[test]
+    fn test_export_checks_the_variant() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let classic = packed_game(GameVariant::CLASSIC);
+        let (pk, _) = Groth16::<Bn254>::setup(classic.clone(), rng).unwrap();
+        let dir = std::env::temp_dir().join(format!(
+            "mastermind-test-solidity-export-{}",
+            std::process::id()
+        ));
+
+        export(&dir, &pk, &classic, rng).unwrap();
+        let game = fs::read_to_string(dir.join("MastermindGame.sol")).unwrap();
+        assert!(game.contains("NUM_COLORS = 6;"));
+        let verifier = fs::read_to_string(dir.join("Groth16Verifier.sol")).unwrap();
+        assert_eq!(verifier, verifier_contract(&pk.vk).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        // The keys of the classic game are not the ones of a game with blanks,
+        // whose contract would accept a seventh color, nor of the bits encoding.
+        let blanks = GameVariant {
+            blanks: true,
+            ..GameVariant::CLASSIC
+        };
+        assert!(export(&dir, &pk, &packed_game(blanks), rng).is_err());
+        let bits = MastermindCircuit {
+            encoding: InputEncoding::Bits,
+            ..classic
+        };
+        assert!(export(&dir, &pk, &bits, rng).is_err());
+        assert!(!dir.exists());
+    }
+
+    /**
+     * Checks the encoding of the points against the pairing precompile itself,
+     * with the proof of the snarkjs fixtures.
+     */
+    #[test]
+    fn test_pairing_precompile() {
+        let (vk, proof, inputs) = snarkjs::import::<Bn254>(FIXTURES).unwrap();
+        let mut chain = Chain::new();
+        let precompile = Address::with_last_byte(8);
+
+        let input = pairing_input(&vk, &proof, &inputs);
+        assert_eq!(input.len(), 768);
+        assert!(is_true(&chain.call(CODEMAKER, precompile, input).unwrap()));
+
+        let mut tampered = inputs;
+        tampered[1] += Fr::one();
+        let input = pairing_input(&vk, &proof, &tampered);
+        assert!(!is_true(&chain.call(CODEMAKER, precompile, input).unwrap()));
+    }
+
+    #[test]
+    #[ignore = "needs solc on the PATH"]
+    fn test_verifier_contract() {
+        let (vk, proof, inputs) = snarkjs::import::<Bn254>(FIXTURES).unwrap();
+        let mut chain = Chain::new();
+        let verifier = deploy_verifier(&mut chain, &vk);
+
+        let verify = |chain: &mut Chain, inputs: &[Fr]| {
+            let data = call_data(
+                VERIFY_PROOF,
+                &[&encode_proof(&proof), &encode_inputs(inputs)],
+            );
+            is_true(&chain.call(CODEMAKER, verifier, data).unwrap())
+        };
+        assert!(verify(&mut chain, &inputs));
+
+        let mut tampered = inputs.clone();
+        tampered[1] += Fr::one();
+        assert!(!verify(&mut chain, &tampered));
+
+        // An input out of the field is not reduced.
+        let mut data = call_data(
+            VERIFY_PROOF,
+            &[&encode_proof(&proof), &encode_inputs(&inputs)],
+        );
+        let modulus = Fr::MODULUS.to_bytes_be();
+        let offset = data.len() - 32;
+        data[offset..].copy_from_slice(&modulus);
+        assert!(!is_true(&chain.call(CODEMAKER, verifier, data).unwrap()));
+    }
+
+    /**
+     * Plays the round of the fixtures: the guess [1, 2, 4, 3] gets two pegs of
+     * the right color in the right place and two in the wrong place.
+     */
+    #[test]
+    #[ignore = "needs solc on the PATH"]
+    fn test_game_contract() {
+        let (vk, proof, inputs) = snarkjs::import::<Bn254>(FIXTURES).unwrap();
+        let mut chain = Chain::new();
+        let game = deploy_game(&mut chain, &vk, &GameVariant::CLASSIC, inputs[0]);
+
+        let proof = encode_proof(&proof);
+        let respond = |fully: u64, partial: u64| {
+            let signature = "respond(uint256,uint256,uint256[2],uint256[2][2],uint256[2])";
+            call_data(signature, &[&word(fully), &word(partial), &proof])
+        };
+        let rounds = call_data("rounds()", &[]);
+
+        // Only the codebreaker guesses, and only with colors.
+        assert!(chain.call(CODEMAKER, game, guess([1, 2, 4, 3])).is_err());
+        assert!(chain.call(CODEBREAKER, game, guess([1, 2, 4, 6])).is_err());
+        // No response before a guess.
+        assert!(chain.call(CODEMAKER, game, respond(2, 2)).is_err());
+        chain.call(CODEBREAKER, game, guess([1, 2, 4, 3])).unwrap();
+        assert!(chain.call(CODEBREAKER, game, guess([0, 0, 0, 0])).is_err());
+
+        // The proof is only for the right response, from the codemaker.
+        assert!(chain.call(CODEMAKER, game, respond(2, 1)).is_err());
+        assert!(chain.call(CODEBREAKER, game, respond(2, 2)).is_err());
+        let packed = call_data("packedInput(uint256,uint256)", &[&word(2), &word(2)]);
+        assert_eq!(
+            chain.call(CODEMAKER, game, packed).unwrap(),
+            to_word(inputs[1])
+        );
+        chain.call(CODEMAKER, game, respond(2, 2)).unwrap();
+        assert_eq!(
+            chain.call(CODEMAKER, game, rounds.clone()).unwrap(),
+            word(1)
+        );
+
+        // The proof is for the previous guess.
+        chain.call(CODEBREAKER, game, guess([0, 0, 0, 0])).unwrap();
+        assert!(chain.call(CODEMAKER, game, respond(2, 2)).is_err());
+        assert_eq!(chain.call(CODEMAKER, game, rounds).unwrap(), word(1));
+    }
+
+    /**
+     * The game takes the same guesses as `assert_guess_is_valid` for its
+     * variant.
+     */
+    #[test]
+    #[ignore = "needs solc on the PATH"]
+    fn test_game_guess_rules() {
+        let (vk, _, inputs) = snarkjs::import::<Bn254>(FIXTURES).unwrap();
+        let mut chain = Chain::new();
+
+        let game = deploy_game(&mut chain, &vk, &GameVariant::BULLS_AND_COWS, inputs[0]);
+        assert!(chain.call(CODEBREAKER, game, guess([1, 2, 1, 3])).is_err());
+        chain.call(CODEBREAKER, game, guess([1, 2, 4, 3])).unwrap();
+
+        let blanks = GameVariant {
+            blanks: true,
+            ..GameVariant::CLASSIC
+        };
+        let game = deploy_game(&mut chain, &vk, &blanks, inputs[0]);
+        assert!(chain.call(CODEBREAKER, game, guess([1, 2, 4, 7])).is_err());
+        chain.call(CODEBREAKER, game, guess([6, 6, 4, 3])).unwrap();
+    }
+}
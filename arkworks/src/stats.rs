@@ -0,0 +1,308 @@
+//! Cost of each gadget of a circuit, and the path of its unsatisfied
+//! constraints.
+//!
+//! The gadgets of the crate run in `ns!` namespaces: `code/commitment`,
+//! `response/partially_correct`, and so on. Under a `ConstraintLayer`, ark-relations
+//! records the namespaces each constraint was enforced in, so
+//! `which_is_unsatisfied` names the gadget of a failing constraint instead of
+//! its index. `measure` also counts the constraints and variables added while
+//! each namespace of the crate is entered.
+
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{
+    ConstraintLayer, ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+    SynthesisMode, TracingMode,
+};
+use tracing::{span, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+/**
+ * Number of constraints, witness variables and public inputs.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub constraints: usize,
+    pub witness_variables: usize,
+    pub public_inputs: usize,
+}
+
+impl Counts {
+    fn of<F: PrimeField>(cs: &ConstraintSystemRef<F>) -> Self {
+        Counts {
+            constraints: cs.num_constraints(),
+            witness_variables: cs.num_witness_variables(),
+            // Without the constant one
+            public_inputs: cs.num_instance_variables().saturating_sub(1),
+        }
+    }
+
+    fn since(self, before: Counts) -> Self {
+        Counts {
+            constraints: self.constraints - before.constraints,
+            witness_variables: self.witness_variables - before.witness_variables,
+            public_inputs: self.public_inputs - before.public_inputs,
+        }
+    }
+}
+
+/**
+ * What a gadget added to the constraint system, over all the times its
+ * namespace was entered. Nested namespaces are counted in their parents too.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GadgetCost {
+    // Names of the namespaces, from the outermost one, joined by '/'
+    pub path: String,
+    pub calls: usize,
+    pub counts: Counts,
+}
+
+/**
+ * Costs of the gadgets of a circuit, in the order they were first entered.
+ */
+#[derive(Clone, Debug)]
+pub struct CostReport {
+    pub gadgets: Vec<GadgetCost>,
+    pub total: Counts,
+}
+
+thread_local! {
+    // Counts of the constraint system being measured on this thread
+    static COUNTS: RefCell<Option<Box<dyn Fn() -> Counts>>> = const { RefCell::new(None) };
+}
+
+fn current_counts() -> Option<Counts> {
+    COUNTS.with(|counts| counts.borrow().as_ref().map(|f| f()))
+}
+
+/**
+ * A layer recording the counts of the constraint system when the namespaces of
+ * the crate are entered and exited. The constraint system is not `Send`, so it
+ * is read through a thread local.
+ */
+#[derive(Default)]
+struct CostLayer {
+    // Entered namespaces with the counts at the time, and the costs so far
+    stack: Mutex<Vec<(&'static str, Counts)>>,
+    costs: Arc<Mutex<Vec<GadgetCost>>>,
+}
+
+impl CostLayer {
+    fn is_gadget(metadata: &tracing::Metadata<'_>) -> bool {
+        metadata.target() == "r1cs"
+            && metadata
+                .module_path()
+                .is_some_and(|path| path.split("::").next() == Some(env!("CARGO_CRATE_NAME")))
+    }
+
+    fn path(stack: &[(&'static str, Counts)]) -> String {
+        stack
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for CostLayer {
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let (Some(metadata), Some(counts)) = (ctx.metadata(id), current_counts()) else {
+            return;
+        };
+        if !Self::is_gadget(metadata) {
+            return;
+        }
+        let mut stack = self.stack.lock().unwrap();
+        stack.push((metadata.name(), counts));
+        let path = Self::path(&stack);
+
+        let mut costs = self.costs.lock().unwrap();
+        if costs.iter().all(|cost| cost.path != path) {
+            costs.push(GadgetCost {
+                path,
+                calls: 0,
+                counts: Counts::default(),
+            });
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let (Some(metadata), Some(counts)) = (ctx.metadata(id), current_counts()) else {
+            return;
+        };
+        if !Self::is_gadget(metadata) {
+            return;
+        }
+        let mut stack = self.stack.lock().unwrap();
+        let path = Self::path(&stack);
+        let Some((_, before)) = stack.pop() else {
+            return;
+        };
+        let added = counts.since(before);
+
+        let mut costs = self.costs.lock().unwrap();
+        if let Some(cost) = costs.iter_mut().find(|cost| cost.path == path) {
+            cost.calls += 1;
+            cost.counts.constraints += added.constraints;
+            cost.counts.witness_variables += added.witness_variables;
+            cost.counts.public_inputs += added.public_inputs;
+        }
+    }
+}
+
+/**
+ * Synthesize the circuit with the constraint traces of ark-relations enabled,
+ * and the costs of its gadgets recorded if asked.
+ */
+fn synthesize<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+    mode: SynthesisMode,
+    costs: Option<Arc<Mutex<Vec<GadgetCost>>>>,
+) -> Result<ConstraintSystemRef<F>, SynthesisError> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    cs.set_mode(mode);
+    let layer = CostLayer {
+        costs: costs.unwrap_or_default(),
+        ..Default::default()
+    };
+    let subscriber = Registry::default()
+        .with(ConstraintLayer::new(TracingMode::OnlyConstraints))
+        .with(layer);
+
+    let counted = cs.clone();
+    COUNTS.with(|counts| *counts.borrow_mut() = Some(Box::new(move || Counts::of(&counted))));
+    let result =
+        tracing::subscriber::with_default(subscriber, || circuit.generate_constraints(cs.clone()));
+    COUNTS.with(|counts| *counts.borrow_mut() = None);
+    result.map(|()| cs)
+}
+
+/**
+ * Synthesize the circuit and measure what each of its gadgets adds to the
+ * constraint system. The witness need not be set.
+ */
+pub fn measure<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+) -> Result<CostReport, SynthesisError> {
+    let costs = Arc::new(Mutex::new(vec![]));
+    let cs = synthesize(circuit, SynthesisMode::Setup, Some(costs.clone()))?;
+    let gadgets = costs.lock().unwrap().clone();
+    Ok(CostReport {
+        gadgets,
+        total: Counts::of(&cs),
+    })
+}
+
+/**
+ * The namespaces of the first unsatisfied constraint of the circuit, if any.
+ */
+pub fn unsatisfied_constraint<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+) -> Result<Option<String>, SynthesisError> {
+    synthesize(
+        circuit,
+        SynthesisMode::Prove {
+            construct_matrices: true,
+        },
+        None,
+    )?
+    .which_is_unsatisfied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{secret::Secret, GameVariant, InputEncoding, MastermindCircuit};
+    use ark_bn254::Fr;
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn game(encoding: InputEncoding) -> MastermindCircuit<Fr, 6, 4> {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let secret = Secret::<Fr, 4>::new([1, 2, 3, 4], rng);
+        MastermindCircuit {
+            code: secret.code.map(Some),
+            nonce: Some(secret.nonce),
+            hash: Some(secret.commitment(GameVariant::CLASSIC.commitment)),
+            num_partial_correct: Some(2),
+            num_fully_correct: Some(2),
+            guess: [Some(1), Some(2), Some(4), Some(3)],
+            variant: GameVariant::CLASSIC,
+            encoding,
+        }
+    }
+
+    #[test]
+    fn test_measure() {
+        let report = measure(game(InputEncoding::Bits)).unwrap();
+        let cost = |path: &str| {
+            report
+                .gadgets
+                .iter()
+                .find(|cost| cost.path == path)
+                .unwrap_or_else(|| panic!("no gadget {path}"))
+                .counts
+        };
+
+        // The top-level gadgets add up to the whole circuit.
+        let top: Vec<_> = ["inputs", "code", "guess", "response"]
+            .into_iter()
+            .map(cost)
+            .collect();
+        assert_eq!(
+            top.iter().map(|c| c.constraints).sum::<usize>(),
+            report.total.constraints
+        );
+        assert_eq!(
+            top.iter().map(|c| c.witness_variables).sum::<usize>(),
+            report.total.witness_variables
+        );
+        // The guess and the response are public, the code is not.
+        assert_eq!(cost("inputs").public_inputs, report.total.public_inputs);
+        assert_eq!(report.total.public_inputs, 1 + 6 * 64);
+        assert_eq!(cost("code").public_inputs, 0);
+
+        // Nested gadgets are counted in their parents.
+        let code = cost("code");
+        let legal = cost("code/legal");
+        let commitment = cost("code/commitment");
+        assert!(commitment.constraints > 0 && legal.constraints > 0);
+        assert_eq!(code.constraints, legal.constraints + commitment.constraints);
+        assert!(report.gadgets.iter().all(|cost| cost.calls == 1));
+
+        // Packing the public inputs trades public inputs for constraints.
+        let packed = measure(game(InputEncoding::Packed)).unwrap();
+        assert_eq!(packed.total.public_inputs, 2);
+        assert!(packed
+            .gadgets
+            .iter()
+            .any(|cost| cost.path == "inputs/packing"));
+    }
+
+    #[test]
+    fn test_unsatisfied_constraint() {
+        assert_eq!(
+            unsatisfied_constraint(game(InputEncoding::Bits)).unwrap(),
+            None
+        );
+
+        let wrong_response = MastermindCircuit {
+            num_partial_correct: Some(1),
+            ..game(InputEncoding::Bits)
+        };
+        let path = unsatisfied_constraint(wrong_response).unwrap().unwrap();
+        assert!(path.contains("response"), "{path}");
+        assert!(path.contains("partially_correct"), "{path}");
+
+        let wrong_hash = MastermindCircuit {
+            hash: Some(Fr::from(1u64)),
+            ..game(InputEncoding::Bits)
+        };
+        let path = unsatisfied_constraint(wrong_hash).unwrap().unwrap();
+        assert!(path.contains("commitment"), "{path}");
+    }
+}
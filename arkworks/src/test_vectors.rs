@@ -0,0 +1,169 @@
+//! Loader for the test vectors shared by the Rust implementations, see
+//! `test-vectors/README.md` at the root of the repository for the format.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use ark_bn254::Fr;
+use serde::Deserialize;
+
+use crate::{compute_commitment, GameVariant, HashScheme, InputEncoding, MastermindCircuit};
+
+/**
+ * The test vectors checked into the repository.
+ */
+pub const DEFAULT_TEST_VECTORS: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../test-vectors/mastermind.json"
+);
+
+/**
+ * Key of the commitment under the given scheme in `expected_hash`. The
+ * circomlib commitment is shared by all the implementations.
+ */
+pub fn hash_key(scheme: HashScheme) -> &'static str {
+    match scheme {
+        HashScheme::Circomlib => "circomlib",
+        HashScheme::Legacy => "arkworks",
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TestVectorFile {
+    pub vectors: Vec<TestVector>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct Params {
+    // number of peg colors
+    pub colors: usize,
+    // number of pegs in the code
+    pub pegs: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    pub params: Params,
+    // field element, as a decimal string
+    pub nonce: String,
+    pub code: Vec<u64>,
+    pub guess: Vec<u64>,
+    // commitment to (nonce, code), keyed by hash scheme
+    #[serde(default)]
+    pub expected_hash: BTreeMap<String, String>,
+    // claimed response
+    pub expected_black: u64,
+    pub expected_white: u64,
+    // whether the circuit should accept the claimed response
+    pub expected_valid: bool,
+}
+
+impl TestVector {
+    /**
+     * The commitment under the given scheme: the expected hash if the vector
+     * has one, otherwise the hash computed from the nonce and the code.
+     */
+    pub fn hash(&self, scheme: HashScheme) -> Option<Fr> {
+        match self.expected_hash.get(hash_key(scheme)) {
+            Some(hash) => Fr::from_str(hash).ok(),
+            None => Some(compute_commitment(self.nonce()?, &self.code, scheme)),
+        }
+    }
+
+    /**
+     * The nonce, or None if it is not a field element.
+     */
+    pub fn nonce(&self) -> Option<Fr> {
+        Fr::from_str(&self.nonce).ok()
+    }
+
+    /**
+     * Build the circuit for this vector. Returns `None` if the parameters do
+     * not match NPEGS and SZ, or the nonce is not a field element.
+     */
+    pub fn to_circuit<const NPEGS: usize, const SZ: usize>(
+        &self,
+    ) -> Option<MastermindCircuit<Fr, NPEGS, SZ>> {
+        if self.params
+            != (Params {
+                colors: NPEGS,
+                pegs: SZ,
+            })
+        {
+            return None;
+        }
+        let variant = GameVariant::CLASSIC;
+        Some(MastermindCircuit {
+            code: self
+                .code
+                .iter()
+                .copied()
+                .map(Some)
+                .collect::<Vec<_>>()
+                .try_into()
+                .ok()?,
+            nonce: Some(self.nonce()?),
+            hash: Some(self.hash(variant.commitment)?),
+            num_partial_correct: Some(self.expected_white),
+            num_fully_correct: Some(self.expected_black),
+            guess: self
+                .guess
+                .iter()
+                .copied()
+                .map(Some)
+                .collect::<Vec<_>>()
+                .try_into()
+                .ok()?,
+            variant,
+            encoding: InputEncoding::Bits,
+        })
+    }
+}
+
+pub fn load_test_vectors(path: impl AsRef<Path>) -> io::Result<Vec<TestVector>> {
+    let file = File::open(path)?;
+    let parsed: TestVectorFile = serde_json::from_reader(BufReader::new(file))?;
+    Ok(parsed.vectors)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+
+    #[test]
+    fn test_vectors() {
+        let vectors = load_test_vectors(DEFAULT_TEST_VECTORS).unwrap();
+        assert!(!vectors.is_empty());
+        for vector in vectors {
+            for scheme in [HashScheme::Circomlib, HashScheme::Legacy] {
+                if let (Some(expected), Some(nonce)) =
+                    (vector.expected_hash.get(hash_key(scheme)), vector.nonce())
+                {
+                    assert_eq!(
+                        compute_commitment(nonce, &vector.code, scheme).to_string(),
+                        *expected,
+                        "{}: {:?} hash mismatch",
+                        vector.name,
+                        scheme
+                    );
+                }
+            }
+            let circuit = vector
+                .to_circuit::<6, 4>()
+                .unwrap_or_else(|| panic!("{}: unsupported vector", vector.name));
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert_eq!(
+                cs.is_satisfied().unwrap(),
+                vector.expected_valid,
+                "{}",
+                vector.name
+            );
+        }
+    }
+}
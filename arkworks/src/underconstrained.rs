@@ -0,0 +1,465 @@
+//! Detection of under-constrained witnesses, i.e. of witnesses a prover can
+//! change without changing the public inputs or violating a constraint.
+//!
+//! Two passes run over the constraints of a circuit, as read by
+//! `R1cs::from_constraint_system`:
+//!
+//! - `analyze` finds the witnesses used by no constraint, and the ones that
+//!   are not determined by the inputs: starting from the public and private
+//!   inputs, a witness is determined once a constraint fixes it as the only
+//!   unknown of a linear combination, or as a bit of the binary decomposition
+//!   of a determined value. Solving `a · b = c` for b with a and c known is
+//!   only done when nothing else is left, and b is reported as determined only
+//!   if a is not zero. The undetermined witnesses over-approximate the
+//!   under-constrained ones: range checks elsewhere, for one, can make a
+//!   witness unique without the propagation seeing it.
+//! - `perturb` takes a satisfying assignment, and tries other values for each
+//!   witness in turn, the other wires being fixed. A witness which can change
+//!   is under-constrained for that assignment, but the search only finds the
+//!   witnesses free on their own, not the ones that must change together.
+
+use std::collections::VecDeque;
+
+use ark_ff::{BigInteger, PrimeField};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
+use ark_std::rand::Rng;
+
+use crate::r1cs_file::{self, Constraint, LinearCombination, R1cs};
+
+/**
+ * The witnesses found by `analyze`, by wire.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Analysis {
+    // Witnesses in no constraint
+    pub unused: Vec<usize>,
+    // Witnesses in some constraint, but not determined by the inputs
+    pub undetermined: Vec<usize>,
+    // Witnesses determined by the inputs only if some value is not zero, like
+    // the inverse of x in x·inv = 1 - (x == 0)
+    pub conditional: Vec<usize>,
+}
+
+/**
+ * A witness which can take another value in a satisfying assignment.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Perturbation<F: PrimeField> {
+    pub wire: usize,
+    pub value: F,
+    pub alternative: F,
+}
+
+/**
+ * Both passes over a circuit: `analyze`, then `perturb` with the witness of
+ * the circuit.
+ */
+#[derive(Clone, Debug)]
+pub struct Report<F: PrimeField> {
+    pub num_witnesses: usize,
+    pub analysis: Analysis,
+    pub free: Vec<Perturbation<F>>,
+}
+
+/**
+ * The wires with a non-zero coefficient in the linear combination, with their
+ * coefficients summed.
+ */
+fn merge<F: PrimeField>(lcs: &[(&LinearCombination<F>, F)]) -> Vec<(usize, F)> {
+    let mut merged: Vec<(usize, F)> = vec![];
+    for (lc, scale) in lcs {
+        for &(wire, coeff) in lc.iter() {
+            match merged.iter_mut().find(|(w, _)| *w == wire) {
+                Some((_, sum)) => *sum += coeff * scale,
+                None => merged.push((wire, coeff * scale)),
+            }
+        }
+    }
+    merged.retain(|(_, coeff)| !coeff.is_zero());
+    merged
+}
+
+/**
+ * The value of a linear combination made of the constant one only, if it is.
+ */
+fn constant<F: PrimeField>(lc: &LinearCombination<F>) -> Option<F> {
+    let merged = merge(&[(lc, F::one())]);
+    match merged.as_slice() {
+        [] => Some(F::zero()),
+        [(0, value)] => Some(*value),
+        _ => None,
+    }
+}
+
+/**
+ * The wire constrained to be 0 or 1 by the constraint, if any: a·b = c with
+ * a, b and c affine in a single wire x, and a·b - c = k·(x² - x).
+ */
+fn boolean<F: PrimeField>(constraint: &Constraint<F>) -> Option<usize> {
+    let wires: Vec<usize> = [&constraint.a, &constraint.b, &constraint.c]
+        .into_iter()
+        .flat_map(|lc| merge(&[(lc, F::one())]))
+        .map(|(wire, _)| wire)
+        .filter(|&wire| wire != 0)
+        .collect();
+    let x = *wires.first()?;
+    if wires.iter().any(|&wire| wire != x) {
+        return None;
+    }
+    let affine = |lc: &LinearCombination<F>| {
+        let merged = merge(&[(lc, F::one())]);
+        let get = |wire| {
+            merged
+                .iter()
+                .find(|(w, _)| *w == wire)
+                .map_or(F::zero(), |(_, c)| *c)
+        };
+        (get(0), get(x))
+    };
+    let ((a0, a1), (b0, b1), (c0, c1)) = (
+        affine(&constraint.a),
+        affine(&constraint.b),
+        affine(&constraint.c),
+    );
+    let k = a1 * b1;
+    (!k.is_zero() && a0 * b1 + a1 * b0 - c1 == -k && a0 * b0 == c0).then_some(x)
+}
+
+/**
+ * Whether x or -x is 2^e for some e, returning e.
+ */
+fn power_of_two<F: PrimeField>(x: F) -> Option<u64> {
+    [x, -x].into_iter().find_map(|x| {
+        let bits = x.into_bigint();
+        let e = bits.num_bits().checked_sub(1)? as u64;
+        (F::from(2u64).pow([e]) == x).then_some(e)
+    })
+}
+
+/**
+ * Whether the unknown wires of a linear constraint are bits, weighted by
+ * distinct powers of two up to a common factor, small enough that their sum
+ * cannot wrap around the modulus: the bits are then unique.
+ */
+fn is_binary_decomposition<F: PrimeField>(unknown: &[(usize, F)], booleans: &[bool]) -> bool {
+    let Some(&(_, first)) = unknown.first() else {
+        return false;
+    };
+    if unknown.iter().any(|&(wire, _)| !booleans[wire]) {
+        return false;
+    }
+    let first_inverse = first.inverse().unwrap();
+    let mut exponents = vec![];
+    for &(_, coeff) in unknown {
+        match power_of_two(coeff * first_inverse) {
+            Some(e) if (e as u32) < F::MODULUS_BIT_SIZE - 2 => exponents.push(e),
+            _ => return false,
+        }
+    }
+    exponents.sort_unstable();
+    exponents.windows(2).all(|pair| pair[0] != pair[1])
+}
+
+/**
+ * Find the witnesses used by no constraint and the ones the propagation from
+ * the inputs does not determine. The inputs are the public wires and the
+ * `num_private_inputs` wires after them.
+ */
+pub fn analyze<F: PrimeField>(r1cs: &R1cs<F>) -> Analysis {
+    let num_inputs = 1 + r1cs.num_public() + r1cs.num_private_inputs;
+    let mut known = vec![false; r1cs.num_wires];
+    known[..num_inputs.min(r1cs.num_wires)].fill(true);
+
+    // Constraints using each wire, and the wires constrained to be bits
+    let mut uses = vec![vec![]; r1cs.num_wires];
+    let mut booleans = vec![false; r1cs.num_wires];
+    for (i, constraint) in r1cs.constraints.iter().enumerate() {
+        for lc in [&constraint.a, &constraint.b, &constraint.c] {
+            for &(wire, _) in lc {
+                if uses[wire].last() != Some(&i) {
+                    uses[wire].push(i);
+                }
+            }
+        }
+        if let Some(wire) = boolean(constraint) {
+            booleans[wire] = true;
+        }
+    }
+
+    // The wires a constraint determines given the known ones, and whether it
+    // only does if a known factor is not zero
+    let determined = |constraint: &Constraint<F>, known: &[bool]| -> (Vec<usize>, bool) {
+        let unknown = |lc: &[(&LinearCombination<F>, F)]| -> Vec<(usize, F)> {
+            merge(lc)
+                .into_iter()
+                .filter(|&(wire, _)| !known[wire])
+                .collect()
+        };
+        let (a, b, c) = (&constraint.a, &constraint.b, &constraint.c);
+        let one = F::one();
+        let (unknown, conditional) = match (constant(a), constant(b)) {
+            // a·b = c with a or b constant is the linear k·b - c = 0 or k·a - c = 0.
+            (Some(k), _) if !k.is_zero() => (unknown(&[(b, k), (c, -one)]), false),
+            (_, Some(k)) if !k.is_zero() => (unknown(&[(a, k), (c, -one)]), false),
+            _ => match (
+                unknown(&[(a, one)]),
+                unknown(&[(b, one)]),
+                unknown(&[(c, one)]),
+            ) {
+                // With a and b known, c is linear.
+                (ua, ub, uc) if ua.is_empty() && ub.is_empty() => (uc, false),
+                // With c and one factor known, the other factor is linear if
+                // the known one is not zero.
+                (ua, ub, uc) if ua.is_empty() && uc.is_empty() => (ub, true),
+                (ua, ub, uc) if ub.is_empty() && uc.is_empty() => (ua, true),
+                _ => return (vec![], false),
+            },
+        };
+        if unknown.len() == 1 || is_binary_decomposition(&unknown, &booleans) {
+            (
+                unknown.into_iter().map(|(wire, _)| wire).collect(),
+                conditional,
+            )
+        } else {
+            (vec![], false)
+        }
+    };
+
+    // Propagate the unconditional steps first, a conditional one only when
+    // there are none left.
+    let mut conditional = vec![false; r1cs.num_wires];
+    let mut queue: VecDeque<usize> = (0..r1cs.constraints.len()).collect();
+    let mut deferred = VecDeque::new();
+    loop {
+        while let Some(i) = queue.pop_front() {
+            let (wires, is_conditional) = determined(&r1cs.constraints[i], &known);
+            if is_conditional {
+                deferred.push_back(i);
+                continue;
+            }
+            for wire in wires {
+                if !known[wire] {
+                    known[wire] = true;
+                    queue.extend(&uses[wire]);
+                }
+            }
+        }
+        let Some(i) = deferred.pop_front() else {
+            break;
+        };
+        let (wires, _) = determined(&r1cs.constraints[i], &known);
+        for wire in wires {
+            if !known[wire] {
+                known[wire] = true;
+                conditional[wire] = true;
+                queue.extend(&uses[wire]);
+            }
+        }
+    }
+
+    let mut analysis = Analysis::default();
+    for wire in num_inputs..r1cs.num_wires {
+        if uses[wire].is_empty() {
+            analysis.unused.push(wire);
+        } else if !known[wire] {
+            analysis.undetermined.push(wire);
+        } else if conditional[wire] {
+            analysis.conditional.push(wire);
+        }
+    }
+    analysis
+}
+
+/**
+ * Try other values for every witness of a satisfying assignment, one at a
+ * time, and return the witnesses which can change with all the constraints
+ * still satisfied. None if the assignment does not satisfy the constraints.
+ */
+pub fn perturb<F: PrimeField, R: Rng>(
+    r1cs: &R1cs<F>,
+    witness: &[F],
+    rng: &mut R,
+) -> Option<Vec<Perturbation<F>>> {
+    if !r1cs.is_satisfied(witness) {
+        return None;
+    }
+    let mut uses = vec![vec![]; r1cs.num_wires];
+    for (i, constraint) in r1cs.constraints.iter().enumerate() {
+        for lc in [&constraint.a, &constraint.b, &constraint.c] {
+            for &(wire, _) in lc {
+                if uses[wire].last() != Some(&i) {
+                    uses[wire].push(i);
+                }
+            }
+        }
+    }
+
+    let eval = |lc: &LinearCombination<F>, assignment: &[F]| -> F {
+        lc.iter()
+            .map(|&(wire, coeff)| assignment[wire] * coeff)
+            .sum()
+    };
+    let mut assignment = witness.to_vec();
+    let mut free = vec![];
+    for wire in 1 + r1cs.num_public()..r1cs.num_wires {
+        let value = witness[wire];
+        let candidates = [
+            value + F::one(),
+            value - F::one(),
+            F::one() - value,
+            F::zero(),
+            F::one(),
+            F::rand(rng),
+        ];
+        let alternative = candidates.into_iter().filter(|&v| v != value).find(|&v| {
+            assignment[wire] = v;
+            uses[wire].iter().all(|&i| {
+                let c = &r1cs.constraints[i];
+                eval(&c.a, &assignment) * eval(&c.b, &assignment) == eval(&c.c, &assignment)
+            })
+        });
+        assignment[wire] = value;
+        if let Some(alternative) = alternative {
+            free.push(Perturbation {
+                wire,
+                value,
+                alternative,
+            });
+        }
+    }
+    Some(free)
+}
+
+/**
+ * Synthesize the circuit with its witness, and run both passes over it. The
+ * first `num_private_inputs` witnesses are the private inputs, determined by
+ * the prover rather than by the constraints.
+ */
+pub fn check<F: PrimeField, C: ConstraintSynthesizer<F>, R: Rng>(
+    circuit: C,
+    num_private_inputs: usize,
+    rng: &mut R,
+) -> Result<Report<F>, SynthesisError> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+    let mut r1cs = R1cs::from_constraint_system(&cs).ok_or(SynthesisError::MissingCS)?;
+    r1cs.num_private_inputs = num_private_inputs;
+    let witness = r1cs_file::witness(&cs).ok_or(SynthesisError::AssignmentMissing)?;
+    let free = perturb(&r1cs, &witness, rng).ok_or(SynthesisError::Unsatisfiable)?;
+    Ok(Report {
+        num_witnesses: r1cs.num_wires - 1 - r1cs.num_public(),
+        analysis: analyze(&r1cs),
+        free,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{secret::Secret, GameVariant, InputEncoding, MastermindCircuit};
+    use ark_bn254::Fr;
+    use ark_relations::lc;
+    use ark_relations::r1cs::{ConstraintSystemRef, Variable};
+    use ark_std::rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    /**
+     * x public, with witnesses z = x·x, b the 8 bits of z, y with x·y = 0, u
+     * and v with u + v = x, and w in no constraint.
+     */
+    #[derive(Clone)]
+    struct Toy {
+        x: u64,
+    }
+
+    impl ConstraintSynthesizer<Fr> for Toy {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            let x = Fr::from(self.x);
+            let xv = cs.new_input_variable(|| Ok(x))?;
+            let z = cs.new_witness_variable(|| Ok(x * x))?;
+            cs.enforce_constraint(lc!() + xv, lc!() + xv, lc!() + z)?;
+            let mut bits = lc!();
+            for i in 0..8 {
+                let b = cs.new_witness_variable(|| Ok(Fr::from(((self.x * self.x) >> i) & 1)))?;
+                cs.enforce_constraint(lc!() + b, lc!() + Variable::One - b, lc!())?;
+                bits += (Fr::from(1u64 << i), b);
+            }
+            cs.enforce_constraint(bits, lc!() + Variable::One, lc!() + z)?;
+            let y = cs.new_witness_variable(|| Ok(Fr::from(7 * (self.x == 0) as u64)))?;
+            cs.enforce_constraint(lc!() + xv, lc!() + y, lc!())?;
+            let u = cs.new_witness_variable(|| Ok(Fr::from(1u64)))?;
+            let v = cs.new_witness_variable(|| Ok(x - Fr::from(1u64)))?;
+            cs.enforce_constraint(lc!() + u + v, lc!() + Variable::One, lc!() + xv)?;
+            cs.new_witness_variable(|| Ok(Fr::from(42u64)))?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_toy() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        // Wires: 0 the constant one, 1 x, 2 z, 3 to 10 the bits, then y, u,
+        // v, w.
+        let (y, u, v, w) = (11, 12, 13, 14);
+        for x in [0, 11] {
+            let report = check(Toy { x }, 0, rng).unwrap();
+            assert_eq!(report.num_witnesses, 13);
+            assert_eq!(report.analysis.unused, [w]);
+            assert_eq!(report.analysis.conditional, [y]);
+            assert_eq!(report.analysis.undetermined, [u, v]);
+
+            // y is free when x is zero, and u and v must change together.
+            let free: Vec<usize> = report.free.iter().map(|p| p.wire).collect();
+            if x == 0 {
+                assert_eq!(free, [y, w]);
+            } else {
+                assert_eq!(free, [w]);
+            }
+        }
+
+        // Only satisfying assignments are perturbed.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        Toy { x: 3 }.generate_constraints(cs.clone()).unwrap();
+        let r1cs = R1cs::from_constraint_system(&cs).unwrap();
+        let mut witness = r1cs_file::witness(&cs).unwrap();
+        witness[2] += Fr::from(1u64);
+        assert!(perturb(&r1cs, &witness, rng).is_none());
+    }
+
+    #[test]
+    fn test_mastermind() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(0);
+        let secret = Secret::<Fr, 4>::new([1, 2, 3, 4], rng);
+        let num_private_inputs = MastermindCircuit::<Fr, 6, 4>::NUM_PRIVATE_INPUTS;
+        for encoding in [InputEncoding::Bits, InputEncoding::Packed] {
+            let circuit = MastermindCircuit::<Fr, 6, 4> {
+                code: secret.code.map(Some),
+                nonce: Some(secret.nonce),
+                hash: Some(secret.commitment(GameVariant::CLASSIC.commitment)),
+                num_partial_correct: Some(2),
+                num_fully_correct: Some(2),
+                guess: [Some(1), Some(2), Some(4), Some(3)],
+                variant: GameVariant::CLASSIC,
+                encoding,
+            };
+            let num_public = circuit.public_inputs().unwrap().len();
+            let report = check(circuit, num_private_inputs, rng).unwrap();
+            assert!(report.analysis.unused.is_empty());
+            if encoding == InputEncoding::Bits {
+                assert!(report.analysis.undetermined.is_empty());
+            }
+
+            // The inverses of the equality checks between equal values are
+            // free, and they are all the free witnesses: the inputs cannot
+            // change.
+            assert!(!report.free.is_empty());
+            for perturbation in &report.free {
+                assert!(perturbation.wire > num_public + num_private_inputs);
+                assert!(
+                    report.analysis.conditional.contains(&perturbation.wire)
+                        || report.analysis.undetermined.contains(&perturbation.wire)
+                );
+            }
+        }
+    }
+}
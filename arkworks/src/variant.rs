@@ -0,0 +1,175 @@
+//! Game variants supported by the mastermind circuit, together with a native
+//! (out of circuit) reference implementation of the rules.
+
+use crate::HashScheme;
+
+/**
+ * How the response to a guess is computed.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scoring {
+    /// Classic black/white pegs: the white pegs are the colors shared by the
+    /// code and the guess (counted with multiplicity) minus the black pegs.
+    #[default]
+    Classic,
+    /// Bulls and Cows (a.k.a. Bagels): both the code and the guess must be made
+    /// of distinct digits, and the cows are the pairs (i, j), i != j, with
+    /// code[i] == guess[j].
+    BullsAndCows,
+}
+
+/**
+ * The rules of the game. The variant is fixed when the circuit is set up, as
+ * it changes the shape of the constraint system.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameVariant {
+    /// Whether the code may use the same color more than once.
+    pub duplicates: bool,
+    /// Whether pegs may be left blank. A blank is encoded as the extra color
+    /// NPEGS and is scored like any other color.
+    pub blanks: bool,
+    pub scoring: Scoring,
+    /// How the codemaker commits to the code. Both players must agree on it,
+    /// like on the rules.
+    pub commitment: HashScheme,
+}
+
+impl Default for GameVariant {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+impl GameVariant {
+    pub const CLASSIC: GameVariant = GameVariant {
+        duplicates: true,
+        blanks: false,
+        scoring: Scoring::Classic,
+        commitment: HashScheme::Circomlib,
+    };
+
+    pub const BULLS_AND_COWS: GameVariant = GameVariant {
+        duplicates: false,
+        blanks: false,
+        scoring: Scoring::BullsAndCows,
+        commitment: HashScheme::Circomlib,
+    };
+
+    /**
+     * The number of legal peg values, including the blank if allowed.
+     */
+    pub fn num_colors<const NPEGS: usize>(&self) -> u64 {
+        NPEGS as u64 + self.blanks as u64
+    }
+
+    /**
+     * Whether the code must be made of distinct colors.
+     */
+    pub fn distinct_code(&self) -> bool {
+        !self.duplicates || self.scoring == Scoring::BullsAndCows
+    }
+
+    /**
+     * Whether the guess must be made of distinct colors.
+     */
+    pub fn distinct_guess(&self) -> bool {
+        self.scoring == Scoring::BullsAndCows
+    }
+
+    pub fn is_legal_code<const NPEGS: usize>(&self, code: &[u64]) -> bool {
+        self.pegs_in_range::<NPEGS>(code) && (!self.distinct_code() || all_distinct(code))
+    }
+
+    pub fn is_legal_guess<const NPEGS: usize>(&self, guess: &[u64]) -> bool {
+        self.pegs_in_range::<NPEGS>(guess) && (!self.distinct_guess() || all_distinct(guess))
+    }
+
+    /**
+     * Reference scorer. Returns (number fully correct, number partially
+     * correct) for a legal code and guess.
+     */
+    pub fn score(&self, code: &[u64], guess: &[u64]) -> (u64, u64) {
+        let fully_correct = code.iter().zip(guess).filter(|(c, g)| c == g).count() as u64;
+        let partial_correct = match self.scoring {
+            Scoring::Classic => {
+                let mut colors = code.to_vec();
+                colors.sort_unstable();
+                colors.dedup();
+                let common: u64 = colors
+                    .iter()
+                    .map(|c| count(code, *c).min(count(guess, *c)))
+                    .sum();
+                common - fully_correct
+            }
+            Scoring::BullsAndCows => {
+                let mut cows = 0;
+                for (i, c) in code.iter().enumerate() {
+                    for (j, g) in guess.iter().enumerate() {
+                        if i != j && c == g {
+                            cows += 1;
+                        }
+                    }
+                }
+                cows
+            }
+        };
+        (fully_correct, partial_correct)
+    }
+
+    fn pegs_in_range<const NPEGS: usize>(&self, pegs: &[u64]) -> bool {
+        pegs.iter().all(|p| *p < self.num_colors::<NPEGS>())
+    }
+}
+
+fn count(pegs: &[u64], color: u64) -> u64 {
+    pegs.iter().filter(|p| **p == color).count() as u64
+}
+
+fn all_distinct(pegs: &[u64]) -> bool {
+    (0..pegs.len()).all(|i| (i + 1..pegs.len()).all(|j| pegs[i] != pegs[j]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_classic_score() {
+        let v = GameVariant::CLASSIC;
+        assert_eq!(v.score(&[1, 2, 3, 4], &[1, 2, 4, 3]), (2, 2));
+        assert_eq!(v.score(&[0, 0, 0, 0], &[1, 2, 4, 3]), (0, 0));
+        assert_eq!(v.score(&[0, 3, 3, 5], &[1, 3, 0, 5]), (2, 1));
+        assert_eq!(v.score(&[1, 1, 2, 2], &[2, 2, 1, 1]), (0, 4));
+    }
+
+    #[test]
+    fn test_bulls_and_cows_score() {
+        let v = GameVariant::BULLS_AND_COWS;
+        assert_eq!(v.score(&[1, 2, 3, 4], &[1, 2, 4, 3]), (2, 2));
+        assert_eq!(v.score(&[9, 8, 7, 6], &[6, 1, 8, 0]), (0, 2));
+        assert!(v.is_legal_code::<10>(&[9, 8, 7, 6]));
+        assert!(!v.is_legal_code::<10>(&[9, 9, 7, 6]));
+        assert!(!v.is_legal_guess::<10>(&[1, 1, 2, 3]));
+    }
+
+    #[test]
+    fn test_legality() {
+        let no_dups = GameVariant {
+            duplicates: false,
+            ..GameVariant::CLASSIC
+        };
+        assert!(no_dups.is_legal_code::<6>(&[0, 1, 2, 3]));
+        assert!(!no_dups.is_legal_code::<6>(&[0, 1, 2, 2]));
+        // duplicates in the guess are still fine
+        assert!(no_dups.is_legal_guess::<6>(&[2, 2, 2, 2]));
+
+        let blanks = GameVariant {
+            blanks: true,
+            ..GameVariant::CLASSIC
+        };
+        assert!(!GameVariant::CLASSIC.is_legal_code::<6>(&[6, 1, 2, 3]));
+        assert!(blanks.is_legal_code::<6>(&[6, 1, 2, 3]));
+        assert!(!blanks.is_legal_code::<6>(&[7, 1, 2, 3]));
+    }
+}
@@ -0,0 +1,70 @@
+//! Runs a ceremony with the binary, one process per participant.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn ceremony(args: &[&str], dir: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_arkworks"))
+        .arg("ceremony")
+        .args(args)
+        .arg(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+#[ignore = "takes minutes without --release"]
+fn test_ceremony() {
+    let dir = std::env::temp_dir().join(format!("mastermind-ceremony-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(ceremony(&["init"], &dir).status.success());
+    // Only one ceremony per directory.
+    assert!(!ceremony(&["init"], &dir).status.success());
+    // Nor keys without any contribution.
+    assert!(!ceremony(&["finalize"], &dir).status.success());
+
+    for entropy in ["alice", "bob", "carol"] {
+        let output = ceremony(&["contribute", "--entropy", entropy], &dir);
+        assert!(output.status.success());
+    }
+    let output = ceremony(&["verify"], &dir);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("3 contributions verified"));
+
+    let output = ceremony(&["finalize"], &dir);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Proof verified"));
+    for file in [
+        "proving_key.bin",
+        "verifying_key.bin",
+        "verification_key.json",
+    ] {
+        assert!(dir.join(file).exists());
+    }
+
+    // The CLI proves the published game with the keys of the ceremony.
+    let output = Command::new(env!("CARGO_BIN_EXE_arkworks"))
+        .arg("--keys")
+        .arg(&dir)
+        .arg("--snarkjs")
+        .arg(dir.join("out"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("--snarkjs and --solidity outputs"));
+    assert_eq!(
+        std::fs::read(dir.join("out/mastermind-bn254/verification_key.json")).unwrap(),
+        std::fs::read(dir.join("verification_key.json")).unwrap()
+    );
+
+    // A contribution that does not match its keys.
+    std::fs::copy(
+        dir.join("contribution-1.bin"),
+        dir.join("contribution-2.bin"),
+    )
+    .unwrap();
+    assert!(!ceremony(&["verify"], &dir).status.success());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,52 @@
+use clap::Parser;
+use halo2_scaffold::game::client::CodebreakerClient;
+use halo2_scaffold::mastermind::variant::GameVariant;
+
+#[derive(Parser, Debug)]
+/// Play guesses against a running codemaker server, verifying every response.
+struct Args {
+    /// Guesses to submit, each given as comma separated pegs (e.g. `1,3,0,5`)
+    #[arg(required = true, value_parser = parse_guess)]
+    guesses: Vec<[u64; 4]>,
+    #[arg(short = 'k', long = "degree", default_value_t = 7)]
+    degree: u32,
+    #[arg(long, default_value_t = 3)]
+    lookup_bits: usize,
+    /// Rules of the game, as the JSON of a `GameVariant`. The verifying keys are derived from them, so they must be
+    /// the server's
+    #[arg(long, value_parser = parse_variant, default_value = "{}")]
+    variant: GameVariant,
+    #[arg(short, long, default_value = "127.0.0.1:7878")]
+    addr: String,
+}
+
+fn parse_guess(s: &str) -> Result<[u64; 4], String> {
+    let pegs = s
+        .split(',')
+        .map(|p| p.trim().parse::<u64>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    pegs.try_into()
+        .map_err(|_| "a guess must have exactly 4 pegs".to_string())
+}
+
+fn parse_variant(s: &str) -> Result<GameVariant, String> {
+    serde_json::from_str(s).map_err(|e| e.to_string())
+}
+
+fn main() {
+    env_logger::init();
+    let args = Args::parse();
+
+    let mut client =
+        CodebreakerClient::connect(&args.addr, args.degree, args.lookup_bits, args.variant)
+            .expect("Failed to connect to the server");
+    let hash = client.commitment().expect("Invalid commitment");
+    println!("Verified commitment: {hash:?}");
+    for guess in args.guesses {
+        let feedback = client.guess(guess).expect("Invalid response");
+        println!(
+            "Guess {guess:?}: {} correct, {} partial (verified)",
+            feedback.correct, feedback.partial
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::Parser;
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_scaffold::mastermind::{init, validate};
+
+#[derive(Parser, Debug)]
+/// Compute the commitment to a code natively and write a self-consistent input for the `validate` circuit.
+struct Args {
+    /// Secret code and nonce, in the same format as `data/init.in`
+    #[arg(short, long, default_value = "data/init.in")]
+    secret: PathBuf,
+    /// Nonce, instead of the one in the secret file
+    #[arg(long)]
+    nonce: Option<String>,
+    /// Code, given as comma separated pegs (e.g. `0,3,3,5`), instead of the one in the secret file
+    #[arg(long, value_parser = parse_pegs)]
+    code: Option<[u64; 4]>,
+    /// The codebreaker's guess, given as comma separated pegs (e.g. `1,3,0,5`)
+    #[arg(short, long, value_parser = parse_pegs)]
+    guess: [u64; 4],
+    /// Where to write the input of the `validate` circuit
+    #[arg(short, long, default_value = "data/validate.in")]
+    output: PathBuf,
+}
+
+fn parse_pegs(s: &str) -> Result<[u64; 4], String> {
+    let pegs = s
+        .split(',')
+        .map(|p| p.trim().parse::<u64>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    pegs.try_into()
+        .map_err(|_| "a code must have exactly 4 pegs".to_string())
+}
+
+fn main() {
+    env_logger::init();
+    let args = Args::parse();
+
+    let mut secret = match (&args.nonce, &args.code) {
+        // Nothing is read from the secret file, which does not need to exist.
+        (Some(nonce), Some(code)) => init::CircuitInput {
+            nonce: nonce.clone(),
+            pegs: code.map(|p| p.to_string()),
+            variant: Default::default(),
+        },
+        _ => serde_json::from_reader(
+            File::open(&args.secret)
+                .unwrap_or_else(|e| panic!("Secret file not found at {:?}. {e:?}", args.secret)),
+        )
+        .expect("Secret file should be a valid JSON file"),
+    };
+    if let Some(nonce) = args.nonce {
+        secret.nonce = nonce;
+    }
+    if let Some(code) = args.code {
+        secret.pegs = code.map(|p| p.to_string());
+    }
+
+    let input = validate::CircuitInput::for_game::<Fr>(&secret, args.guess);
+    println!("Hash output: {}", input.hash);
+    println!(
+        "Response: {} correct, {} partial",
+        input.correct.unwrap(),
+        input.partial.unwrap()
+    );
+
+    let file = File::create(&args.output)
+        .unwrap_or_else(|e| panic!("Could not create {:?}. {e:?}", args.output));
+    serde_json::to_writer_pretty(file, &input).expect("Failed to write the input");
+    println!("Input written to: {:?}", args.output);
+}
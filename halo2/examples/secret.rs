@@ -0,0 +1,85 @@
+use std::fs::OpenOptions;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+
+use clap::Parser;
+use halo2_scaffold::mastermind::secret::{derive_nonce, new_secret, random_code, random_nonce};
+use halo2_scaffold::mastermind::variant::GameVariant;
+use rand::rngs::OsRng;
+
+#[derive(Parser, Debug)]
+/// Generate the codemaker's secret, i.e. a code and a full-size nonce, and write it in the format of `data/init.in`.
+/// The file is only readable by its owner.
+struct Args {
+    /// Code, given as comma separated pegs (e.g. `0,3,3,5`). A random legal code is drawn if not given
+    #[arg(long, value_parser = parse_pegs)]
+    code: Option<[u64; 4]>,
+    /// Derive the nonce from this seed phrase instead of drawing it from the OS RNG
+    #[arg(long)]
+    seed_phrase: Option<String>,
+    /// Game number, so that every game derived from the same seed phrase gets its own nonce
+    #[arg(long, default_value_t = 0, requires = "seed_phrase")]
+    game: u64,
+    /// Rules of the game, as the JSON of a `GameVariant` (e.g. `{"colors": 8, "blanks": true}`)
+    #[arg(long, value_parser = parse_variant, default_value = "{}")]
+    variant: GameVariant,
+    /// Where to write the secret
+    #[arg(short, long, default_value = "data/secret.in")]
+    output: PathBuf,
+    /// Overwrite the output if it exists
+    #[arg(long)]
+    force: bool,
+}
+
+fn parse_variant(s: &str) -> Result<GameVariant, String> {
+    serde_json::from_str(s).map_err(|e| e.to_string())
+}
+
+fn parse_pegs(s: &str) -> Result<[u64; 4], String> {
+    let pegs = s
+        .split(',')
+        .map(|p| p.trim().parse::<u64>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    pegs.try_into()
+        .map_err(|_| "a code must have exactly 4 pegs".to_string())
+}
+
+fn main() {
+    env_logger::init();
+    let args = Args::parse();
+
+    let variant = args.variant;
+    let code = args
+        .code
+        .unwrap_or_else(|| random_code(&variant, &mut OsRng));
+    assert!(variant.is_legal_code(&code), "{code:?} is not a legal code");
+    let nonce = match &args.seed_phrase {
+        Some(phrase) => derive_nonce(phrase, args.game),
+        None => random_nonce(&mut OsRng),
+    };
+
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if args.force {
+        options.create(true).truncate(true);
+    } else {
+        // Never overwrite a secret, which may be the only copy of a code already committed to.
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    options.mode(0o600);
+    let file = options.open(&args.output).unwrap_or_else(|e| {
+        panic!(
+            "Could not create {:?} (pass --force to overwrite it). {e:?}",
+            args.output
+        )
+    });
+    // The mode only applies to a new file, so also restrict a file overwritten with --force.
+    #[cfg(unix)]
+    file.set_permissions(PermissionsExt::from_mode(0o600))
+        .expect("Failed to restrict the permissions of the secret");
+    serde_json::to_writer_pretty(file, &new_secret(code, nonce, variant))
+        .expect("Failed to write the secret");
+    println!("Secret written to: {:?}", args.output);
+}
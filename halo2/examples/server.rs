@@ -0,0 +1,39 @@
+use std::{fs::File, net::TcpListener, path::PathBuf};
+
+use clap::Parser;
+use halo2_scaffold::game::server::{CodemakerServer, Secret};
+
+#[derive(Parser, Debug)]
+/// Run the codemaker side of a two-party game, answering guesses with proofs.
+struct Args {
+    /// Secret code and nonce, in the same format as `data/init.in`
+    #[arg(short, long, default_value = "data/init.in")]
+    secret: PathBuf,
+    #[arg(short = 'k', long = "degree", default_value_t = 7)]
+    degree: u32,
+    #[arg(long, default_value_t = 3)]
+    lookup_bits: usize,
+    /// Directory where the verifying keys and pinnings for the codebreaker are written
+    #[arg(long, default_value = "configs/game")]
+    key_dir: PathBuf,
+    #[arg(short, long, default_value = "127.0.0.1:7878")]
+    addr: String,
+}
+
+fn main() {
+    env_logger::init();
+    let args = Args::parse();
+
+    let secret: Secret = serde_json::from_reader(
+        File::open(&args.secret)
+            .unwrap_or_else(|e| panic!("Secret file not found at {:?}. {e:?}", args.secret)),
+    )
+    .expect("Secret file should be a valid JSON file");
+    let mut server = CodemakerServer::new(secret, args.degree, args.lookup_bits, &args.key_dir)
+        .expect("Failed to set up the server");
+    println!("Verifying keys written to: {:?}", args.key_dir);
+
+    let listener = TcpListener::bind(&args.addr).expect("Failed to bind");
+    println!("Listening on {}", args.addr);
+    server.serve(listener).expect("Server error");
+}
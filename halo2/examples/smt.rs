@@ -0,0 +1,52 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::Parser;
+use halo2_scaffold::mastermind::smt::{self, Verdict};
+use halo2_scaffold::mastermind::validate::CircuitInput;
+use halo2_scaffold::scaffold::lookup_bits_from_env;
+
+#[derive(Parser, Debug)]
+/// Write the uniqueness query of the response of the `validate` circuit as SMT-LIB, and run it with cvc5 if it is on
+/// the PATH. `LOOKUP_BITS` is read from the environment, as for the other examples.
+struct Args {
+    /// Input of the `validate` circuit, in the same format as `data/validate.in`
+    #[arg(short, long, default_value = "data/validate.in")]
+    input: PathBuf,
+    /// The circuit has 2^k rows
+    #[arg(short, default_value_t = 6)]
+    k: u32,
+    /// Ask whether the response is unique over all inputs, instead of for the inputs of this game only
+    #[arg(long)]
+    all_inputs: bool,
+    /// Where to write the query
+    #[arg(short, long, default_value = "data/validate.smt2")]
+    output: PathBuf,
+}
+
+fn main() {
+    env_logger::init();
+    let args = Args::parse();
+
+    let input: CircuitInput = serde_json::from_reader(
+        File::open(&args.input)
+            .unwrap_or_else(|e| panic!("Input file not found at {:?}. {e:?}", args.input)),
+    )
+    .expect("Input file should be a valid JSON file");
+    let lookup_bits = lookup_bits_from_env(args.k).expect("LOOKUP_BITS is not set");
+
+    let query = smt::validate_query(input, args.k, lookup_bits, !args.all_inputs);
+    std::fs::write(&args.output, &query)
+        .unwrap_or_else(|e| panic!("Could not write {:?}. {e:?}", args.output));
+    println!(
+        "Query of {} bytes written to: {:?}",
+        query.len(),
+        args.output
+    );
+
+    match smt::solve(&query).expect("Failed to run the solver") {
+        None => println!("{} is not on the PATH", smt::SOLVER),
+        Some(Verdict::Unique) => println!("unsat, the response is unique"),
+        Some(Verdict::NotUnique) => println!("sat, the response is NOT unique"),
+        Some(Verdict::Unknown(output)) => println!("unknown: {}", output.trim()),
+    }
+}
@@ -0,0 +1,169 @@
+//! The codebreaker side of the game.
+
+use std::{
+    fmt, io,
+    net::{TcpStream, ToSocketAddrs},
+};
+
+use halo2_base::halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::VerifyingKey,
+    poly::kzg::commitment::ParamsKZG,
+};
+use halo2_base::utils::fs::gen_srs;
+
+use super::protocol::{read_message, write_message, ProvedResponse, Request, Response};
+use super::verifying_keys;
+use crate::mastermind::variant::GameVariant;
+use crate::scaffold::verify;
+
+/// Feedback for a guess, taken from the public instances of a verified proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Feedback {
+    /// Number of pegs with the right color in the right position ("black pegs").
+    pub correct: u64,
+    /// Number of pegs with the right color in the wrong position ("white pegs").
+    pub partial: u64,
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    Io(io::Error),
+    /// The server closed the connection before answering.
+    Disconnected,
+    /// The server reported an error.
+    Server(String),
+    /// The server answered with a response of the wrong kind.
+    UnexpectedResponse(Response),
+    /// The proof does not verify against the verifying key of the game.
+    InvalidProof,
+    /// The proof verifies, but its public instances are not the ones the codebreaker expects.
+    InstanceMismatch,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {e}"),
+            Self::Disconnected => write!(f, "server disconnected"),
+            Self::Server(e) => write!(f, "server error: {e}"),
+            Self::UnexpectedResponse(r) => write!(f, "unexpected response: {r:?}"),
+            Self::InvalidProof => write!(f, "proof does not verify"),
+            Self::InstanceMismatch => write!(f, "proof public instances do not match the game"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Plays against a [`super::server::CodemakerServer`], verifying every response it receives.
+pub struct CodebreakerClient {
+    stream: TcpStream,
+    params: ParamsKZG<Bn256>,
+    init_vk: VerifyingKey<G1Affine>,
+    validate_vk: VerifyingKey<G1Affine>,
+    hash: Option<Fr>,
+}
+
+impl CodebreakerClient {
+    /// Connects to the server, deriving the verifying keys of the game from the degree `k`, the lookup bits and the
+    /// variant, which must be the server's.
+    pub fn connect(
+        addr: impl ToSocketAddrs,
+        k: u32,
+        lookup_bits: usize,
+        variant: GameVariant,
+    ) -> io::Result<Self> {
+        let params = gen_srs(k);
+        let (init_vk, validate_vk) = verifying_keys(&params, lookup_bits, variant);
+        let stream = TcpStream::connect(addr)?;
+        Ok(Self {
+            stream,
+            params,
+            init_vk,
+            validate_vk,
+            hash: None,
+        })
+    }
+
+    /// The commitment to the code, once [`Self::commitment`] has succeeded.
+    pub fn hash(&self) -> Option<Fr> {
+        self.hash
+    }
+
+    /// Asks the codemaker to commit to their code and verifies the proof that the committed code is legal.
+    pub fn commitment(&mut self) -> Result<Fr, ClientError> {
+        let proved = match self.request(&Request::Commit)? {
+            Response::Committed(proved) => proved,
+            other => return Err(ClientError::UnexpectedResponse(other)),
+        };
+        let instances = self.verify(&self.init_vk, &proved)?;
+        let [hash] = instances[..] else {
+            return Err(ClientError::InstanceMismatch);
+        };
+        self.hash = Some(hash);
+        Ok(hash)
+    }
+
+    /// Submits a guess and fetches the proved feedback for it.
+    ///
+    /// The feedback is only returned if the proof verifies and was generated for the committed hash and this guess.
+    pub fn guess(&mut self, guess: [u64; 4]) -> Result<Feedback, ClientError> {
+        let hash = self.hash.ok_or(ClientError::InstanceMismatch)?;
+        let round = match self.request(&Request::Guess { guess })? {
+            Response::GuessAccepted { round } => round,
+            other => return Err(ClientError::UnexpectedResponse(other)),
+        };
+        let proved = match self.request(&Request::FetchResponse { round })? {
+            Response::Feedback(proved) => proved,
+            other => return Err(ClientError::UnexpectedResponse(other)),
+        };
+        let instances = self.verify(&self.validate_vk, &proved)?;
+        // Instance layout of the validate circuit: [correct, partial, hash, guess[0..4]].
+        let [correct, partial, proved_hash, proved_guess @ ..] = &instances[..] else {
+            return Err(ClientError::InstanceMismatch);
+        };
+        if *proved_hash != hash || proved_guess.iter().ne(guess.map(Fr::from).iter()) {
+            return Err(ClientError::InstanceMismatch);
+        }
+        Ok(Feedback {
+            correct: small_value(correct)?,
+            partial: small_value(partial)?,
+        })
+    }
+
+    fn request(&mut self, request: &Request) -> Result<Response, ClientError> {
+        write_message(&mut self.stream, request)?;
+        match read_message(&mut self.stream)? {
+            Some(Response::Error(e)) => Err(ClientError::Server(e)),
+            Some(response) => Ok(response),
+            None => Err(ClientError::Disconnected),
+        }
+    }
+
+    fn verify(
+        &self,
+        vk: &VerifyingKey<G1Affine>,
+        proved: &ProvedResponse,
+    ) -> Result<Vec<Fr>, ClientError> {
+        let instances = proved
+            .decode_instances()
+            .ok_or(ClientError::InstanceMismatch)?;
+        verify(&self.params, vk, &instances, &proved.proof)
+            .map_err(|_| ClientError::InvalidProof)?;
+        Ok(instances)
+    }
+}
+
+/// The feedback counts are at most the code length, so anything else is not a legitimate response.
+fn small_value(fe: &Fr) -> Result<u64, ClientError> {
+    (0..=4u64)
+        .find(|v| Fr::from(*v) == *fe)
+        .ok_or(ClientError::InstanceMismatch)
+}
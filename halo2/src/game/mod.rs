@@ -0,0 +1,159 @@
+//! A local two-party Mastermind game where the codemaker and the codebreaker run as separate processes.
+//!
+//! The codemaker runs a [`server::CodemakerServer`] which holds the secret code and answers every request with a
+//! proof generated from the `init` and `validate` circuits in [`crate::mastermind`]. The codebreaker uses a
+//! [`client::CodebreakerClient`], which checks each proof before trusting the response. The client derives the
+//! verifying keys on its own from the rules both players agreed on, as keys sent by the codemaker could be for any
+//! circuit. Messages are exchanged over TCP using the framing in [`protocol`].
+
+use std::path::{Path, PathBuf};
+
+use halo2_base::halo2_proofs::{
+    halo2curves::bn256::{Bn256, G1Affine},
+    plonk::VerifyingKey,
+    poly::kzg::commitment::ParamsKZG,
+};
+
+use crate::mastermind::{init, validate, variant::GameVariant};
+use crate::scaffold::keygen;
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+/// Name used for the key and pinning files of the code commitment (`init`) circuit.
+pub const INIT_NAME: &str = "init";
+/// Name used for the key and pinning files of the response (`validate`) circuit.
+pub const VALIDATE_NAME: &str = "validate";
+
+/// Path of the verifying key for the circuit `name` inside `key_dir`.
+pub fn vk_path(key_dir: &Path, name: &str) -> PathBuf {
+    key_dir.join(format!("{name}.vk"))
+}
+
+/// Path of the pinning (circuit parameters and break points) for the circuit `name` inside `key_dir`.
+pub fn pinning_path(key_dir: &Path, name: &str) -> PathBuf {
+    key_dir.join(format!("{name}.json"))
+}
+
+/// Keygen only depends on the shape of the circuit (which is fixed by the variant), so any well-formed input will do.
+fn dummy_init_input(variant: GameVariant) -> init::CircuitInput {
+    init::CircuitInput {
+        nonce: "0".to_string(),
+        pegs: ["0", "0", "0", "0"].map(String::from),
+        variant,
+    }
+}
+
+/// Same as [`dummy_init_input`], for the `validate` circuit.
+fn dummy_validate_input(variant: GameVariant) -> validate::CircuitInput {
+    validate::CircuitInput {
+        nonce: "0".to_string(),
+        pegs: ["0", "0", "0", "0"].map(String::from),
+        hash: "0".to_string(),
+        guess: ["0", "0", "0", "0"].map(String::from),
+        variant,
+        correct: None,
+        partial: None,
+    }
+}
+
+/// The verifying keys of the `init` and `validate` circuits of the variant, as generated by the codemaker's keygen
+/// with the same parameters and lookup bits.
+pub fn verifying_keys(
+    params: &ParamsKZG<Bn256>,
+    lookup_bits: usize,
+    variant: GameVariant,
+) -> (VerifyingKey<G1Affine>, VerifyingKey<G1Affine>) {
+    let (init_pk, _) = keygen(
+        init::codebreaker_init,
+        dummy_init_input(variant),
+        Some(lookup_bits),
+        params,
+    );
+    let (validate_pk, _) = keygen(
+        validate::codebreaker_validate,
+        dummy_validate_input(variant),
+        Some(lookup_bits),
+        params,
+    );
+    (init_pk.get_vk().clone(), validate_pk.get_vk().clone())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{net::TcpListener, thread};
+
+    use super::client::{ClientError, CodebreakerClient, Feedback};
+    use super::protocol::fe_to_string;
+    use super::server::{CodemakerServer, Secret};
+    use crate::mastermind::variant::GameVariant;
+
+    #[test]
+    fn test_game_on_loopback() {
+        let k = 7;
+        let key_dir =
+            std::env::temp_dir().join(format!("halo2-mastermind-game-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&key_dir);
+        let secret = Secret {
+            nonce: "8779517863189055527118789074678742345675695536270475907100309065095985163231"
+                .to_string(),
+            pegs: ["0", "3", "3", "5"].map(String::from),
+            variant: Default::default(),
+        };
+        let mut server = CodemakerServer::new(secret, k, 3, &key_dir).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().unwrap();
+                server.serve_connection(stream).unwrap();
+            }
+        });
+
+        let mut client = CodebreakerClient::connect(addr, k, 3, Default::default()).unwrap();
+        // same game as `data/init.in` and `data/validate.in`
+        let hash = client.commitment().unwrap();
+        assert_eq!(
+            fe_to_string(&hash),
+            "19133020706006282893309435396441264744901159657485679649169119012074738471483"
+        );
+        assert_eq!(
+            client.guess([1, 3, 0, 5]).unwrap(),
+            Feedback {
+                correct: 2,
+                partial: 1
+            }
+        );
+        assert_eq!(
+            client.guess([0, 3, 3, 5]).unwrap(),
+            Feedback {
+                correct: 4,
+                partial: 0
+            }
+        );
+
+        // A guess with a color out of range is rejected as soon as it is submitted.
+        assert!(matches!(
+            client.guess([6, 3, 3, 5]),
+            Err(ClientError::Server(_))
+        ));
+
+        drop(client);
+
+        // A codebreaker playing by other rules derives other keys, which the codemaker's proofs do not verify against.
+        let variant = GameVariant {
+            duplicates: false,
+            ..Default::default()
+        };
+        let mut client = CodebreakerClient::connect(addr, k, 3, variant).unwrap();
+        assert!(matches!(
+            client.commitment(),
+            Err(ClientError::InvalidProof)
+        ));
+        drop(client);
+        handle.join().unwrap();
+        std::fs::remove_dir_all(&key_dir).unwrap();
+    }
+}
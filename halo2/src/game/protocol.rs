@@ -0,0 +1,96 @@
+//! Wire format of the game. Every message is a JSON document prefixed by its length as a big-endian `u32`.
+
+use std::io::{self, ErrorKind, Read, Write};
+
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::utils::{fe_to_biguint, BigPrimeField};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Upper bound on the size of a single message, to avoid allocating arbitrary amounts of memory for a bad prefix.
+pub const MAX_MESSAGE_LEN: u32 = 1 << 24;
+
+/// Requests sent by the codebreaker to the codemaker.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Request {
+    /// Ask the codemaker to commit to their code. Answered with [`Response::Committed`].
+    Commit,
+    /// Submit a guess for the next round. Answered with [`Response::GuessAccepted`].
+    Guess { guess: [u64; 4] },
+    /// Fetch the proved feedback for a previously submitted guess. Answered with [`Response::Feedback`].
+    FetchResponse { round: usize },
+}
+
+/// Responses sent by the codemaker.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Response {
+    /// Proof of the `init` circuit. The only public instance is the hash of the code.
+    Committed(ProvedResponse),
+    /// The guess has been recorded for the given round.
+    GuessAccepted { round: usize },
+    /// Proof of the `validate` circuit for the requested round.
+    Feedback(ProvedResponse),
+    /// The request could not be served.
+    Error(String),
+}
+
+/// A proof together with the public instances it was generated for.
+///
+/// Field elements are encoded as decimal strings, matching the circuit input files in `data/`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProvedResponse {
+    pub instances: Vec<String>,
+    pub proof: Vec<u8>,
+}
+
+impl ProvedResponse {
+    pub fn new(instances: &[Fr], proof: Vec<u8>) -> Self {
+        Self {
+            instances: instances.iter().map(fe_to_string).collect(),
+            proof,
+        }
+    }
+
+    /// Decodes the public instances, returning `None` if any of them is not a valid field element.
+    pub fn decode_instances(&self) -> Option<Vec<Fr>> {
+        self.instances.iter().map(|s| fe_from_string(s)).collect()
+    }
+}
+
+/// Encodes a field element as a decimal string.
+pub fn fe_to_string<F: BigPrimeField>(fe: &F) -> String {
+    fe_to_biguint(fe).to_string()
+}
+
+/// Decodes a field element from a decimal string.
+pub fn fe_from_string<F: BigPrimeField>(s: &str) -> Option<F> {
+    F::from_str_vartime(s)
+}
+
+/// Writes a single length-prefixed message.
+pub fn write_message<T: Serialize>(writer: &mut impl Write, msg: &T) -> io::Result<()> {
+    let bytes = serde_json::to_vec(msg)?;
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_LEN)
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "message too large"))?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+/// Reads a single length-prefixed message. Returns `Ok(None)` if the peer closed the connection between messages.
+pub fn read_message<T: DeserializeOwned>(reader: &mut impl Read) -> io::Result<Option<T>> {
+    let mut len_bytes = [0u8; 4];
+    match reader.read_exact(&mut len_bytes) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(len_bytes);
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(ErrorKind::InvalidData, "message too large"));
+    }
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(Some(serde_json::from_slice(&bytes)?))
+}
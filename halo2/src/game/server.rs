@@ -0,0 +1,166 @@
+//! The codemaker side of the game.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    net::{TcpListener, TcpStream},
+    path::Path,
+};
+
+use halo2_base::halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::ProvingKey,
+    poly::kzg::commitment::ParamsKZG,
+    SerdeFormat,
+};
+use halo2_base::utils::fs::gen_srs;
+
+use super::protocol::{
+    fe_to_string, read_message, write_message, ProvedResponse, Request, Response,
+};
+use super::{
+    dummy_init_input, dummy_validate_input, pinning_path, vk_path, INIT_NAME, VALIDATE_NAME,
+};
+use crate::mastermind::{init, validate};
+use crate::scaffold::{keygen, prove, CircuitPinning};
+
+/// The secret held by the codemaker: the nonce and the code. This is the same data as `data/init.in`.
+pub type Secret = init::CircuitInput;
+
+/// Serves proved responses for a single secret code.
+pub struct CodemakerServer {
+    params: ParamsKZG<Bn256>,
+    init_pk: ProvingKey<G1Affine>,
+    init_pinning: CircuitPinning,
+    validate_pk: ProvingKey<G1Affine>,
+    validate_pinning: CircuitPinning,
+    secret: Secret,
+    hash: Option<Fr>,
+    guesses: Vec<[u64; 4]>,
+}
+
+impl CodemakerServer {
+    /// Runs keygen for the `init` and `validate` circuits and writes the verifying keys and pinnings to `key_dir`,
+    /// for anyone checking the proofs later. The codebreaker does not read them: it derives the same verifying keys
+    /// from the rules of the game, see [`super::verifying_keys`].
+    pub fn new(secret: Secret, k: u32, lookup_bits: usize, key_dir: &Path) -> io::Result<Self> {
+        let params = gen_srs(k);
+        fs::create_dir_all(key_dir)?;
+
+        let (init_pk, init_pinning) = keygen(
+            init::codebreaker_init,
+            dummy_init_input(secret.variant),
+            Some(lookup_bits),
+            &params,
+        );
+        let (validate_pk, validate_pinning) = keygen(
+            validate::codebreaker_validate,
+            dummy_validate_input(secret.variant),
+            Some(lookup_bits),
+            &params,
+        );
+        write_verifier_data(key_dir, INIT_NAME, &init_pk, &init_pinning)?;
+        write_verifier_data(key_dir, VALIDATE_NAME, &validate_pk, &validate_pinning)?;
+
+        Ok(Self {
+            params,
+            init_pk,
+            init_pinning,
+            validate_pk,
+            validate_pinning,
+            secret,
+            hash: None,
+            guesses: vec![],
+        })
+    }
+
+    /// Accepts connections one at a time and serves each of them until the peer disconnects. A connection which
+    /// fails is logged and dropped, and the server goes on with the next one.
+    pub fn serve(&mut self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            if let Err(e) = stream.and_then(|stream| self.serve_connection(stream)) {
+                log::warn!("Connection failed: {e}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Serves requests on a single connection until the peer disconnects.
+    pub fn serve_connection(&mut self, mut stream: TcpStream) -> io::Result<()> {
+        while let Some(request) = read_message::<Request>(&mut stream)? {
+            let response = self.handle(request);
+            write_message(&mut stream, &response)?;
+        }
+        Ok(())
+    }
+
+    /// Computes the response to a single request.
+    pub fn handle(&mut self, request: Request) -> Response {
+        match request {
+            Request::Commit => {
+                let snark = prove(
+                    init::codebreaker_init,
+                    self.secret.clone(),
+                    self.init_pinning.clone(),
+                    &self.params,
+                    &self.init_pk,
+                    None,
+                );
+                self.hash = Some(snark.instances[0][0]);
+                Response::Committed(ProvedResponse::new(&snark.instances[0], snark.proof))
+            }
+            Request::Guess { guess } => {
+                if self.hash.is_none() {
+                    return Response::Error("the code has not been committed yet".to_string());
+                }
+                // An illegal guess could never be proved by `codebreaker_validate`, so reject it now rather than
+                // when its response is fetched.
+                if !self.secret.variant.is_legal_guess(&guess) {
+                    return Response::Error(format!(
+                        "the guess {guess:?} is not legal in this game"
+                    ));
+                }
+                self.guesses.push(guess);
+                Response::GuessAccepted {
+                    round: self.guesses.len() - 1,
+                }
+            }
+            Request::FetchResponse { round } => {
+                let (Some(hash), Some(guess)) = (self.hash, self.guesses.get(round)) else {
+                    return Response::Error(format!("no guess was submitted for round {round}"));
+                };
+                let input = validate::CircuitInput {
+                    nonce: self.secret.nonce.clone(),
+                    pegs: self.secret.pegs.clone(),
+                    hash: fe_to_string(&hash),
+                    guess: guess.map(|g| g.to_string()),
+                    variant: self.secret.variant,
+                    correct: None,
+                    partial: None,
+                };
+                let snark = prove(
+                    validate::codebreaker_validate,
+                    input,
+                    self.validate_pinning.clone(),
+                    &self.params,
+                    &self.validate_pk,
+                    None,
+                );
+                Response::Feedback(ProvedResponse::new(&snark.instances[0], snark.proof))
+            }
+        }
+    }
+}
+
+fn write_verifier_data(
+    key_dir: &Path,
+    name: &str,
+    pk: &ProvingKey<G1Affine>,
+    pinning: &CircuitPinning,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(vk_path(key_dir, name))?);
+    pk.get_vk().write(&mut writer, SerdeFormat::RawBytes)?;
+    let pinning_file = File::create(pinning_path(key_dir, name))?;
+    serde_json::to_writer(pinning_file, pinning)?;
+    Ok(())
+}
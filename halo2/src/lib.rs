@@ -1,4 +1,7 @@
+#![feature(generic_const_exprs)]
 #![allow(incomplete_features)]
 
+pub mod game;
+pub mod mastermind;
 pub mod scaffold;
 pub mod vanilla_circuits;
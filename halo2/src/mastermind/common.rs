@@ -0,0 +1,87 @@
+use halo2_base::gates::circuit::builder::BaseCircuitBuilder;
+use halo2_base::gates::{GateChip, GateInstructions, RangeChip, RangeInstructions};
+use halo2_base::poseidon::hasher::PoseidonHasher;
+use halo2_base::utils::BigPrimeField;
+use halo2_base::{AssignedValue, Context};
+use snark_verifier_sdk::halo2::OptimizedPoseidonSpec;
+
+use super::poseidon::{circomlib_hash, circomlib_hash_gadget, HashScheme};
+
+const T: usize = 3;
+const RATE: usize = 2;
+const R_F: usize = 8;
+const R_P: usize = 57;
+
+/// Computes the commitment to the code: the hash of the nonce followed by the pegs, under the given scheme.
+pub fn hash_pegs<F: BigPrimeField>(
+    ctx: &mut Context<F>,
+    nonce: AssignedValue<F>,
+    pegs: [AssignedValue<F>; 4],
+    scheme: HashScheme,
+) -> AssignedValue<F> {
+    let poseidon_gate = GateChip::<F>::default();
+    let hash_inputs: [AssignedValue<F>; 5] = [nonce, pegs[0], pegs[1], pegs[2], pegs[3]];
+    match scheme {
+        HashScheme::Circomlib => circomlib_hash_gadget(ctx, &poseidon_gate, &hash_inputs),
+        HashScheme::Legacy => {
+            let mut poseidon =
+                PoseidonHasher::<F, T, RATE>::new(OptimizedPoseidonSpec::new::<R_F, R_P, 0>());
+            poseidon.initialize_consts(ctx, &poseidon_gate);
+            poseidon.hash_fix_len_array(ctx, &poseidon_gate, &hash_inputs)
+        }
+    }
+}
+
+/// Computes the commitment to the code natively, i.e. without proving anything. It is the value `hash_pegs`
+/// constrains the hash to be.
+pub fn commit_pegs<F: BigPrimeField>(nonce: F, pegs: [F; 4], scheme: HashScheme) -> F {
+    match scheme {
+        HashScheme::Circomlib => circomlib_hash(&[nonce, pegs[0], pegs[1], pegs[2], pegs[3]]),
+        HashScheme::Legacy => {
+            // halo2-base has no native version of its hasher, so run the gadget's witness generation on a
+            // throwaway builder.
+            let mut builder = BaseCircuitBuilder::<F>::new(false);
+            let ctx = builder.main(0);
+            let nonce = ctx.load_witness(nonce);
+            let pegs = pegs.map(|p| ctx.load_witness(p));
+            *hash_pegs(ctx, nonce, pegs, scheme).value()
+        }
+    }
+}
+
+/// Constrains every peg to be one of the `num_colors` legal values.
+pub fn assert_pegs_in_range<F: BigPrimeField>(
+    range_chip: &RangeChip<F>,
+    ctx: &mut Context<F>,
+    pegs: [AssignedValue<F>; 4],
+    num_colors: u64,
+) {
+    pegs.iter().for_each(|v| {
+        range_chip.check_less_than_safe(ctx, *v, num_colors);
+    });
+}
+
+/// Constrains the pegs to be pairwise distinct.
+pub fn assert_pegs_distinct<F: BigPrimeField>(ctx: &mut Context<F>, pegs: [AssignedValue<F>; 4]) {
+    let gate = GateChip::<F>::default();
+    for i in 0..pegs.len() {
+        for j in (i + 1)..pegs.len() {
+            let eq = gate.is_equal(ctx, pegs[i], pegs[j]);
+            gate.assert_is_const(ctx, &eq, &F::ZERO);
+        }
+    }
+}
+
+/// Computes the hash of the code by evaluating the hash gadget on a throwaway builder.
+#[cfg(test)]
+pub(crate) fn eval_hash_pegs<F: BigPrimeField>(
+    nonce: F,
+    pegs: [u64; 4],
+    scheme: HashScheme,
+) -> String {
+    let mut builder = BaseCircuitBuilder::<F>::new(false);
+    let ctx = builder.main(0);
+    let nonce = ctx.load_witness(nonce);
+    let pegs = pegs.map(|p| ctx.load_witness(F::from(p)));
+    crate::game::protocol::fe_to_string(hash_pegs(ctx, nonce, pegs, scheme).value())
+}
@@ -0,0 +1,54 @@
+use halo2_base::gates::circuit::builder::BaseCircuitBuilder;
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::utils::BigPrimeField;
+use halo2_base::AssignedValue;
+use serde::{Deserialize, Serialize};
+
+use super::common::{assert_pegs_distinct, assert_pegs_in_range, hash_pegs};
+use super::variant::GameVariant;
+use crate::scaffold;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitInput {
+    pub nonce: String, // field element, but easier to deserialize as a string
+    pub pegs: [String; 4],
+    #[serde(default)]
+    pub variant: GameVariant,
+}
+
+/// The public data of the init circuit. Its single public instance is the `hash`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicInputs {
+    pub hash: String,
+}
+
+impl scaffold::PublicInputs for PublicInputs {
+    fn instances(&self) -> Vec<Fr> {
+        vec![scaffold::parse_fe::<Fr>(&self.hash).expect("Error deserializing hash")]
+    }
+}
+
+pub fn codebreaker_init<F: BigPrimeField>(
+    builder: &mut BaseCircuitBuilder<F>,
+    input: CircuitInput,
+    make_public: &mut Vec<AssignedValue<F>>,
+) {
+    let range_chip = builder.range_chip();
+    let ctx = builder.main(0);
+    let nonce =
+        ctx.load_witness(F::from_str_vartime(&input.nonce).expect("Error deserializing nonce"));
+    let pegs: [AssignedValue<F>; 4] = input
+        .pegs
+        .map(|p| ctx.load_witness(F::from_str_vartime(&p).expect("Error deserializing peg")));
+
+    // Ensure pegs are in the range, and distinct if the variant requires it.
+    assert_pegs_in_range::<F>(&range_chip, ctx, pegs, input.variant.num_colors());
+    if input.variant.distinct_code() {
+        assert_pegs_distinct(ctx, pegs);
+    }
+
+    let hash = hash_pegs(ctx, nonce, pegs, input.variant.commitment);
+    make_public.push(hash);
+
+    log::info!("Hash output: {:?}", hash.value());
+}
@@ -0,0 +1,13 @@
+//! This module contains the Mastermind circuits built with `halo2-lib`.
+//!
+//! The circuit functions live in the library (rather than directly in the examples) so that they can be shared by
+//! the `init`/`validate` examples and by the two-party game server in [`crate::game`].
+
+pub mod common;
+pub mod init;
+pub mod poseidon;
+pub mod secret;
+pub mod smt;
+pub mod test_vectors;
+pub mod validate;
+pub mod variant;
@@ -0,0 +1,216 @@
+//! circomlib's Poseidon over BN254, so that the commitment to a code is the same in the circom, arkworks and halo2
+//! implementations. See `test-vectors/README.md` at the root of the repository for the parameters.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use halo2_base::gates::{GateChip, GateInstructions};
+use halo2_base::utils::BigPrimeField;
+use halo2_base::{AssignedValue, Context, QuantumCell::Constant};
+use serde::{Deserialize, Serialize};
+
+/// The parameters of circomlib's `Poseidon(5)`, shared with the arkworks crate.
+const CIRCOMLIB_PARAMS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../test-vectors/poseidon-circomlib-bn254.json"
+));
+
+/// The Poseidon parameters the commitment is computed with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashScheme {
+    /// circomlib's parameters, shared with the circom and arkworks crates.
+    #[default]
+    Circomlib,
+    /// The original parameters of this crate: `OptimizedPoseidonSpec` with T=3, R_F=8, R_P=57. No other
+    /// implementation computes this hash.
+    Legacy,
+}
+
+#[derive(Deserialize)]
+struct RawParams {
+    t: usize,
+    alpha: u64,
+    full_rounds: usize,
+    partial_rounds: usize,
+    round_constants: Vec<Vec<String>>,
+    mds: Vec<Vec<String>>,
+}
+
+/// circomlib's Poseidon parameters for the nonce and the 4 pegs (t = 6). They are BN254 constants: over any other
+/// field the hash is still a valid Poseidon instance, but not circomlib's.
+#[derive(Clone, Debug)]
+pub struct CircomlibParams<F> {
+    pub t: usize,
+    pub alpha: u64,
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    pub round_constants: Vec<Vec<F>>,
+    pub mds: Vec<Vec<F>>,
+}
+
+impl<F: BigPrimeField> CircomlibParams<F> {
+    /// The parameters over F, parsed on the first call for each field and cached for the life of the process.
+    pub fn load() -> &'static Self {
+        // A static cannot be generic, so the parameters of every field are kept in one map.
+        type Cache = Mutex<HashMap<TypeId, &'static (dyn Any + Send + Sync)>>;
+        static CACHE: OnceLock<Cache> = OnceLock::new();
+        let mut cache = CACHE.get_or_init(Default::default).lock().unwrap();
+        let params = *cache
+            .entry(TypeId::of::<F>())
+            .or_insert_with(|| Box::leak(Box::new(Self::parse())));
+        params
+            .downcast_ref()
+            .expect("Poseidon parameters cached under the wrong field")
+    }
+
+    fn parse() -> Self {
+        let raw: RawParams =
+            serde_json::from_str(CIRCOMLIB_PARAMS).expect("Error parsing Poseidon parameters");
+        let parse = |rows: Vec<Vec<String>>| -> Vec<Vec<F>> {
+            rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|x| {
+                            F::from_str_vartime(x).expect("Error deserializing Poseidon constant")
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        Self {
+            t: raw.t,
+            alpha: raw.alpha,
+            full_rounds: raw.full_rounds,
+            partial_rounds: raw.partial_rounds,
+            round_constants: parse(raw.round_constants),
+            mds: parse(raw.mds),
+        }
+    }
+
+    pub fn num_rounds(&self) -> usize {
+        self.full_rounds + self.partial_rounds
+    }
+
+    /// Whether round `r` applies the S-box to the whole state (the first and last `full_rounds / 2` rounds) or to
+    /// the first element only.
+    pub fn is_full_round(&self, r: usize) -> bool {
+        let half = self.full_rounds / 2;
+        r < half || r >= half + self.partial_rounds
+    }
+}
+
+/// Computes circomlib's Poseidon hash of the inputs natively, i.e. out of circuit.
+pub fn circomlib_hash<F: BigPrimeField>(inputs: &[F]) -> F {
+    let params = CircomlibParams::<F>::load();
+    assert_eq!(
+        inputs.len() + 1,
+        params.t,
+        "Poseidon parameters are for {} inputs",
+        params.t - 1
+    );
+
+    let mut state = vec![F::ZERO];
+    state.extend_from_slice(inputs);
+    for r in 0..params.num_rounds() {
+        for (s, c) in state.iter_mut().zip(&params.round_constants[r]) {
+            *s += c;
+        }
+        let sbox_width = if params.is_full_round(r) {
+            state.len()
+        } else {
+            1
+        };
+        for s in &mut state[..sbox_width] {
+            *s = s.pow_vartime([params.alpha]);
+        }
+        state = params
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&state)
+                    .fold(F::ZERO, |acc, (m, s)| acc + *m * s)
+            })
+            .collect();
+    }
+    state[0]
+}
+
+/// Constrains the output to be circomlib's Poseidon hash of the inputs.
+pub fn circomlib_hash_gadget<F: BigPrimeField>(
+    ctx: &mut Context<F>,
+    gate: &GateChip<F>,
+    inputs: &[AssignedValue<F>],
+) -> AssignedValue<F> {
+    let params = CircomlibParams::<F>::load();
+    assert_eq!(
+        inputs.len() + 1,
+        params.t,
+        "Poseidon parameters are for {} inputs",
+        params.t - 1
+    );
+    assert_eq!(params.alpha, 5, "the gadget only implements the x^5 S-box");
+
+    let mut state = vec![ctx.load_zero()];
+    state.extend_from_slice(inputs);
+    for r in 0..params.num_rounds() {
+        for (s, c) in state.iter_mut().zip(&params.round_constants[r]) {
+            *s = gate.add(ctx, *s, Constant(*c));
+        }
+        let sbox_width = if params.is_full_round(r) {
+            state.len()
+        } else {
+            1
+        };
+        for s in &mut state[..sbox_width] {
+            let x2 = gate.mul(ctx, *s, *s);
+            let x4 = gate.mul(ctx, x2, x2);
+            *s = gate.mul(ctx, x4, *s);
+        }
+        state = params
+            .mds
+            .iter()
+            .map(|row| gate.inner_product(ctx, state.clone(), row.iter().map(|m| Constant(*m))))
+            .collect();
+    }
+    state[0]
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::gates::circuit::builder::BaseCircuitBuilder;
+    use halo2_base::gates::GateChip;
+    use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::{circomlib_hash, circomlib_hash_gadget};
+    use crate::game::protocol::fe_to_string;
+
+    fn hash_inputs(inputs: [u64; 5]) -> [Fr; 5] {
+        inputs.map(Fr::from)
+    }
+
+    #[test]
+    fn test_known_answer() {
+        // From circom/README.md: the commitment of circom/examples/input1.json.
+        assert_eq!(
+            fe_to_string(&circomlib_hash(&hash_inputs([42, 1, 2, 3, 4]))),
+            "14145853328434050885524917836371294524222664259619069142924766963653570759368"
+        );
+    }
+
+    #[test]
+    fn test_gadget_matches_native() {
+        let inputs = hash_inputs([512, 0, 3, 3, 5]);
+        let mut builder = BaseCircuitBuilder::<Fr>::new(false);
+        let ctx = builder.main(0);
+        let assigned = ctx.assign_witnesses(inputs);
+        let hash = circomlib_hash_gadget(ctx, &GateChip::default(), &assigned);
+        assert_eq!(*hash.value(), circomlib_hash(&inputs));
+        assert_eq!(
+            fe_to_string(hash.value()),
+            "2267616588710594907316516533648313499968392157484174540006516884212624890658"
+        );
+    }
+}
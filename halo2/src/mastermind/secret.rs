@@ -0,0 +1,107 @@
+//! Generation of the codemaker's secret, i.e. the code and the nonce it is committed with (`data/init.in`).
+//!
+//! The nonce is the only thing that keeps the commitment hiding: a standard game has 6^4 = 1296 codes, so anyone can
+//! hash every code under a known or guessable nonce. It must be a uniformly random field element, drawn from a CSPRNG
+//! or derived from a seed phrase with [`derive_nonce`]. The arkworks crate derives the same nonces.
+
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::halo2_proofs::halo2curves::ff::{Field, FromUniformBytes};
+use pbkdf2::pbkdf2_hmac;
+use rand::{CryptoRng, Rng, RngCore};
+use sha2::Sha256;
+
+use super::init;
+use super::variant::GameVariant;
+use crate::game::protocol::fe_to_string;
+
+/// Number of PBKDF2 iterations when deriving a nonce from a seed phrase.
+pub const NONCE_KDF_ITERATIONS: u32 = 100_000;
+
+/// Prefix of the PBKDF2 salt, followed by the game number in decimal.
+pub const NONCE_KDF_SALT: &str = "mastermind/nonce/";
+
+/// Draws a nonce uniformly from the field.
+pub fn random_nonce<R: RngCore + CryptoRng>(rng: &mut R) -> Fr {
+    Fr::random(rng)
+}
+
+/// Derives the nonce of the given game from a seed phrase: 64 bytes of PBKDF2-HMAC-SHA256 of the phrase, with the
+/// salt `mastermind/nonce/<game>`, reduced modulo the field order. Every game must use its own number, as a nonce
+/// reused with the same code gives the same commitment.
+pub fn derive_nonce(phrase: &str, game: u64) -> Fr {
+    let salt = format!("{NONCE_KDF_SALT}{game}");
+    let mut bytes = [0u8; 64];
+    pbkdf2_hmac::<Sha256>(
+        phrase.as_bytes(),
+        salt.as_bytes(),
+        NONCE_KDF_ITERATIONS,
+        &mut bytes,
+    );
+    Fr::from_uniform_bytes(&bytes)
+}
+
+/// Draws a legal code for the variant.
+pub fn random_code<R: RngCore>(variant: &GameVariant, rng: &mut R) -> [u64; 4] {
+    loop {
+        let code = [(); 4].map(|_| rng.gen_range(0..variant.num_colors()));
+        if variant.is_legal_code(&code) {
+            return code;
+        }
+    }
+}
+
+/// The secret file of the codemaker for the code and the nonce.
+pub fn new_secret(code: [u64; 4], nonce: Fr, variant: GameVariant) -> init::CircuitInput {
+    init::CircuitInput {
+        nonce: fe_to_string(&nonce),
+        pegs: code.map(|p| p.to_string()),
+        variant,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde::Deserialize;
+
+    use super::{derive_nonce, random_code, random_nonce};
+    use crate::mastermind::variant::GameVariant;
+    use crate::scaffold::parse_fe;
+
+    #[derive(Deserialize)]
+    struct NonceVector {
+        phrase: String,
+        game: u64,
+        nonce: String,
+    }
+
+    #[derive(Deserialize)]
+    struct NonceVectorFile {
+        vectors: Vec<NonceVector>,
+    }
+
+    #[test]
+    fn test_derive_nonce() {
+        let vectors: NonceVectorFile = serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../test-vectors/secret.json"
+        )))
+        .unwrap();
+        for vector in vectors.vectors {
+            assert_eq!(
+                derive_nonce(&vector.phrase, vector.game),
+                parse_fe::<Fr>(&vector.nonce).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let variant = GameVariant::BULLS_AND_COWS;
+        assert!(variant.is_legal_code(&random_code(&variant, &mut rng)));
+        assert_ne!(random_nonce(&mut rng), random_nonce(&mut rng));
+    }
+}
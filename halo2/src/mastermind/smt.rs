@@ -0,0 +1,434 @@
+//! Export of the gates of the validate circuit as SMT-LIB, to ask a solver whether the response is unique.
+//!
+//! halo2-base lays every value out in the advice column of a context, and constrains them with three kinds of
+//! constraints: the basic gate `a + b * c = d` on four consecutive cells starting at each enabled selector, copy
+//! constraints between two cells or between a cell and a constant, and lookups of a cell into the range
+//! `[0, 2^lookup_bits)`. [`Gates`] reads them out of a [`BaseCircuitBuilder`] after the circuit function ran in mock
+//! mode, with one index per cell over all the contexts.
+//!
+//! The query is written in the finite field theory of cvc5 (`QF_FF`), like the one of the arkworks crate: two copies
+//! of the cells share the inputs of `codebreaker_validate` (nonce, pegs, hash and guess), satisfy all the constraints,
+//! and differ on the response. It is unsatisfiable exactly when the inputs determine the response.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, Write as _};
+use std::process::{Command, Stdio};
+
+use halo2_base::gates::circuit::builder::BaseCircuitBuilder;
+use halo2_base::gates::circuit::CircuitBuilderStage;
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::utils::{fe_to_biguint, modulus, BigPrimeField};
+use halo2_base::{AssignedValue, ContextCell};
+
+use super::validate::{validate_cells, CircuitInput};
+
+/// The solver [`solve`] runs, which must be on the PATH.
+pub const SOLVER: &str = "cvc5";
+
+/// The constraints of a circuit built with halo2-base, over the cells of all its contexts.
+#[derive(Clone, Debug)]
+pub struct Gates<F: BigPrimeField> {
+    /// The value of every cell, as assigned by the circuit function.
+    pub values: Vec<F>,
+    /// Basic gates `a + b * c = d`, as the cells `[a, b, c, d]`.
+    pub gates: Vec<[usize; 4]>,
+    /// Cells which must be equal.
+    pub copies: Vec<(usize, usize)>,
+    /// Cells which must be equal to a constant.
+    pub constants: Vec<(usize, F)>,
+    /// Cells which must be in `[0, 2^lookup_bits)`.
+    pub lookups: Vec<usize>,
+    pub lookup_bits: usize,
+    /// Index of the first cell of each context, by `(type_id, context_id)`.
+    offsets: HashMap<(&'static str, usize), usize>,
+}
+
+impl<F: BigPrimeField> Gates<F> {
+    /// Reads the constraints out of a builder the circuit function ran on. The builder must not be in a witness
+    /// generation only stage, which records no constraint.
+    pub fn from_builder(builder: &BaseCircuitBuilder<F>) -> Self {
+        let mut gates = Gates {
+            values: vec![],
+            gates: vec![],
+            copies: vec![],
+            constants: vec![],
+            lookups: vec![],
+            lookup_bits: builder.lookup_bits().unwrap_or(0),
+            offsets: HashMap::new(),
+        };
+        for ctx in builder
+            .core()
+            .phase_manager
+            .iter()
+            .flat_map(|phase| &phase.threads)
+        {
+            let first = gates.values.len();
+            gates.offsets.insert((ctx.type_id(), ctx.id()), first);
+            gates
+                .values
+                .extend(ctx.advice.iter().map(|value| value.evaluate()));
+            for (row, _) in ctx
+                .selector
+                .iter()
+                .enumerate()
+                .filter(|&(_, &enabled)| enabled)
+            {
+                let cell = first + row;
+                gates.gates.push([cell, cell + 1, cell + 2, cell + 3]);
+            }
+        }
+
+        let copy_manager = builder.core().copy_manager.lock().unwrap();
+        gates.copies = copy_manager
+            .advice_equalities
+            .iter()
+            .map(|(left, right)| (gates.cell(left), gates.cell(right)))
+            .collect();
+        gates.constants = copy_manager
+            .constant_equalities
+            .iter()
+            .map(|(constant, cell)| (gates.cell(cell), *constant))
+            .collect();
+        drop(copy_manager);
+
+        // The builder only gives its lookup managers out through a range chip sharing them, which needs lookup bits.
+        if builder.lookup_bits().is_some() {
+            for lookup_manager in builder.range_chip().lookup_manager() {
+                let cells_to_lookup = lookup_manager.cells_to_lookup.lock().unwrap();
+                for [value] in cells_to_lookup.values().flatten() {
+                    let cell = value.cell.expect("lookup of a cell outside of a context");
+                    gates.lookups.push(gates.cell(&cell));
+                }
+            }
+        }
+        gates
+    }
+
+    /// The index of a cell of one of the contexts.
+    pub fn cell(&self, cell: &ContextCell) -> usize {
+        self.offsets[&(cell.type_id, cell.context_id)] + cell.offset
+    }
+
+    /// Whether the values satisfy every constraint.
+    pub fn is_satisfied(&self, values: &[F]) -> bool {
+        let bound = F::from(1u64 << self.lookup_bits);
+        values.len() == self.values.len()
+            && self
+                .gates
+                .iter()
+                .all(|&[a, b, c, d]| values[a] + values[b] * values[c] == values[d])
+            && self
+                .copies
+                .iter()
+                .all(|&(left, right)| values[left] == values[right])
+            && self
+                .constants
+                .iter()
+                .all(|&(cell, constant)| values[cell] == constant)
+            && self
+                .lookups
+                .iter()
+                .all(|&cell| fe_to_biguint(&values[cell]) < fe_to_biguint(&bound))
+    }
+
+    /// The SMT-LIB query asking for two assignments of the cells which both satisfy the constraints, agree on the
+    /// `shared` cells, and differ on at least one of the `outputs` cells. The shared cells are named `w<cell>`, and
+    /// the others `a<cell>` in the first copy and `b<cell>` in the second. With `fix_inputs`, the shared cells are
+    /// also fixed to their values. The query is unsatisfiable if and only if the outputs are unique.
+    pub fn uniqueness_query(
+        &self,
+        shared: &[usize],
+        outputs: &[usize],
+        fix_inputs: bool,
+    ) -> String {
+        let mut is_shared = vec![false; self.values.len()];
+        for &cell in shared {
+            is_shared[cell] = true;
+        }
+        let name = |copy: char, cell: usize| {
+            if is_shared[cell] {
+                format!("w{cell}")
+            } else {
+                format!("{copy}{cell}")
+            }
+        };
+        let literal = |value: &F| format!("(as ff{} F)", fe_to_biguint(value));
+
+        let mut query = String::new();
+        writeln!(
+            query,
+            "; {} cells, {} gates, {} copies, {} constants, {} lookups of {} bits, {} shared cells, {} outputs",
+            self.values.len(),
+            self.gates.len(),
+            self.copies.len(),
+            self.constants.len(),
+            self.lookups.len(),
+            self.lookup_bits,
+            shared.len(),
+            outputs.len()
+        )
+        .unwrap();
+        writeln!(query, "(set-logic QF_FF)").unwrap();
+        writeln!(
+            query,
+            "(define-sort F () (_ FiniteField {}))",
+            modulus::<F>()
+        )
+        .unwrap();
+        for cell in shared {
+            writeln!(query, "(declare-const w{cell} F)").unwrap();
+        }
+        for copy in ['a', 'b'] {
+            for cell in (0..self.values.len()).filter(|&cell| !is_shared[cell]) {
+                writeln!(query, "(declare-const {copy}{cell} F)").unwrap();
+            }
+        }
+
+        let range: Vec<String> = (0..1u64 << self.lookup_bits)
+            .map(|i| literal(&F::from(i)))
+            .collect();
+        for copy in ['a', 'b'] {
+            let name = |cell| name(copy, cell);
+            writeln!(query, "; constraints of the copy {copy}").unwrap();
+            for &[a, b, c, d] in &self.gates {
+                writeln!(
+                    query,
+                    "(assert (= (ff.add {} (ff.mul {} {})) {}))",
+                    name(a),
+                    name(b),
+                    name(c),
+                    name(d)
+                )
+                .unwrap();
+            }
+            for &(left, right) in &self.copies {
+                writeln!(query, "(assert (= {} {}))", name(left), name(right)).unwrap();
+            }
+            for (cell, constant) in &self.constants {
+                writeln!(query, "(assert (= {} {}))", name(*cell), literal(constant)).unwrap();
+            }
+            for &cell in &self.lookups {
+                let name = name(cell);
+                let members: Vec<String> = range
+                    .iter()
+                    .map(|value| format!("(= {name} {value})"))
+                    .collect();
+                writeln!(query, "(assert (or {}))", members.join(" ")).unwrap();
+            }
+        }
+
+        if fix_inputs {
+            writeln!(query, "; fixed inputs").unwrap();
+            for &cell in shared {
+                writeln!(
+                    query,
+                    "(assert (= w{cell} {}))",
+                    literal(&self.values[cell])
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(query, "; some output differs").unwrap();
+        let differences: Vec<String> = outputs
+            .iter()
+            .map(|&cell| format!("(not (= {} {}))", name('a', cell), name('b', cell)))
+            .collect();
+        let differs = match differences.len() {
+            0 => "false".to_string(),
+            1 => differences[0].clone(),
+            _ => format!("(or {})", differences.join(" ")),
+        };
+        writeln!(query, "(assert {differs})").unwrap();
+        writeln!(query, "(check-sat)").unwrap();
+        query
+    }
+}
+
+/// Runs `codebreaker_validate` on the input in mock mode, and returns its constraints with the cells of its inputs
+/// (nonce, pegs, hash and guess) and of its outputs (correct and partial).
+pub fn validate_gates(
+    input: CircuitInput,
+    k: u32,
+    lookup_bits: usize,
+) -> (Gates<Fr>, Vec<usize>, Vec<usize>) {
+    let mut builder = BaseCircuitBuilder::<Fr>::from_stage(CircuitBuilderStage::Mock);
+    builder.set_k(k as usize);
+    builder.set_lookup_bits(lookup_bits);
+    builder.set_instance_columns(1);
+    let cells = validate_cells(&mut builder, input, &mut vec![]);
+
+    let gates = Gates::from_builder(&builder);
+    let cell =
+        |value: AssignedValue<Fr>| gates.cell(&value.cell.expect("value outside of a context"));
+    let inputs = [cells.nonce]
+        .into_iter()
+        .chain(cells.pegs)
+        .chain([cells.hash])
+        .chain(cells.guess)
+        .map(cell)
+        .collect();
+    let outputs = vec![cell(cells.correct), cell(cells.partial)];
+    (gates, inputs, outputs)
+}
+
+/// The uniqueness query of the response of `codebreaker_validate` for the input. With `fix_inputs`, the query is
+/// for the inputs of this game only; otherwise it is over all inputs, and the values of the input do not matter.
+pub fn validate_query(input: CircuitInput, k: u32, lookup_bits: usize, fix_inputs: bool) -> String {
+    let (gates, inputs, outputs) = validate_gates(input, k, lookup_bits);
+    gates.uniqueness_query(&inputs, &outputs, fix_inputs)
+}
+
+/// What the solver answered to a uniqueness query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// unsat: the inputs determine the outputs.
+    Unique,
+    /// sat: two assignments share the inputs but not the outputs.
+    NotUnique,
+    /// Anything else, with the output of the solver.
+    Unknown(String),
+}
+
+/// Runs the query with the solver on the PATH, or returns `None` if there is none.
+pub fn solve(query: &str) -> io::Result<Option<Verdict>> {
+    let mut child = match Command::new(SOLVER)
+        .arg("--lang=smt2")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        child => child?,
+    };
+    // The answer is a single line, so the solver does not block on its output while the query is written.
+    child.stdin.take().unwrap().write_all(query.as_bytes())?;
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(Some(match stdout.trim() {
+        "unsat" => Verdict::Unique,
+        "sat" => Verdict::NotUnique,
+        _ => Verdict::Unknown(format!(
+            "{}{}",
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        )),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::{solve, validate_gates, validate_query, Verdict};
+    use crate::mastermind::common::eval_hash_pegs;
+    use crate::mastermind::validate::CircuitInput;
+    use crate::mastermind::variant::GameVariant;
+    use crate::scaffold;
+
+    const K: u32 = 8;
+    const LOOKUP_BITS: usize = 4;
+
+    /// A full-size nonce, as generated by `mastermind::secret`.
+    const NONCE: &str =
+        "8779517863189055527118789074678742345675695536270475907100309065095985163231";
+
+    /// A tiny game of 2 colors; the validate circuit always has 4 pegs.
+    const TINY: GameVariant = GameVariant {
+        colors: 2,
+        ..GameVariant::CLASSIC
+    };
+
+    fn input(code: [u64; 4], guess: [u64; 4]) -> CircuitInput {
+        CircuitInput {
+            nonce: NONCE.to_string(),
+            pegs: code.map(|p| p.to_string()),
+            hash: eval_hash_pegs(
+                scaffold::parse_fe::<Fr>(NONCE).unwrap(),
+                code,
+                TINY.commitment,
+            ),
+            guess: guess.map(|g| g.to_string()),
+            variant: TINY,
+            correct: None,
+            partial: None,
+        }
+    }
+
+    #[test]
+    fn test_gates() {
+        let (gates, inputs, outputs) =
+            validate_gates(input([0, 1, 1, 0], [1, 1, 0, 0]), K, LOOKUP_BITS);
+        assert_eq!(inputs.len(), 10);
+        assert_eq!(gates.values[inputs[1]], Fr::from(0));
+        assert_eq!(gates.values[inputs[2]], Fr::from(1));
+        // 2 correct, 2 partial.
+        assert_eq!(
+            outputs
+                .iter()
+                .map(|&cell| gates.values[cell])
+                .collect::<Vec<_>>(),
+            [Fr::from(2), Fr::from(2)]
+        );
+
+        // The constraints read out of the builder hold for the values of the game, and catch another response.
+        assert!(!gates.gates.is_empty() && !gates.copies.is_empty() && !gates.lookups.is_empty());
+        assert!(gates.is_satisfied(&gates.values));
+        let mut values = gates.values.clone();
+        values[outputs[0]] = Fr::from(3);
+        assert!(!gates.is_satisfied(&values));
+    }
+
+    #[test]
+    fn test_validate_query() {
+        let (gates, inputs, _) = validate_gates(input([0, 1, 1, 0], [1, 1, 0, 0]), K, LOOKUP_BITS);
+        for fix_inputs in [false, true] {
+            let query = validate_query(
+                input([0, 1, 1, 0], [1, 1, 0, 0]),
+                K,
+                LOOKUP_BITS,
+                fix_inputs,
+            );
+
+            // Balanced, every constant declared once, and one assertion per constraint of each copy, per fixed
+            // input, and for the difference of the outputs.
+            assert_eq!(query.matches('(').count(), query.matches(')').count());
+            let declared: Vec<&str> = query
+                .lines()
+                .filter_map(|line| line.strip_prefix("(declare-const "))
+                .collect();
+            assert_eq!(
+                declared.iter().collect::<HashSet<_>>().len(),
+                declared.len()
+            );
+            assert_eq!(declared.len(), 2 * gates.values.len() - inputs.len());
+            let constraints = gates.gates.len()
+                + gates.copies.len()
+                + gates.constants.len()
+                + gates.lookups.len();
+            let fixed = if fix_inputs { inputs.len() } else { 0 };
+            assert_eq!(
+                query.matches("(assert ").count(),
+                2 * constraints + fixed + 1
+            );
+            assert!(query.trim_end().ends_with("(check-sat)"));
+        }
+
+        // Over all inputs, the query only depends on the shape of the circuit.
+        assert_eq!(
+            validate_query(input([0, 1, 1, 0], [1, 1, 0, 0]), K, LOOKUP_BITS, false),
+            validate_query(input([1, 1, 1, 1], [0, 0, 0, 0]), K, LOOKUP_BITS, false)
+        );
+    }
+
+    #[test]
+    #[ignore = "needs cvc5 on the PATH"]
+    fn test_solve() {
+        let query = validate_query(input([0, 1, 1, 0], [1, 1, 0, 0]), K, LOOKUP_BITS, true);
+        assert_eq!(solve(&query).unwrap(), Some(Verdict::Unique));
+    }
+}
@@ -0,0 +1,169 @@
+//! Loader for the test vectors shared by the Rust implementations, see `test-vectors/README.md` at the root of the
+//! repository for the format.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+};
+
+use serde::Deserialize;
+
+use super::{init, poseidon::HashScheme, validate, variant::GameVariant};
+
+/// The test vectors checked into the repository.
+pub const DEFAULT_TEST_VECTORS: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../test-vectors/mastermind.json"
+);
+
+/// Key of the commitment under the given scheme in `expected_hash`. The circomlib commitment is shared by all the
+/// implementations.
+pub fn hash_key(scheme: HashScheme) -> &'static str {
+    match scheme {
+        HashScheme::Circomlib => "circomlib",
+        HashScheme::Legacy => "halo2",
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TestVectorFile {
+    pub vectors: Vec<TestVector>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct Params {
+    /// Number of peg colors.
+    pub colors: u64,
+    /// Number of pegs in the code.
+    pub pegs: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    pub params: Params,
+    /// Field element, as a decimal string.
+    pub nonce: String,
+    pub code: Vec<u64>,
+    pub guess: Vec<u64>,
+    /// Commitment to (nonce, code), keyed by hash scheme.
+    #[serde(default)]
+    pub expected_hash: BTreeMap<String, String>,
+    /// Claimed response.
+    pub expected_black: u64,
+    pub expected_white: u64,
+    /// Whether the circuit should accept the claimed response.
+    pub expected_valid: bool,
+}
+
+impl TestVector {
+    /// The commitment under the given scheme, if the vector has one.
+    pub fn hash(&self, scheme: HashScheme) -> Option<&String> {
+        self.expected_hash.get(hash_key(scheme))
+    }
+
+    pub fn variant(&self) -> GameVariant {
+        GameVariant {
+            colors: self.params.colors,
+            ..GameVariant::CLASSIC
+        }
+    }
+
+    /// Input of the `init` circuit. Returns `None` if the code does not have 4 pegs.
+    pub fn to_init_input(&self) -> Option<init::CircuitInput> {
+        Some(init::CircuitInput {
+            nonce: self.nonce.clone(),
+            pegs: to_strings(&self.code)?,
+            variant: self.variant(),
+        })
+    }
+
+    /// Input of the `validate` circuit for the given commitment. Returns `None` if the code or the guess does not
+    /// have 4 pegs.
+    pub fn to_validate_input(&self, hash: String) -> Option<validate::CircuitInput> {
+        Some(validate::CircuitInput {
+            nonce: self.nonce.clone(),
+            pegs: to_strings(&self.code)?,
+            hash,
+            guess: to_strings(&self.guess)?,
+            variant: self.variant(),
+            // The test checks the claimed response against the public outputs instead.
+            correct: None,
+            partial: None,
+        })
+    }
+}
+
+pub fn load_test_vectors(path: impl AsRef<Path>) -> io::Result<Vec<TestVector>> {
+    let file = File::open(path)?;
+    let parsed: TestVectorFile = serde_json::from_reader(BufReader::new(file))?;
+    Ok(parsed.vectors)
+}
+
+fn to_strings(pegs: &[u64]) -> Option<[String; 4]> {
+    pegs.iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .try_into()
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::{load_test_vectors, DEFAULT_TEST_VECTORS};
+    use crate::game::protocol::fe_to_string;
+    use crate::mastermind::common::eval_hash_pegs;
+    use crate::mastermind::poseidon::{circomlib_hash, HashScheme};
+    use crate::mastermind::validate::codebreaker_validate;
+    use crate::scaffold::{mock, parse_fe};
+
+    #[test]
+    fn test_vectors() {
+        let vectors = load_test_vectors(DEFAULT_TEST_VECTORS).unwrap();
+        assert!(!vectors.is_empty());
+        for vector in vectors {
+            let code: [u64; 4] = vector.code.clone().try_into().unwrap();
+            let nonce = parse_fe::<Fr>(&vector.nonce).unwrap();
+            for scheme in [HashScheme::Circomlib, HashScheme::Legacy] {
+                if let Some(expected) = vector.hash(scheme) {
+                    let computed = eval_hash_pegs(nonce, code, scheme);
+                    assert_eq!(
+                        computed, *expected,
+                        "{}: {:?} hash mismatch",
+                        vector.name, scheme
+                    );
+                }
+            }
+
+            // The native hash agrees with the circuit.
+            let computed_hash = eval_hash_pegs(nonce, code, vector.variant().commitment);
+            let inputs = [
+                nonce,
+                Fr::from(code[0]),
+                Fr::from(code[1]),
+                Fr::from(code[2]),
+                Fr::from(code[3]),
+            ];
+            assert_eq!(
+                fe_to_string(&circomlib_hash(&inputs)),
+                computed_hash,
+                "{}",
+                vector.name
+            );
+
+            let input = vector.to_validate_input(computed_hash).unwrap();
+            // The circuit computes the response, so a wrong claimed response shows up as different public outputs.
+            let accepted = mock(codebreaker_validate, input, 8, Some(4))
+                .map(|instances| {
+                    instances[0] == Fr::from(vector.expected_black)
+                        && instances[1] == Fr::from(vector.expected_white)
+                })
+                .unwrap_or(false);
+            assert_eq!(accepted, vector.expected_valid, "{}", vector.name);
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use halo2_base::gates::circuit::builder::BaseCircuitBuilder;
+use halo2_base::gates::{GateChip, GateInstructions, RangeInstructions};
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::utils::{fe_to_biguint, BigPrimeField};
+use halo2_base::AssignedValue;
+#[allow(unused_imports)]
+use halo2_base::{
+    Context,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use serde::{Deserialize, Serialize};
+
+use super::common::{assert_pegs_distinct, assert_pegs_in_range, commit_pegs, hash_pegs};
+use super::init;
+use super::variant::{GameVariant, Scoring};
+use crate::scaffold;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitInput {
+    pub nonce: String, // field element, but easier to deserialize as a string
+    pub pegs: [String; 4],
+    pub hash: String,
+    pub guess: [String; 4],
+    #[serde(default)]
+    pub variant: GameVariant,
+    /// The response the codemaker expects the circuit to output. The counts are computed by the circuit, so these
+    /// are only checked natively by [`CircuitInput::check_response`], to catch an inconsistent input file early.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correct: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partial: Option<u64>,
+}
+
+/// The public data of the validate circuit: the game (commitment and guess) and the response. The public instances
+/// are, in order, `[correct, partial, hash, guess[0], guess[1], guess[2], guess[3]]`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicInputs {
+    pub hash: String,
+    pub guess: [u64; 4],
+    pub correct: u64,
+    pub partial: u64,
+}
+
+impl scaffold::PublicInputs for PublicInputs {
+    fn instances(&self) -> Vec<Fr> {
+        let hash = scaffold::parse_fe::<Fr>(&self.hash).expect("Error deserializing hash");
+        [Fr::from(self.correct), Fr::from(self.partial), hash]
+            .into_iter()
+            .chain(self.guess.map(Fr::from))
+            .collect()
+    }
+}
+
+impl CircuitInput {
+    /// Builds a self-consistent input for the codemaker's secret and a guess: the hash is the commitment to the code
+    /// and the expected counts are the response to the guess. Panics if the nonce or a peg is malformed.
+    pub fn for_game<F: BigPrimeField>(secret: &init::CircuitInput, guess: [u64; 4]) -> Self {
+        let nonce = F::from_str_vartime(&secret.nonce).expect("Error deserializing nonce");
+        let code = secret
+            .pegs
+            .clone()
+            .map(|p| p.parse::<u64>().expect("Error deserializing peg"));
+        let hash = commit_pegs(nonce, code.map(F::from), secret.variant.commitment);
+        let (correct, partial) = secret.variant.score(&code, &guess);
+        Self {
+            nonce: secret.nonce.clone(),
+            pegs: secret.pegs.clone(),
+            hash: fe_to_biguint(&hash).to_string(),
+            guess: guess.map(|g| g.to_string()),
+            variant: secret.variant,
+            correct: Some(correct),
+            partial: Some(partial),
+        }
+    }
+
+    /// Checks natively that the expected response, if the input states one, is the response of the code to the
+    /// guess, which the circuit outputs whatever the input expects.
+    pub fn check_response(&self) -> Result<(), String> {
+        let parse = |pegs: &[String; 4]| {
+            pegs.clone()
+                .map(|p| p.parse::<u64>().map_err(|e| format!("{p:?}: {e}")))
+        };
+        let [a, b, c, d] = parse(&self.pegs);
+        let code = [a?, b?, c?, d?];
+        let [a, b, c, d] = parse(&self.guess);
+        let guess = [a?, b?, c?, d?];
+        let (correct, partial) = self.variant.score(&code, &guess);
+        for (name, expected, computed) in [
+            ("correct", self.correct, correct),
+            ("partial", self.partial, partial),
+        ] {
+            if let Some(expected) = expected.filter(|&expected| expected != computed) {
+                return Err(format!(
+                    "the input expects {expected} {name} guesses, but the response has {computed}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The public data a verifier expects for this input, if it states the expected response.
+    pub fn public_inputs(&self) -> Option<PublicInputs> {
+        Some(PublicInputs {
+            hash: self.hash.clone(),
+            guess: self
+                .guess
+                .clone()
+                .map(|g| g.parse().expect("Error deserializing peg")),
+            correct: self.correct?,
+            partial: self.partial?,
+        })
+    }
+}
+
+/// The cells of the inputs and of the response of the validate circuit.
+#[derive(Clone, Copy, Debug)]
+pub struct ValidateCells<F: BigPrimeField> {
+    pub nonce: AssignedValue<F>,
+    pub pegs: [AssignedValue<F>; 4],
+    pub hash: AssignedValue<F>,
+    pub guess: [AssignedValue<F>; 4],
+    pub correct: AssignedValue<F>,
+    pub partial: AssignedValue<F>,
+}
+
+pub fn codebreaker_validate<F: BigPrimeField>(
+    builder: &mut BaseCircuitBuilder<F>,
+    input: CircuitInput,
+    make_public: &mut Vec<AssignedValue<F>>,
+) {
+    validate_cells(builder, input, make_public);
+}
+
+/// Same as [`codebreaker_validate`], returning the cells the inputs were loaded into and the response.
+pub fn validate_cells<F: BigPrimeField>(
+    builder: &mut BaseCircuitBuilder<F>,
+    input: CircuitInput,
+    make_public: &mut Vec<AssignedValue<F>>,
+) -> ValidateCells<F> {
+    let range_chip = builder.range_chip();
+    let ctx = builder.main(0);
+
+    let nonce =
+        ctx.load_witness(F::from_str_vartime(&input.nonce).expect("Error deserializing nonce"));
+    let pegs: [AssignedValue<F>; 4] = input
+        .pegs
+        .map(|p| ctx.load_witness(F::from_str_vartime(&p).expect("Error deserializing peg")));
+    let hash =
+        ctx.load_witness(F::from_str_vartime(&input.hash).expect("Error deserializing hash"));
+    let guesses: [AssignedValue<F>; 4] = input
+        .guess
+        .map(|g| ctx.load_witness(F::from_str_vartime(&g).expect("Error deserializing peg")));
+
+    // Constrain that the pegs and guesses are within range (and distinct if the variant requires it)
+    let variant = input.variant;
+    assert_pegs_in_range::<F>(&range_chip, ctx, pegs, variant.num_colors());
+    assert_pegs_in_range::<F>(&range_chip, ctx, guesses, variant.num_colors());
+    if variant.distinct_code() {
+        assert_pegs_distinct(ctx, pegs);
+    }
+    if variant.distinct_guess() {
+        assert_pegs_distinct(ctx, guesses);
+    }
+
+    // Constrain that the correct game information has been loaded
+    let hash_calc = hash_pegs(ctx, nonce, pegs, variant.commitment);
+    ctx.constrain_equal(&hash_calc, &hash);
+
+    // Tally the correct guesses
+    let equalities: Vec<AssignedValue<F>> = pegs
+        .iter()
+        .zip(guesses)
+        .map(|(peg, guess)| -> AssignedValue<F> {
+            let guess_chip = GateChip::<F>::default();
+            guess_chip.is_equal(ctx, *peg, guess)
+        })
+        .collect();
+    let correct_guesses = GateChip::<F>::default().sum(ctx, equalities);
+
+    // Tally the number of partial guesses
+    let partial_guesses = match variant.scoring {
+        Scoring::Classic => {
+            let min_val = |ctx: &mut Context<F>,
+                           a: AssignedValue<F>,
+                           b: AssignedValue<F>|
+             -> AssignedValue<F> {
+                let a_less_than_b = range_chip.is_less_than(ctx, a, b, 4);
+                GateChip::<F>::default().select(ctx, a, b, a_less_than_b)
+            };
+
+            let count_color = |ctx: &mut Context<F>,
+                               pegs: [AssignedValue<F>; 4],
+                               color: AssignedValue<F>|
+             -> AssignedValue<F> {
+                let eq_vec: Vec<AssignedValue<F>> = pegs
+                    .iter()
+                    .map(|v| GateChip::<F>::default().is_equal(ctx, *v, color))
+                    .collect();
+                GateChip::<F>::default().sum(ctx, eq_vec)
+            };
+
+            let min_vals: Vec<AssignedValue<F>> = (0u64..variant.num_colors())
+                .map(|c| {
+                    let color = ctx.load_constant(c.into());
+                    let guess_color = count_color(ctx, guesses, color);
+                    let code_color = count_color(ctx, pegs, color);
+                    min_val(ctx, guess_color, code_color)
+                })
+                .collect();
+
+            let min_sum = GateChip::<F>::default().sum(ctx, min_vals);
+            GateInstructions::sub(&GateChip::<F>::default(), ctx, min_sum, correct_guesses)
+        }
+        Scoring::BullsAndCows => {
+            // With distinct digits on both sides, every match at different positions is a cow.
+            let mut cows = vec![];
+            for (i, peg) in pegs.iter().enumerate() {
+                for (j, guess) in guesses.iter().enumerate() {
+                    if i != j {
+                        cows.push(GateChip::<F>::default().is_equal(ctx, *peg, *guess));
+                    }
+                }
+            }
+            GateChip::<F>::default().sum(ctx, cows)
+        }
+    };
+
+    // Make the values public. The hash and the guess are exposed as well so that a verifier can check that the
+    // response belongs to the committed code and to the guess they submitted.
+    make_public.push(correct_guesses);
+    make_public.push(partial_guesses);
+    make_public.push(hash);
+    make_public.extend(guesses);
+
+    log::info!("Correct guesses: {:?}", correct_guesses.value());
+    log::info!("Partial guesses: {:?}", partial_guesses.value());
+    ValidateCells {
+        nonce,
+        pegs,
+        hash,
+        guess: guesses,
+        correct: correct_guesses,
+        partial: partial_guesses,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::{codebreaker_validate, CircuitInput, PublicInputs};
+    use crate::mastermind::common::eval_hash_pegs;
+    use crate::mastermind::init;
+    use crate::mastermind::poseidon::HashScheme;
+    use crate::mastermind::variant::GameVariant;
+    use crate::scaffold::mock;
+    use crate::scaffold::{self, PublicInputs as _};
+
+    const K: u32 = 8;
+    const LOOKUP_BITS: usize = 4;
+
+    /// A full-size nonce, as generated by `mastermind::secret`.
+    const NONCE: &str =
+        "8779517863189055527118789074678742345675695536270475907100309065095985163231";
+
+    fn input(code: [u64; 4], guess: [u64; 4], variant: GameVariant) -> CircuitInput {
+        CircuitInput {
+            nonce: NONCE.to_string(),
+            pegs: code.map(|p| p.to_string()),
+            hash: eval_hash_pegs(
+                scaffold::parse_fe::<Fr>(NONCE).unwrap(),
+                code,
+                variant.commitment,
+            ),
+            guess: guess.map(|g| g.to_string()),
+            variant,
+            correct: None,
+            partial: None,
+        }
+    }
+
+    /// Runs the mock prover on the game, returning the (correct, partial) public outputs if it is satisfied.
+    fn run(code: [u64; 4], guess: [u64; 4], variant: GameVariant) -> Option<(Fr, Fr)> {
+        let input = input(code, guess, variant);
+        let instances = mock(codebreaker_validate, input, K, Some(LOOKUP_BITS)).ok()?;
+        Some((instances[0], instances[1]))
+    }
+
+    /// Checks that the circuit accepts the game and agrees with the reference scorer.
+    fn assert_scores(code: [u64; 4], guess: [u64; 4], variant: GameVariant) {
+        let (correct, partial) = variant.score(&code, &guess);
+        assert_eq!(
+            run(code, guess, variant),
+            Some((Fr::from(correct), Fr::from(partial)))
+        );
+    }
+
+    #[test]
+    fn test_classic() {
+        let v = GameVariant::CLASSIC;
+        assert_scores([0, 3, 3, 5], [1, 3, 0, 5], v);
+        assert_scores([1, 1, 2, 2], [2, 2, 1, 1], v);
+        assert_eq!(run([6, 3, 3, 5], [1, 3, 0, 5], v), None);
+    }
+
+    #[test]
+    fn test_no_duplicates() {
+        let v = GameVariant {
+            duplicates: false,
+            ..GameVariant::CLASSIC
+        };
+        assert_scores([0, 1, 2, 3], [3, 3, 3, 3], v);
+        assert_eq!(run([0, 3, 3, 5], [1, 3, 0, 5], v), None);
+    }
+
+    #[test]
+    fn test_blanks() {
+        let v = GameVariant {
+            blanks: true,
+            ..GameVariant::CLASSIC
+        };
+        assert_scores([6, 1, 6, 3], [6, 6, 1, 0], v);
+        assert_eq!(run([7, 1, 6, 3], [6, 6, 1, 0], v), None);
+        assert_eq!(run([6, 1, 6, 3], [6, 6, 1, 0], GameVariant::CLASSIC), None);
+    }
+
+    #[test]
+    fn test_bulls_and_cows() {
+        let v = GameVariant::BULLS_AND_COWS;
+        assert_scores([9, 8, 7, 6], [6, 1, 8, 0], v);
+        assert_scores([1, 2, 3, 4], [1, 2, 4, 3], v);
+        assert_eq!(run([9, 9, 7, 6], [6, 1, 8, 0], v), None);
+        assert_eq!(run([9, 8, 7, 6], [6, 6, 8, 0], v), None);
+    }
+
+    #[test]
+    fn test_legacy_commitment() {
+        let v = GameVariant {
+            commitment: HashScheme::Legacy,
+            ..GameVariant::CLASSIC
+        };
+        assert_scores([0, 3, 3, 5], [1, 3, 0, 5], v);
+
+        // The circuit checks the hash under the scheme of the variant.
+        let mut input = input([0, 3, 3, 5], [1, 3, 0, 5], v);
+        input.variant = GameVariant::CLASSIC;
+        assert!(mock(codebreaker_validate, input, K, Some(LOOKUP_BITS)).is_err());
+    }
+
+    #[test]
+    fn test_for_game() {
+        let data = |name: &str| -> String {
+            std::fs::read_to_string(format!("{}/data/{name}", env!("CARGO_MANIFEST_DIR"))).unwrap()
+        };
+        let secret: init::CircuitInput = serde_json::from_str(&data("init.in")).unwrap();
+        let expected: CircuitInput = serde_json::from_str(&data("validate.in")).unwrap();
+
+        // `data/validate.in` is the input generated for the guess [1, 3, 0, 5].
+        let input = CircuitInput::for_game::<Fr>(&secret, [1, 3, 0, 5]);
+        assert_eq!(
+            serde_json::to_value(&input).unwrap(),
+            serde_json::to_value(&expected).unwrap()
+        );
+
+        let public = input.public_inputs().unwrap();
+        let instances = mock(codebreaker_validate, input, K, Some(LOOKUP_BITS)).unwrap();
+        assert_eq!(instances[..2], [Fr::from(2), Fr::from(1)]);
+
+        // A verifier stating the game's public data expects exactly the instances of the proof.
+        assert_eq!(
+            scaffold::check_instances(&public.instances(), &instances),
+            Ok(())
+        );
+        let wrong = PublicInputs {
+            partial: 2,
+            ..public
+        };
+        assert!(scaffold::check_instances(&wrong.instances(), &instances).is_err());
+    }
+
+    #[test]
+    fn test_inconsistent_expected_response() {
+        let mut input = input([0, 3, 3, 5], [1, 3, 0, 5], GameVariant::CLASSIC);
+        assert_eq!(input.check_response(), Ok(()));
+        input.correct = Some(2);
+        input.partial = Some(1);
+        assert_eq!(input.check_response(), Ok(()));
+
+        input.correct = Some(3);
+        assert_eq!(
+            input.check_response(),
+            Err("the input expects 3 correct guesses, but the response has 2".to_string())
+        );
+        // The circuit does not panic on such an input: it outputs the actual response, which a verifier expecting
+        // the stated one rejects.
+        let public = input.public_inputs().unwrap();
+        let instances = mock(codebreaker_validate, input, K, Some(LOOKUP_BITS)).unwrap();
+        assert_eq!(instances[..2], [Fr::from(2), Fr::from(1)]);
+        assert!(scaffold::check_instances(&public.instances(), &instances).is_err());
+    }
+}
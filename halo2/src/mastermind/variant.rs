@@ -0,0 +1,158 @@
+//! Game variants supported by the Mastermind circuits, together with a native (out of circuit) reference scorer.
+
+use serde::{Deserialize, Serialize};
+
+use super::poseidon::HashScheme;
+
+/// How the response to a guess is computed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scoring {
+    /// Classic black/white pegs: the partial guesses are the colors shared by the code and the guess (counted with
+    /// multiplicity) minus the correct guesses.
+    #[default]
+    Classic,
+    /// Bulls and Cows (a.k.a. Bagels): both the code and the guess must be made of distinct digits, and the partial
+    /// guesses are the pairs (i, j), i != j, with code[i] == guess[j].
+    BullsAndCows,
+}
+
+/// The rules of the game. Every field has a default, so circuit inputs only need to mention the ones they change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameVariant {
+    /// Number of peg colors (6 for a standard game, 10 for Bulls and Cows).
+    pub colors: u64,
+    /// Whether the code may use the same color more than once.
+    pub duplicates: bool,
+    /// Whether pegs may be left blank. A blank is encoded as the extra color `colors`.
+    pub blanks: bool,
+    pub scoring: Scoring,
+    /// How the codemaker commits to the code. Both players must agree on it, like on the rules.
+    pub commitment: HashScheme,
+}
+
+impl Default for GameVariant {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+impl GameVariant {
+    pub const CLASSIC: GameVariant = GameVariant {
+        colors: 6,
+        duplicates: true,
+        blanks: false,
+        scoring: Scoring::Classic,
+        commitment: HashScheme::Circomlib,
+    };
+
+    pub const BULLS_AND_COWS: GameVariant = GameVariant {
+        colors: 10,
+        duplicates: false,
+        blanks: false,
+        scoring: Scoring::BullsAndCows,
+        commitment: HashScheme::Circomlib,
+    };
+
+    /// The number of legal peg values, including the blank if allowed.
+    pub fn num_colors(&self) -> u64 {
+        self.colors + self.blanks as u64
+    }
+
+    /// Whether the code must be made of distinct colors.
+    pub fn distinct_code(&self) -> bool {
+        !self.duplicates || self.scoring == Scoring::BullsAndCows
+    }
+
+    /// Whether the guess must be made of distinct colors.
+    pub fn distinct_guess(&self) -> bool {
+        self.scoring == Scoring::BullsAndCows
+    }
+
+    pub fn is_legal_code(&self, code: &[u64]) -> bool {
+        self.pegs_in_range(code) && (!self.distinct_code() || all_distinct(code))
+    }
+
+    pub fn is_legal_guess(&self, guess: &[u64]) -> bool {
+        self.pegs_in_range(guess) && (!self.distinct_guess() || all_distinct(guess))
+    }
+
+    /// Reference scorer. Returns (correct guesses, partial guesses) for a legal code and guess.
+    pub fn score(&self, code: &[u64], guess: &[u64]) -> (u64, u64) {
+        let correct = code.iter().zip(guess).filter(|(c, g)| c == g).count() as u64;
+        let partial = match self.scoring {
+            Scoring::Classic => {
+                let common: u64 = (0..self.num_colors())
+                    .map(|c| count(code, c).min(count(guess, c)))
+                    .sum();
+                common - correct
+            }
+            Scoring::BullsAndCows => code
+                .iter()
+                .enumerate()
+                .flat_map(|(i, c)| guess.iter().enumerate().map(move |(j, g)| (i, j, c, g)))
+                .filter(|(i, j, c, g)| i != j && c == g)
+                .count() as u64,
+        };
+        (correct, partial)
+    }
+
+    fn pegs_in_range(&self, pegs: &[u64]) -> bool {
+        pegs.iter().all(|p| *p < self.num_colors())
+    }
+}
+
+fn count(pegs: &[u64], color: u64) -> u64 {
+    pegs.iter().filter(|p| **p == color).count() as u64
+}
+
+fn all_distinct(pegs: &[u64]) -> bool {
+    (0..pegs.len()).all(|i| (i + 1..pegs.len()).all(|j| pegs[i] != pegs[j]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reference_scorer() {
+        assert_eq!(
+            GameVariant::CLASSIC.score(&[0, 3, 3, 5], &[1, 3, 0, 5]),
+            (2, 1)
+        );
+        assert_eq!(
+            GameVariant::CLASSIC.score(&[1, 1, 2, 2], &[2, 2, 1, 1]),
+            (0, 4)
+        );
+        assert_eq!(
+            GameVariant::BULLS_AND_COWS.score(&[9, 8, 7, 6], &[6, 1, 8, 0]),
+            (0, 2)
+        );
+
+        let no_dups = GameVariant {
+            duplicates: false,
+            ..GameVariant::CLASSIC
+        };
+        assert!(!no_dups.is_legal_code(&[0, 3, 3, 5]));
+        assert!(no_dups.is_legal_guess(&[3, 3, 3, 3]));
+        assert!(!GameVariant::BULLS_AND_COWS.is_legal_guess(&[3, 3, 1, 2]));
+    }
+
+    #[test]
+    fn test_deserialize_defaults() {
+        let v: GameVariant = serde_json::from_str(r#"{"blanks": true}"#).unwrap();
+        assert_eq!(
+            v,
+            GameVariant {
+                blanks: true,
+                ..GameVariant::CLASSIC
+            }
+        );
+        let v: GameVariant =
+            serde_json::from_str(r#"{"colors": 10, "scoring": "bulls_and_cows"}"#).unwrap();
+        assert_eq!(v.scoring, Scoring::BullsAndCows);
+        let v: GameVariant = serde_json::from_str(r#"{"commitment": "legacy"}"#).unwrap();
+        assert_eq!(v.commitment, HashScheme::Legacy);
+    }
+}
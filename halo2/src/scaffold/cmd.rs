@@ -42,4 +42,8 @@ pub struct Cli {
     pub config_path: Option<PathBuf>,
     #[arg(short, long = "data-path")]
     pub data_path: Option<PathBuf>,
+    /// Public instances the verifier expects, relative to `data/`: a JSON array of decimal field elements, or the
+    /// circuit's public data (e.g. `data/validate.public`). Only used by `verify`.
+    #[arg(long = "instances")]
+    pub instances_path: Option<PathBuf>,
 }
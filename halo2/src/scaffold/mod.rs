@@ -8,9 +8,9 @@ use halo2_base::{
         flex_gate::MultiPhaseThreadBreakPoints,
     },
     halo2_proofs::{
-        dev::MockProver,
+        dev::{MockProver, VerifyFailure},
         halo2curves::bn256::{Bn256, Fr, G1Affine},
-        plonk::{verify_proof, Circuit, ProvingKey, VerifyingKey},
+        plonk::{verify_proof, Error, ProvingKey, VerifyingKey},
         poly::{
             commitment::{Params, ParamsProver},
             kzg::{
@@ -21,19 +21,19 @@ use halo2_base::{
         },
         SerdeFormat,
     },
-    utils::fs::gen_srs,
+    utils::{fs::gen_srs, BigPrimeField},
     AssignedValue,
 };
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Deserialize};
 use snark_verifier_sdk::{
     gen_pk,
     halo2::{gen_snark_shplonk, read_snark, PoseidonTranscript},
-    read_pk, CircuitExt, NativeLoader,
+    read_pk, CircuitExt, NativeLoader, Snark,
 };
 use std::{
     env::var,
     fs::{self, File},
-    io::{BufReader, BufWriter},
+    io::{self, BufReader, BufWriter},
     path::{Path, PathBuf},
     time::Instant,
 };
@@ -42,6 +42,42 @@ use self::cmd::{Cli, SnarkCmd};
 
 pub mod cmd;
 
+/// The circuit parameters and break points recorded at keygen time, needed to rebuild the circuit for proving.
+pub type CircuitPinning = (BaseCircuitParams, MultiPhaseThreadBreakPoints);
+
+/// The public data of a circuit, from which a verifier computes the public instances they expect a proof to have,
+/// instead of trusting the ones the prover wrote into the snark.
+pub trait PublicInputs: DeserializeOwned {
+    /// The public instances, in the order the circuit exposes them.
+    fn instances(&self) -> Vec<Fr>;
+}
+
+/// Raw public instances: a JSON array of decimal field elements. Works for any circuit.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RawInstances(pub Vec<String>);
+
+impl PublicInputs for RawInstances {
+    fn instances(&self) -> Vec<Fr> {
+        self.0
+            .iter()
+            .map(|x| parse_fe::<Fr>(x).expect("Error deserializing instance"))
+            .collect()
+    }
+}
+
+/// Parses a field element from a decimal string.
+pub fn parse_fe<F: BigPrimeField>(s: &str) -> Option<F> {
+    F::from_str_vartime(s)
+}
+
+/// The contents of an `--instances` file: raw instances, or the public data of the circuit.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExpectedInstances<P> {
+    Raw(RawInstances),
+    Public(P),
+}
+
 pub struct CircuitScaffold<T, Fn> {
     f: Fn,
     private_inputs: T,
@@ -51,6 +87,18 @@ pub fn run<T: DeserializeOwned>(
     f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>),
     cli: Cli,
 ) {
+    run_with_public::<T, RawInstances>(f, cli)
+}
+
+/// Like [`run`], but `verify --instances` may also be given the public data `P` of the circuit.
+pub fn run_with_public<T: DeserializeOwned, P: PublicInputs>(
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>),
+    cli: Cli,
+) {
+    if let SnarkCmd::Verify = cli.command {
+        // Verification only depends on public data, so the private inputs are not even read.
+        return run_verify::<P>(cli);
+    }
     let name = &cli.name;
     let input_path = PathBuf::from("data").join(
         cli.input_path
@@ -62,10 +110,10 @@ pub fn run<T: DeserializeOwned>(
             .unwrap_or_else(|e| panic!("Input file not found at {input_path:?}. {e:?}")),
     )
     .expect("Input file should be a valid JSON file");
-    run_on_inputs(f, cli, private_inputs)
+    run_on_inputs::<T, P>(f, cli, private_inputs)
 }
 
-pub fn run_on_inputs<T: DeserializeOwned>(
+pub fn run_on_inputs<T: DeserializeOwned, P: PublicInputs>(
     f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>),
     cli: Cli,
     private_inputs: T,
@@ -82,12 +130,12 @@ pub fn run_on_inputs<T: DeserializeOwned>(
 
     let params = gen_srs(k);
     println!("Universal trusted setup (unsafe!) available at: params/kzg_bn254_{k}.srs");
+    let lookup_bits = lookup_bits_from_env(k);
     match cli.command {
         SnarkCmd::Mock => {
-            let circuit = precircuit.create_circuit(CircuitBuilderStage::Mock, None, &params);
-            MockProver::run(k, &circuit, circuit.instances())
-                .unwrap()
-                .assert_satisfied();
+            if let Err(failures) = mock(precircuit.f, precircuit.private_inputs, k, lookup_bits) {
+                panic!("Circuit is not satisfied: {failures:#?}");
+            }
         }
         SnarkCmd::Keygen => {
             let pk_path = data_path.join(PathBuf::from(format!("{name}.pk")));
@@ -95,13 +143,15 @@ pub fn run_on_inputs<T: DeserializeOwned>(
                 fs::remove_file(&pk_path).unwrap();
             }
             let pinning_path = config_path.join(PathBuf::from(format!("{name}.json")));
-            let circuit = precircuit.create_circuit(CircuitBuilderStage::Keygen, None, &params);
-            let pk = gen_pk(&params, &circuit, None);
-            let c_params = circuit.params();
-            let break_points = circuit.break_points();
+            let (pk, pinning) = keygen(
+                precircuit.f,
+                precircuit.private_inputs,
+                lookup_bits,
+                &params,
+            );
             let mut pinning_file = File::create(&pinning_path)
                 .unwrap_or_else(|_| panic!("Could not create file at {pinning_path:?}"));
-            serde_json::to_writer(&mut pinning_file, &(c_params, break_points))
+            serde_json::to_writer(&mut pinning_file, &pinning)
                 .expect("Could not write pinning file");
             let mut pk_file = BufWriter::new(
                 File::create(&pk_path)
@@ -122,78 +172,228 @@ pub fn run_on_inputs<T: DeserializeOwned>(
         }
         SnarkCmd::Prove => {
             let pinning_path = config_path.join(PathBuf::from(format!("{name}.json")));
-            let mut pinning_file = File::open(&pinning_path)
-                .unwrap_or_else(|_| panic!("Could not read file at {pinning_path:?}"));
-            let pinning: (BaseCircuitParams, MultiPhaseThreadBreakPoints) =
-                serde_json::from_reader(&mut pinning_file).expect("Could not read pinning file");
-            let circuit =
-                precircuit.create_circuit(CircuitBuilderStage::Prover, Some(pinning), &params);
+            let pinning = read_pinning(&pinning_path);
             let pk_path = data_path.join(PathBuf::from(format!("{name}.pk")));
-            let pk = custom_read_pk(pk_path, &circuit);
+            let pk = read_pk::<BaseCircuitBuilder<Fr>>(&pk_path, pinning.0.clone())
+                .unwrap_or_else(|e| panic!("Failed to open file: {pk_path:?}: {e:?}"));
             let snark_path = data_path.join(PathBuf::from(format!("{name}.snark")));
             if snark_path.exists() {
                 fs::remove_file(&snark_path).unwrap();
             }
             let start = Instant::now();
-            gen_snark_shplonk(&params, &pk, circuit, Some(&snark_path));
+            prove(
+                precircuit.f,
+                precircuit.private_inputs,
+                pinning,
+                &params,
+                &pk,
+                Some(&snark_path),
+            );
             let prover_time = start.elapsed();
             println!("Proving time: {:?}", prover_time);
             println!("Snark written to: {snark_path:?}");
         }
-        SnarkCmd::Verify => {
-            let vk_path = data_path.join(PathBuf::from(format!("{name}.vk")));
-            let mut circuit = precircuit.create_circuit(CircuitBuilderStage::Keygen, None, &params);
-            let vk = custom_read_vk(vk_path, &circuit);
-            let snark_path = data_path.join(PathBuf::from(format!("{name}.snark")));
-            let snark = read_snark(&snark_path)
-                .unwrap_or_else(|e| panic!("Snark not found at {snark_path:?}. {e:?}"));
-
-            let verifier_params = params.verifier_params();
-            let strategy = SingleStrategy::new(&params);
-            let mut transcript =
-                PoseidonTranscript::<NativeLoader, &[u8]>::new::<0>(&snark.proof[..]);
-            let instance = &snark.instances[0][..];
-            let start = Instant::now();
-            verify_proof::<
-                KZGCommitmentScheme<Bn256>,
-                VerifierSHPLONK<'_, Bn256>,
-                _,
-                _,
-                SingleStrategy<'_, Bn256>,
-            >(
-                verifier_params,
-                &vk,
-                strategy,
-                &[&[instance]],
-                &mut transcript,
-            )
-            .unwrap();
-            let verification_time = start.elapsed();
-            println!("Snark verified successfully in {:?}", verification_time);
-            circuit.clear();
+        SnarkCmd::Verify => verify_files::<P>(
+            &params,
+            &config_path,
+            &data_path,
+            &name,
+            cli.instances_path.as_deref(),
+        ),
+    }
+}
+
+/// Runs the `verify` command. It only reads the SRS, the verifying key and pinning written by `keygen`, the snark and
+/// the expected instances: the circuit's private inputs are not needed.
+pub fn run_verify<P: PublicInputs>(cli: Cli) {
+    let config_path = cli.config_path.unwrap_or_else(|| PathBuf::from("configs"));
+    let data_path = cli.data_path.unwrap_or_else(|| PathBuf::from("data"));
+    let params = gen_srs(cli.degree);
+    verify_files::<P>(
+        &params,
+        &config_path,
+        &data_path,
+        &cli.name,
+        cli.instances_path.as_deref(),
+    );
+}
+
+/// Verifies `data/{name}.snark` with the verifying key `data/{name}.vk` and pinning `configs/{name}.json`, against
+/// the expected instances if given.
+fn verify_files<P: PublicInputs>(
+    params: &ParamsKZG<Bn256>,
+    config_path: &Path,
+    data_path: &Path,
+    name: &str,
+    instances_path: Option<&Path>,
+) {
+    let vk_path = data_path.join(format!("{name}.vk"));
+    let pinning_path = config_path.join(format!("{name}.json"));
+    let vk = read_vk(&vk_path, &pinning_path)
+        .unwrap_or_else(|e| panic!("Failed to read the verifying key at {vk_path:?}: {e:?}"));
+    let snark_path = data_path.join(format!("{name}.snark"));
+    let snark = read_snark(&snark_path)
+        .unwrap_or_else(|e| panic!("Snark not found at {snark_path:?}. {e:?}"));
+
+    let instances = match instances_path {
+        Some(instances_path) => {
+            let instances_path = data_path.join(instances_path);
+            let expected = read_expected_instances::<P>(&instances_path);
+            if let Err(mismatch) = check_instances(&expected, &snark.instances[0]) {
+                panic!("The snark does not prove the expected instances: {mismatch}");
+            }
+            expected
         }
+        None => {
+            println!("No --instances given: verifying against the instances chosen by the prover");
+            snark.instances[0].clone()
+        }
+    };
+
+    let start = Instant::now();
+    verify(params, &vk, &instances, &snark.proof).unwrap();
+    let verification_time = start.elapsed();
+    println!("Snark verified successfully in {:?}", verification_time);
+}
+
+/// Reads `LOOKUP_BITS` from the environment. The range chip uses a lookup table with 2^LOOKUP_BITS rows, so this must
+/// be set for circuits that perform range checks.
+pub fn lookup_bits_from_env(k: u32) -> Option<usize> {
+    var("LOOKUP_BITS")
+        .map(|str| {
+            let lookup_bits = str.parse::<usize>().unwrap();
+            // we use a lookup table with 2^LOOKUP_BITS rows. Due to blinding factors, we need a little more than 2^LOOKUP_BITS rows total in our circuit
+            assert!(
+                lookup_bits < k as usize,
+                "LOOKUP_BITS needs to be less than DEGREE"
+            );
+            lookup_bits
+        })
+        .ok()
+}
+
+/// Runs the mock prover on the circuit defined by `f`. Returns the public instances if all constraints are
+/// satisfied, and the failing constraints otherwise.
+pub fn mock<T>(
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>),
+    private_inputs: T,
+    k: u32,
+    lookup_bits: Option<usize>,
+) -> Result<Vec<Fr>, Vec<VerifyFailure>> {
+    let precircuit = CircuitScaffold { f, private_inputs };
+    let circuit = precircuit.create_circuit(CircuitBuilderStage::Mock, None, lookup_bits, k);
+    let instances = circuit.instances();
+    MockProver::run(k, &circuit, instances.clone())
+        .unwrap()
+        .verify()?;
+    Ok(instances.into_iter().next().unwrap_or_default())
+}
+
+/// Generates the proving key for the circuit defined by `f`, together with the pinning needed to rebuild the circuit
+/// at proving time. The witness values in `private_inputs` do not affect the keys, only the shape of the circuit.
+pub fn keygen<T>(
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>),
+    private_inputs: T,
+    lookup_bits: Option<usize>,
+    params: &ParamsKZG<Bn256>,
+) -> (ProvingKey<G1Affine>, CircuitPinning) {
+    let precircuit = CircuitScaffold { f, private_inputs };
+    let circuit =
+        precircuit.create_circuit(CircuitBuilderStage::Keygen, None, lookup_bits, params.k());
+    let pk = gen_pk(params, &circuit, None);
+    let c_params = circuit.params();
+    let break_points = circuit.break_points();
+    (pk, (c_params, break_points))
+}
+
+/// Generates a SHPLONK proof for the circuit defined by `f` on the given inputs, optionally writing it to `path`.
+pub fn prove<T>(
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>),
+    private_inputs: T,
+    pinning: CircuitPinning,
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    path: Option<&Path>,
+) -> Snark {
+    let precircuit = CircuitScaffold { f, private_inputs };
+    let circuit =
+        precircuit.create_circuit(CircuitBuilderStage::Prover, Some(pinning), None, params.k());
+    gen_snark_shplonk(params, pk, circuit, path)
+}
+
+/// Verifies a SHPLONK proof against the verifying key and the given public instances (single instance column).
+pub fn verify(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    instances: &[Fr],
+    proof: &[u8],
+) -> Result<(), Error> {
+    let verifier_params = params.verifier_params();
+    let strategy = SingleStrategy::new(params);
+    let mut transcript = PoseidonTranscript::<NativeLoader, &[u8]>::new::<0>(proof);
+    verify_proof::<
+        KZGCommitmentScheme<Bn256>,
+        VerifierSHPLONK<'_, Bn256>,
+        _,
+        _,
+        SingleStrategy<'_, Bn256>,
+    >(
+        verifier_params,
+        vk,
+        strategy,
+        &[&[instances]],
+        &mut transcript,
+    )
+    .map(|_| ())
+}
+
+/// Reads the instances a verifier expects from a JSON file holding either raw instances or the public data `P`.
+pub fn read_expected_instances<P: PublicInputs>(path: &Path) -> Vec<Fr> {
+    let file =
+        File::open(path).unwrap_or_else(|e| panic!("Instances file not found at {path:?}. {e:?}"));
+    match serde_json::from_reader(BufReader::new(file))
+        .expect("Instances file should be a valid JSON file")
+    {
+        ExpectedInstances::<P>::Raw(raw) => raw.instances(),
+        ExpectedInstances::<P>::Public(public) => public.instances(),
     }
 }
 
-fn custom_read_pk<C, P>(fname: P, circuit: &C) -> ProvingKey<G1Affine>
-where
-    C: Circuit<Fr>,
-    P: AsRef<Path>,
-{
-    read_pk::<C>(fname.as_ref(), circuit.params())
-        .unwrap_or_else(|e| panic!("Failed to open file: {:?}: {e:?}", fname.as_ref()))
+/// Checks that the instances of a snark are the ones the verifier expects, describing the first difference if not.
+pub fn check_instances(expected: &[Fr], actual: &[Fr]) -> Result<(), String> {
+    if expected.len() != actual.len() {
+        return Err(format!(
+            "expected {} instances, found {}",
+            expected.len(),
+            actual.len()
+        ));
+    }
+    match expected.iter().zip(actual).position(|(e, a)| e != a) {
+        Some(i) => Err(format!(
+            "instance {i} is {:?}, expected {:?}",
+            actual[i], expected[i]
+        )),
+        None => Ok(()),
+    }
 }
 
-fn custom_read_vk<C, P>(fname: P, circuit: &C) -> VerifyingKey<G1Affine>
-where
-    C: Circuit<Fr>,
-    P: AsRef<Path>,
-{
-    let f = File::open(&fname)
-        .unwrap_or_else(|e| panic!("Failed to open file: {:?}: {e:?}", fname.as_ref()));
-    let mut bufreader = BufReader::new(f);
-    VerifyingKey::read::<_, C>(&mut bufreader, SerdeFormat::RawBytes, circuit.params())
-        .expect("Could not read vkey")
+/// Reads the pinning (circuit parameters and break points) written by the `keygen` command.
+pub fn read_pinning(pinning_path: &Path) -> CircuitPinning {
+    let mut pinning_file = File::open(pinning_path)
+        .unwrap_or_else(|_| panic!("Could not read file at {pinning_path:?}"));
+    serde_json::from_reader(&mut pinning_file).expect("Could not read pinning file")
+}
+
+/// Reads a verifying key written by `keygen`. The pinning provides the circuit parameters needed to deserialize it, so
+/// no circuit has to be built.
+pub fn read_vk(vk_path: &Path, pinning_path: &Path) -> io::Result<VerifyingKey<G1Affine>> {
+    let (circuit_params, _) = read_pinning(pinning_path);
+    let mut reader = BufReader::new(File::open(vk_path)?);
+    VerifyingKey::read::<_, BaseCircuitBuilder<Fr>>(
+        &mut reader,
+        SerdeFormat::RawBytes,
+        circuit_params,
+    )
 }
 
 impl<T, Fn> CircuitScaffold<T, Fn>
@@ -204,26 +404,17 @@ where
     fn create_circuit(
         self,
         stage: CircuitBuilderStage,
-        pinning: Option<(BaseCircuitParams, MultiPhaseThreadBreakPoints)>,
-        params: &ParamsKZG<Bn256>,
+        pinning: Option<CircuitPinning>,
+        lookup_bits: Option<usize>,
+        k: u32,
     ) -> BaseCircuitBuilder<Fr> {
         let mut builder = BaseCircuitBuilder::from_stage(stage);
         if let Some((params, break_points)) = pinning {
             builder.set_params(params);
             builder.set_break_points(break_points);
         } else {
-            let k = params.k() as usize;
-            // we use env var `LOOKUP_BITS` to determine whether to use `GateThreadBuilder` or `RangeCircuitBuilder`. The difference is that the latter creates a lookup table with 2^LOOKUP_BITS rows, while the former does not.
-            let lookup_bits: Option<usize> = var("LOOKUP_BITS")
-                .map(|str| {
-                    let lookup_bits = str.parse::<usize>().unwrap();
-                    // we use a lookup table with 2^LOOKUP_BITS rows. Due to blinding factors, we need a little more than 2^LOOKUP_BITS rows total in our circuit
-                    assert!(lookup_bits < k, "LOOKUP_BITS needs to be less than DEGREE");
-                    lookup_bits
-                })
-                .ok();
             // we initiate a "thread builder". This is what keeps track of the execution trace of our program. If not in proving mode, it also keeps track of the ZK constraints.
-            builder.set_k(k);
+            builder.set_k(k as usize);
             if let Some(lookup_bits) = lookup_bits {
                 builder.set_lookup_bits(lookup_bits);
             }
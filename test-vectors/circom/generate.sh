@@ -0,0 +1,22 @@
+#!/bin/sh
+# Generate the circom fixtures of the arkworks tests: the constraint system of
+# circom/mastermind.circom and its witness for circom/examples/input1.json.
+# Needs circom (2.2.2 in the CI) and node on the PATH.
+set -eu
+
+CIRCOMLIB_VERSION=2.0.5
+
+here=$(cd "$(dirname "$0")" && pwd)
+circuit="$here/../../circom"
+build=$(mktemp -d)
+trap 'rm -rf "$build"' EXIT
+
+cd "$circuit"
+if [ ! -d node_modules/circomlib ]; then
+    npm install --no-save "circomlib@$CIRCOMLIB_VERSION"
+fi
+circom mastermind.circom --r1cs --wasm -o "$build"
+node "$build/mastermind_js/generate_witness.js" "$build/mastermind_js/mastermind.wasm" \
+    examples/input1.json "$build/mastermind.wtns"
+cp "$build/mastermind.r1cs" "$build/mastermind.wtns" "$here/"
+echo "Fixtures written to $here"
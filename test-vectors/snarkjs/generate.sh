@@ -0,0 +1,29 @@
+#!/bin/sh
+# Prove the circom fixtures of test-vectors/circom with snarkjs itself, writing
+# its proof.json, verification_key.json and public.json to circom-mastermind/.
+# Needs the fixtures, see ../circom/generate.sh, and npx on the PATH.
+set -eu
+
+SNARKJS_VERSION=0.7.5
+
+here=$(cd "$(dirname "$0")" && pwd)
+fixtures="$here/../circom"
+out="$here/circom-mastermind"
+build=$(mktemp -d)
+trap 'rm -rf "$build"' EXIT
+
+snarkjs() {
+    npx --yes "snarkjs@$SNARKJS_VERSION" "$@"
+}
+
+mkdir -p "$out"
+cd "$build"
+snarkjs powersoftau new bn128 16 pot16_0000.ptau
+snarkjs powersoftau contribute pot16_0000.ptau pot16_0001.ptau --name=fixtures -e=fixtures
+snarkjs powersoftau prepare phase2 pot16_0001.ptau pot16_final.ptau
+snarkjs groth16 setup "$fixtures/mastermind.r1cs" pot16_final.ptau mastermind_0000.zkey
+snarkjs zkey contribute mastermind_0000.zkey mastermind.zkey --name=fixtures -e=fixtures
+snarkjs zkey export verificationkey mastermind.zkey "$out/verification_key.json"
+snarkjs groth16 prove mastermind.zkey "$fixtures/mastermind.wtns" "$out/proof.json" "$out/public.json"
+snarkjs groth16 verify "$out/verification_key.json" "$out/public.json" "$out/proof.json"
+echo "Fixtures written to $out"